@@ -383,6 +383,16 @@ fn serialization(c: &mut Criterion) {
                 }
             });
         });
+
+        let mut slice = vec![0; max_size];
+
+        group.bench_function(BenchmarkId::new("serialize_into_slice", &dataset.name), |b| {
+            b.iter(|| {
+                for bitmap in &dataset.bitmaps {
+                    black_box(bitmap.serialize_into_slice(&mut slice).unwrap());
+                }
+            });
+        });
     }
 
     group.finish();
@@ -420,6 +430,47 @@ fn deserialization(c: &mut Criterion) {
                 }
             });
         });
+
+        group.bench_function(BenchmarkId::new("deserialize_from_slice", &dataset.name), |b| {
+            b.iter(|| {
+                for buf in input.iter() {
+                    black_box(RoaringBitmap::deserialize_from_slice(buf.as_slice()).unwrap());
+                }
+            });
+        });
+    }
+
+    group.finish();
+}
+
+fn container_serialization(c: &mut Criterion) {
+    let mut group = c.benchmark_group("container_serialization");
+
+    let mut runs: RoaringBitmap = (0..1000).flat_map(|i| i * 100..i * 100 + 50).collect();
+    runs.run_optimize();
+    let containers = [
+        ("array", (0..16_000_000).step_by(8).collect::<RoaringBitmap>()),
+        ("bitmap", (0..16_000_000).step_by(2).collect::<RoaringBitmap>()),
+        ("run", runs),
+    ];
+
+    for (name, bitmap) in &containers {
+        let bytes = bitmap.serialize_to_vec();
+        let mut buf = Vec::with_capacity(bytes.len());
+
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+
+        group.bench_function(BenchmarkId::new("serialize_into", name), |b| {
+            b.iter(|| {
+                buf.clear();
+                bitmap.serialize_into(&mut buf).unwrap();
+                black_box(&buf);
+            });
+        });
+
+        group.bench_function(BenchmarkId::new("deserialize_from", name), |b| {
+            b.iter(|| black_box(RoaringBitmap::deserialize_from(bytes.as_slice()).unwrap()));
+        });
     }
 
     group.finish();
@@ -718,6 +769,7 @@ criterion_group!(
     is_empty,
     serialization,
     deserialization,
+    container_serialization,
     successive_and,
     successive_or
 );
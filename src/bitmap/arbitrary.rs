@@ -1,7 +1,7 @@
 #[cfg(test)]
 mod test {
     use crate::bitmap::container::Container;
-    use crate::bitmap::store::{ArrayStore, BitmapStore, Store};
+    use crate::bitmap::store::{ArrayStore, BitmapStore, RunStore, Store};
     use crate::RoaringBitmap;
     use proptest::bits::{BitSetLike, BitSetStrategy, SampledBitSetStrategy};
     use proptest::collection::{vec, SizeRange};
@@ -150,11 +150,41 @@ mod test {
         }
     }
 
+    impl Debug for RunStore {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            if self.run_amount() < 16 {
+                write!(f, "RunStore<{:?}>", self.as_slice())
+            } else {
+                write!(
+                    f,
+                    "RunStore<{:?} runs of {:?} values between {:?} and {:?}>",
+                    self.run_amount(),
+                    self.len(),
+                    self.min().unwrap(),
+                    self.max().unwrap()
+                )
+            }
+        }
+    }
+
+    impl RunStore {
+        pub fn arbitrary() -> impl Strategy<Value = RunStore> {
+            vec((any::<u16>(), 0..=u16::MAX / 64), 1..=64).prop_map(|ranges| {
+                let mut runs = RunStore::new();
+                for (start, len) in ranges {
+                    runs.insert_range(start..=start.saturating_add(len));
+                }
+                runs
+            })
+        }
+    }
+
     impl Debug for Store {
         fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
             match self {
                 Store::Array(a) => write!(f, "Store({:?})", a),
                 Store::Bitmap(b) => write!(f, "Store({:?})", b),
+                Store::Run(r) => write!(f, "Store({:?})", r),
             }
         }
     }
@@ -165,6 +195,7 @@ mod test {
                 ArrayStore::sampled(1..=4096, ..=u16::MAX as usize).prop_map(Store::Array),
                 BitmapStore::sampled(4097..u16::MAX as usize, ..=u16::MAX as usize)
                     .prop_map(Store::Bitmap),
+                RunStore::arbitrary().prop_map(Store::Run),
             ]
         }
     }
@@ -184,6 +215,7 @@ mod test {
 
     impl RoaringBitmap {
         prop_compose! {
+            /// Generates bitmaps of up to 16 containers of any kind.
             pub fn arbitrary()(bitmap in (0usize..=16).prop_flat_map(containers)) -> RoaringBitmap {
                 bitmap
             }
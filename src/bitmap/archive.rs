@@ -0,0 +1,437 @@
+use std::collections::btree_map::{BTreeMap, Entry};
+use std::convert::TryInto;
+use std::io;
+use std::ops::Range;
+
+use byteorder::{LittleEndian, WriteBytesExt};
+
+use crate::{DeserializeError, RoaringBitmap, RoaringBitmapView};
+
+/// The magic bytes starting and ending an archive.
+const ARCHIVE_MAGIC: &[u8; 4] = b"RBAR";
+const ARCHIVE_VERSION: u32 = 1;
+/// The magic bytes and the version.
+const HEADER_SIZE: usize = 8;
+/// The offset of the index, the number of entries and the magic bytes.
+const FOOTER_SIZE: usize = 20;
+
+/// Writes many bitmaps in a single archive, which can be read with a [`BitmapArchive`].
+///
+/// Each bitmap is stored under a unique key in [the standard Roaring on-disk format][format].
+/// The bitmaps are written as they are appended, an index of the keys sorted with the
+/// positions of their bitmaps is written at the end by [`BitmapArchiveWriter::finish`].
+///
+/// The archive is made of:
+/// - the magic bytes `RBAR` and the version of the format as a little-endian `u32`,
+/// - the bitmaps one after the other,
+/// - for each bitmap, in ascending order of key: the length of its key as a `u32`, the key,
+///   the position of the bitmap from the start of the archive and its length as `u64`s,
+/// - the position of the index and the number of bitmaps as `u64`s, then the magic bytes.
+///
+/// [format]: https://github.com/RoaringBitmap/RoaringFormatSpec
+///
+/// # Examples
+///
+/// ```rust
+/// use roaring::{BitmapArchive, BitmapArchiveWriter, RoaringBitmap};
+///
+/// let mut writer = BitmapArchiveWriter::new(Vec::new()).unwrap();
+/// writer.append("primes", &RoaringBitmap::from([2, 3, 5, 7])).unwrap();
+/// writer.append("evens", &(0..10).step_by(2).collect()).unwrap();
+/// let bytes = writer.finish().unwrap();
+///
+/// let archive = BitmapArchive::from_bytes(&bytes).unwrap();
+/// assert_eq!(archive.load("primes").unwrap(), Some(RoaringBitmap::from([2, 3, 5, 7])));
+/// assert!(archive.view("evens").unwrap().unwrap().contains(8));
+/// assert_eq!(archive.load("odds").unwrap(), None);
+/// ```
+pub struct BitmapArchiveWriter<W> {
+    writer: W,
+    position: u64,
+    index: BTreeMap<Vec<u8>, Range<u64>>,
+    /// Whether a bitmap was partially written, the archive can't be completed then.
+    poisoned: bool,
+}
+
+impl<W: io::Write> BitmapArchiveWriter<W> {
+    /// Creates an archive writer, writing the header of the archive to `writer`.
+    pub fn new(mut writer: W) -> io::Result<BitmapArchiveWriter<W>> {
+        writer.write_all(ARCHIVE_MAGIC)?;
+        writer.write_u32::<LittleEndian>(ARCHIVE_VERSION)?;
+        Ok(BitmapArchiveWriter {
+            writer,
+            position: HEADER_SIZE as u64,
+            index: BTreeMap::new(),
+            poisoned: false,
+        })
+    }
+
+    /// Writes `bitmap` to the archive under `key`.
+    ///
+    /// Returns an [`io::ErrorKind::InvalidInput`] error if a bitmap was already written under
+    /// the same key, without writing anything. When writing the bitmap fails, part of it may
+    /// have been written: the writer is poisoned and every later call to this method or to
+    /// [`BitmapArchiveWriter::finish`] fails.
+    pub fn append<K: AsRef<[u8]>>(&mut self, key: K, bitmap: &RoaringBitmap) -> io::Result<()> {
+        self.check_poisoned()?;
+        let key = key.as_ref();
+        if key.len() > u32::MAX as usize {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "archive key is too long"));
+        }
+        let entry = match self.index.entry(key.to_vec()) {
+            Entry::Vacant(entry) => entry,
+            Entry::Occupied(..) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "a bitmap is already archived under this key",
+                ))
+            }
+        };
+
+        // Only cleared once the whole bitmap is written
+        self.poisoned = true;
+        bitmap.serialize_into(&mut self.writer)?;
+        self.poisoned = false;
+
+        let end = self.position + bitmap.serialized_size() as u64;
+        entry.insert(self.position..end);
+        self.position = end;
+        Ok(())
+    }
+
+    /// Returns the number of bitmaps written so far.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Returns `true` if no bitmap was written yet.
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Writes the index and the footer of the archive, and returns the underlying writer.
+    /// The archive is incomplete until this method is called.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.check_poisoned()?;
+        let index_offset = self.position;
+        for (key, range) in &self.index {
+            self.writer.write_u32::<LittleEndian>(key.len() as u32)?;
+            self.writer.write_all(key)?;
+            self.writer.write_u64::<LittleEndian>(range.start)?;
+            self.writer.write_u64::<LittleEndian>(range.end - range.start)?;
+        }
+        self.writer.write_u64::<LittleEndian>(index_offset)?;
+        self.writer.write_u64::<LittleEndian>(self.index.len() as u64)?;
+        self.writer.write_all(ARCHIVE_MAGIC)?;
+        Ok(self.writer)
+    }
+
+    fn check_poisoned(&self) -> io::Result<()> {
+        if self.poisoned {
+            Err(io::Error::new(
+                io::ErrorKind::Other,
+                "a bitmap was partially written to the archive",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// A read-only archive of bitmaps borrowing the bytes written by a [`BitmapArchiveWriter`].
+///
+/// Only the index is read when the archive is opened, each bitmap is found with a binary
+/// search of its key and is either deserialized or viewed in place. Memory mapping the file
+/// of an archive lets the operating system load only the bitmaps used.
+///
+/// # Examples
+///
+/// ```rust
+/// use roaring::{BitmapArchive, BitmapArchiveWriter, RoaringBitmap};
+///
+/// let mut writer = BitmapArchiveWriter::new(Vec::new()).unwrap();
+/// for i in 0..100u32 {
+///     let bitmap: RoaringBitmap = (i..i * 1_000).collect();
+///     writer.append(i.to_be_bytes(), &bitmap).unwrap();
+/// }
+/// let bytes = writer.finish().unwrap();
+///
+/// let archive = BitmapArchive::from_bytes(&bytes).unwrap();
+/// assert_eq!(archive.len(), 100);
+/// assert_eq!(archive.load(42u32.to_be_bytes()).unwrap().unwrap().len(), 41_958);
+/// ```
+pub struct BitmapArchive<'a> {
+    bytes: &'a [u8],
+    /// The keys in ascending order with the positions of their bitmaps.
+    entries: Vec<(&'a [u8], Range<usize>)>,
+}
+
+impl<'a> BitmapArchive<'a> {
+    /// Reads the index of an archive written by a [`BitmapArchiveWriter`], which must span
+    /// all of `bytes`.
+    ///
+    /// The errors are located from the start of the archive, like the errors of the bitmaps
+    /// read with [`BitmapArchive::load`] and [`BitmapArchive::view`].
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<BitmapArchive<'a>, DeserializeError> {
+        let invalid = |offset, source: &str| DeserializeError::InvalidIndex {
+            offset: offset as u64,
+            source: source.into(),
+        };
+        let truncated = |offset| DeserializeError::Truncated { key: None, offset: offset as u64 };
+
+        if bytes.len() >= 4 && &bytes[..4] != ARCHIVE_MAGIC {
+            return Err(DeserializeError::UnknownCookie { cookie: read_u32(bytes, 0), offset: 0 });
+        }
+        if bytes.len() < HEADER_SIZE + FOOTER_SIZE {
+            return Err(truncated(bytes.len()));
+        }
+        if read_u32(bytes, 4) != ARCHIVE_VERSION {
+            return Err(DeserializeError::UnknownCookie { cookie: read_u32(bytes, 4), offset: 4 });
+        }
+        // An archive cut short has no magic bytes at its end
+        if &bytes[bytes.len() - 4..] != ARCHIVE_MAGIC {
+            return Err(truncated(bytes.len()));
+        }
+
+        let footer = bytes.len() - FOOTER_SIZE;
+        let index_offset = read_u64(bytes, footer);
+        let count = read_u64(bytes, footer + 8);
+        if index_offset < HEADER_SIZE as u64 || index_offset > footer as u64 {
+            return Err(invalid(footer, "archive index offset out of bounds"));
+        }
+        let index_offset = index_offset as usize;
+        // Each entry takes at least 20 bytes
+        if count > ((footer - index_offset) / 20) as u64 {
+            return Err(truncated(footer));
+        }
+
+        let mut entries: Vec<(&[u8], Range<usize>)> = Vec::with_capacity(count as usize);
+        let mut position = index_offset;
+        for _ in 0..count {
+            // The key length, the position and the length of the bitmap
+            if footer - position < 20 {
+                return Err(truncated(position));
+            }
+            let entry = position;
+            let key_len = read_u32(bytes, position) as usize;
+            if key_len > footer - position - 20 {
+                return Err(truncated(position));
+            }
+            let key = &bytes[position + 4..position + 4 + key_len];
+            position += 4 + key_len;
+            let start = read_u64(bytes, position);
+            let len = read_u64(bytes, position + 8);
+            position += 16;
+
+            // The bitmaps are between the header and the index
+            let end = index_offset as u64;
+            if start < HEADER_SIZE as u64 || start > end || len > end - start {
+                return Err(invalid(entry, "archived bitmap out of bounds"));
+            }
+            if entries.last().map_or(false, |(previous, _)| *previous >= key) {
+                return Err(invalid(entry, "archive keys are not sorted"));
+            }
+            let start = start as usize;
+            entries.push((key, start..start + len as usize));
+        }
+        if position != footer {
+            return Err(invalid(position, "archive index has trailing bytes"));
+        }
+
+        Ok(BitmapArchive { bytes, entries })
+    }
+
+    /// Returns the number of bitmaps in the archive.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the archive has no bitmaps.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterator over the keys of the archive, in ascending order.
+    pub fn keys(&self) -> impl Iterator<Item = &'a [u8]> + '_ {
+        self.entries.iter().map(|(key, _)| *key)
+    }
+
+    /// Returns `true` if a bitmap is archived under `key`.
+    pub fn contains_key<K: AsRef<[u8]>>(&self, key: K) -> bool {
+        self.find(key.as_ref()).is_some()
+    }
+
+    /// Returns the bitmap archived under `key`, in the standard Roaring on-disk format.
+    pub fn get_bytes<K: AsRef<[u8]>>(&self, key: K) -> Option<&'a [u8]> {
+        self.find(key.as_ref()).map(|range| &self.bytes[range])
+    }
+
+    /// Deserializes the bitmap archived under `key` with [`RoaringBitmap::deserialize_from`].
+    ///
+    /// The errors are located from the start of the archive.
+    pub fn load<K: AsRef<[u8]>>(&self, key: K) -> Result<Option<RoaringBitmap>, DeserializeError> {
+        self.find(key.as_ref())
+            .map(|range| {
+                let start = range.start as u64;
+                RoaringBitmap::deserialize_checked(&self.bytes[range]).map_err(|e| e.shifted(start))
+            })
+            .transpose()
+    }
+
+    /// Views the bitmap archived under `key` in place with [`RoaringBitmapView::from_bytes`].
+    ///
+    /// The errors are located from the start of the archive.
+    pub fn view<K: AsRef<[u8]>>(
+        &self,
+        key: K,
+    ) -> Result<Option<RoaringBitmapView<'a>>, DeserializeError> {
+        self.find(key.as_ref())
+            .map(|range| {
+                let start = range.start as u64;
+                RoaringBitmapView::from_bytes(&self.bytes[range]).map_err(|e| e.shifted(start))
+            })
+            .transpose()
+    }
+
+    fn find(&self, key: &[u8]) -> Option<Range<usize>> {
+        let loc = self.entries.binary_search_by(|(k, _)| (*k).cmp(key)).ok()?;
+        Some(self.entries[loc].1.clone())
+    }
+}
+
+fn read_u32(bytes: &[u8], position: usize) -> u32 {
+    u32::from_le_bytes(bytes[position..position + 4].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], position: usize) -> u64 {
+    u64::from_le_bytes(bytes[position..position + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BitmapArchive, BitmapArchiveWriter};
+    use crate::{DeserializeError, RoaringBitmap};
+    use proptest::prelude::*;
+    use std::io;
+
+    proptest! {
+        #[test]
+        fn archive_roundtrip(
+            bitmaps in proptest::collection::btree_map(
+                proptest::collection::vec(any::<u8>(), 0..8),
+                RoaringBitmap::arbitrary(),
+                0..8,
+            )
+        ) {
+            let mut writer = BitmapArchiveWriter::new(Vec::new()).unwrap();
+            // Append in reverse order, the index is sorted anyway
+            for (key, bitmap) in bitmaps.iter().rev() {
+                writer.append(key, bitmap).unwrap();
+            }
+            prop_assert_eq!(writer.len(), bitmaps.len());
+            let bytes = writer.finish().unwrap();
+
+            let archive = BitmapArchive::from_bytes(&bytes).unwrap();
+            prop_assert_eq!(archive.len(), bitmaps.len());
+            prop_assert!(archive.keys().eq(bitmaps.keys().map(|key| &key[..])));
+            for (key, bitmap) in &bitmaps {
+                prop_assert_eq!(&archive.load(key).unwrap().unwrap(), bitmap);
+                prop_assert_eq!(&RoaringBitmap::from(&archive.view(key).unwrap().unwrap()), bitmap);
+            }
+            prop_assert!(!archive.contains_key([0xff; 9]));
+        }
+    }
+
+    #[test]
+    fn archive_errors() {
+        let mut writer = BitmapArchiveWriter::new(Vec::new()).unwrap();
+        writer.append("a", &RoaringBitmap::from([1, 2, 3])).unwrap();
+        assert!(writer.append("a", &RoaringBitmap::new()).is_err());
+        writer.append("b", &RoaringBitmap::new()).unwrap();
+        let bytes = writer.finish().unwrap();
+        assert!(BitmapArchive::from_bytes(&bytes).is_ok());
+
+        for len in 0..bytes.len() {
+            let result = BitmapArchive::from_bytes(&bytes[..len]);
+            assert!(matches!(result, Err(DeserializeError::Truncated { .. })));
+        }
+        let empty = BitmapArchiveWriter::new(Vec::new()).unwrap().finish().unwrap();
+        assert_eq!(BitmapArchive::from_bytes(&empty).unwrap().len(), 0);
+
+        let mut corrupted = bytes.clone();
+        corrupted[0] = b'X';
+        let result = BitmapArchive::from_bytes(&corrupted);
+        assert!(matches!(result, Err(DeserializeError::UnknownCookie { offset: 0, .. })));
+
+        // The length of the first bitmap, after its key length, key and position
+        let index_offset = bytes.len() - 20 - 2 * 21;
+        let mut corrupted = bytes.clone();
+        corrupted[index_offset + 13] = 0xff;
+        match BitmapArchive::from_bytes(&corrupted) {
+            Err(DeserializeError::InvalidIndex { offset, .. }) => {
+                assert_eq!(offset, index_offset as u64)
+            }
+            result => panic!("unexpected result {:?}", result.map(|archive| archive.len())),
+        }
+
+        // The keys swapped
+        let mut corrupted = bytes.clone();
+        corrupted[index_offset + 4] = b'b';
+        corrupted[index_offset + 25] = b'a';
+        match BitmapArchive::from_bytes(&corrupted) {
+            Err(DeserializeError::InvalidIndex { offset, .. }) => {
+                assert_eq!(offset, index_offset as u64 + 21)
+            }
+            result => panic!("unexpected result {:?}", result.map(|archive| archive.len())),
+        }
+
+        // The errors of the bitmaps are located from the start of the archive
+        let mut corrupted = bytes;
+        corrupted[8] = 0;
+        let archive = BitmapArchive::from_bytes(&corrupted).unwrap();
+        match archive.load("a") {
+            Err(DeserializeError::UnknownCookie { offset: 8, .. }) => (),
+            result => panic!("unexpected result {:?}", result),
+        }
+        assert!(matches!(
+            archive.view("a"),
+            Err(DeserializeError::UnknownCookie { offset: 8, .. })
+        ));
+    }
+
+    #[test]
+    fn archive_poisoned() {
+        /// A writer failing once `capacity` bytes are written.
+        struct Limited {
+            bytes: Vec<u8>,
+            capacity: usize,
+        }
+
+        impl io::Write for Limited {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                let len = buf.len().min(self.capacity - self.bytes.len());
+                self.bytes.extend_from_slice(&buf[..len]);
+                Ok(len)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        // The bitmap takes 22 bytes, after the 8 bytes of the header
+        let limited = Limited { bytes: Vec::new(), capacity: 20 };
+        let mut writer = BitmapArchiveWriter::new(limited).unwrap();
+        assert!(writer.append("a", &RoaringBitmap::from([1, 2, 3])).is_err());
+        assert!(writer.append("b", &RoaringBitmap::new()).is_err());
+        assert!(writer.finish().is_err());
+
+        // A rejected key doesn't poison the writer
+        let limited = Limited { bytes: Vec::new(), capacity: 1024 };
+        let mut writer = BitmapArchiveWriter::new(limited).unwrap();
+        writer.append("a", &RoaringBitmap::new()).unwrap();
+        assert!(writer.append("a", &RoaringBitmap::new()).is_err());
+        writer.append("b", &RoaringBitmap::new()).unwrap();
+        assert!(writer.finish().is_ok());
+    }
+}
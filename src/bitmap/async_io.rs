@@ -0,0 +1,249 @@
+use std::convert::TryFrom;
+use std::io;
+
+use futures_util::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::container::Container;
+use super::serialization::{
+    read_store, serialize_header_into, serialize_store_into, Budget, CountingReader, Descriptor,
+    Header, NO_OFFSET_THRESHOLD, SERIAL_COOKIE, SERIAL_COOKIE_NO_RUNCONTAINER,
+};
+use super::store::{ArrayStore, BitmapStore, RunStore, Store};
+use crate::{DeserializeError, DeserializeOptions, Limit, RoaringBitmap};
+
+impl RoaringBitmap {
+    /// Serialize this bitmap into [the standard Roaring on-disk format][format] like
+    /// [RoaringBitmap::serialize_into], writing it to an asynchronous writer.
+    ///
+    /// The header and then each container are written in turn, only one of them is buffered.
+    ///
+    /// [format]: https://github.com/RoaringBitmap/RoaringFormatSpec
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringBitmap;
+    ///
+    /// futures_executor::block_on(async {
+    ///     let rb1: RoaringBitmap = (1..4).collect();
+    ///     let mut bytes = vec![];
+    ///     rb1.serialize_into_async(&mut bytes).await.unwrap();
+    ///     let rb2 = RoaringBitmap::deserialize_from_async(&bytes[..]).await.unwrap();
+    ///
+    ///     assert_eq!(rb1, rb2);
+    /// });
+    /// ```
+    pub async fn serialize_into_async<W: AsyncWrite + Unpin>(
+        &self,
+        mut writer: W,
+    ) -> io::Result<()> {
+        // The header and then each container are written through the same buffer
+        let mut bytes = Vec::new();
+        serialize_header_into(self.containers.iter().map(Descriptor::of), &mut bytes)?;
+        writer.write_all(&bytes).await?;
+
+        for container in &self.containers {
+            bytes.clear();
+            serialize_store_into(&container.store, &mut bytes)?;
+            writer.write_all(&bytes).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Deserialize a bitmap into memory from [the standard Roaring on-disk format][format] with
+    /// the checks of [RoaringBitmap::deserialize_from], reading it from an asynchronous reader.
+    ///
+    /// Only the bytes of the bitmap are read, the reader is left at the end of it. The header
+    /// and then each container are read in turn, only one of them is buffered.
+    ///
+    /// [format]: https://github.com/RoaringBitmap/RoaringFormatSpec
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringBitmap;
+    ///
+    /// futures_executor::block_on(async {
+    ///     let rb1: RoaringBitmap = (1..4).collect();
+    ///     let mut bytes = rb1.serialize_to_vec();
+    ///     bytes.extend_from_slice(b"trailer");
+    ///
+    ///     let mut reader = &bytes[..];
+    ///     let rb2 = RoaringBitmap::deserialize_from_async(&mut reader).await.unwrap();
+    ///
+    ///     assert_eq!(rb1, rb2);
+    ///     assert_eq!(reader, b"trailer");
+    /// });
+    /// ```
+    pub async fn deserialize_from_async<R: AsyncRead + Unpin>(
+        reader: R,
+    ) -> Result<RoaringBitmap, DeserializeError> {
+        RoaringBitmap::deserialize_from_async_with_options(reader, &DeserializeOptions::new()).await
+    }
+
+    /// Deserialize a bitmap like [RoaringBitmap::deserialize_from_async], without exceeding the
+    /// limits of `options`.
+    ///
+    /// The limits are checked like [RoaringBitmap::deserialize_from_with_options] does, before
+    /// reading each part of the input into memory.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{DeserializeError, DeserializeOptions, Limit, RoaringBitmap};
+    ///
+    /// futures_executor::block_on(async {
+    ///     let rb1: RoaringBitmap = (1..4).collect();
+    ///     let bytes = rb1.serialize_to_vec();
+    ///
+    ///     let options = DeserializeOptions::new().max_bytes(bytes.len() as u64);
+    ///     let rb2 = RoaringBitmap::deserialize_from_async_with_options(&bytes[..], &options);
+    ///     assert_eq!(rb2.await.unwrap(), rb1);
+    ///
+    ///     let options = DeserializeOptions::new().max_bytes(bytes.len() as u64 - 1);
+    ///     match RoaringBitmap::deserialize_from_async_with_options(&bytes[..], &options).await {
+    ///         Err(DeserializeError::LimitExceeded { limit: Limit::Bytes, .. }) => (),
+    ///         _ => panic!("the limit is not exceeded"),
+    ///     }
+    /// });
+    /// ```
+    pub async fn deserialize_from_async_with_options<R: AsyncRead + Unpin>(
+        mut reader: R,
+        options: &DeserializeOptions,
+    ) -> Result<RoaringBitmap, DeserializeError> {
+        let (bitmap, _) = deserialize_checked_async(&mut reader, &mut Budget::new(options)).await?;
+        Ok(bitmap)
+    }
+}
+
+/// Reads a bitmap in the portable format with the checks of [RoaringBitmap::deserialize_from],
+/// buffering only the header and then the data of one container at a time.
+///
+/// The limits of `budget` are checked before each part of the input is read, and consumed
+/// when it is parsed. Returns the bitmap with the number of bytes read, the offsets of the
+/// errors are relative to the start of the bitmap.
+pub(crate) async fn deserialize_checked_async<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    budget: &mut Budget,
+) -> Result<(RoaringBitmap, u64), DeserializeError> {
+    let mut bytes = Vec::new();
+
+    // The header is parsed like a synchronous input once all of its bytes are read
+    read_header(reader, &mut bytes, budget).await?;
+    let header = Header::read_from(&mut CountingReader { inner: &bytes[..], offset: 0 }, budget)?;
+    let mut offset = bytes.len() as u64;
+
+    let mut containers: Vec<Container> = Vec::with_capacity(header.size());
+    for i in 0..header.size() {
+        let key = header.key(i);
+        let len = header.len(i);
+
+        // The binary searches rely on the keys being strictly increasing
+        if containers.last().map_or(false, |previous| previous.key >= key) {
+            let offset = header.description_offset(i);
+            return Err(DeserializeError::UnsortedKeys { key, offset });
+        }
+
+        bytes.clear();
+        if header.is_run(i) {
+            budget.check(Limit::Bytes, 2, offset)?;
+            read_bytes(reader, &mut bytes, 2, Some(key), offset).await?;
+            let runs = usize::from(u16::from_le_bytes(last_bytes(&bytes)));
+            budget.check(Limit::Bytes, 2 + runs as u64 * 4, offset + 2)?;
+            read_bytes(reader, &mut bytes, runs * 4, Some(key), offset).await?;
+        } else {
+            let size = if len <= 4096 { len as usize * 2 } else { 8 * 1024 };
+            budget.check(Limit::Bytes, size as u64, offset)?;
+            read_bytes(reader, &mut bytes, size, Some(key), offset).await?;
+        }
+
+        let mut data = CountingReader { inner: &bytes[..], offset };
+        let store = read_store(
+            &mut data,
+            key,
+            len,
+            header.is_run(i),
+            Store::default(),
+            budget,
+            (&ArrayStore::try_from, &BitmapStore::try_from, &RunStore::try_from_len),
+        )?;
+        offset = data.offset;
+        containers.push(Container { key, store });
+    }
+
+    Ok((RoaringBitmap { containers }, offset))
+}
+
+/// Reads the bytes of the header of a bitmap in the portable format, up to the data of its
+/// containers, and appends them to `bytes`.
+///
+/// The limits of `budget` are checked before reading each part of the header like
+/// [`Header::read_from`] consumes them, with the bytes read so far as none is consumed yet.
+async fn read_header<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    bytes: &mut Vec<u8>,
+    budget: &Budget,
+) -> Result<(), DeserializeError> {
+    let check = |bytes: &[u8], len: usize, offset| {
+        budget.check(Limit::Bytes, (bytes.len() + len) as u64, offset)
+    };
+
+    check(bytes, 4, 0)?;
+    read_bytes(reader, bytes, 4, None, 0).await?;
+    let cookie = u32::from_le_bytes(last_bytes(bytes));
+    let (size, size_offset, has_offsets, has_run_containers) =
+        if cookie == SERIAL_COOKIE_NO_RUNCONTAINER {
+            check(bytes, 4, 4)?;
+            read_bytes(reader, bytes, 4, None, 4).await?;
+            let size = u32::from_le_bytes(last_bytes(bytes));
+            (size as usize, 4, true, false)
+        } else if (cookie as u16) == SERIAL_COOKIE {
+            let size = ((cookie >> 16) + 1) as usize;
+            (size, 0, size >= NO_OFFSET_THRESHOLD, true)
+        } else {
+            return Err(DeserializeError::UnknownCookie { cookie, offset: 0 });
+        };
+
+    // Checked before reading the rest of the header, whose size it declares
+    if size > u16::MAX as usize + 1 {
+        return Err(DeserializeError::TooManyContainers {
+            count: size as u64,
+            offset: size_offset,
+        });
+    }
+    budget.check(Limit::Containers, size as u64, size_offset)?;
+
+    // The run bitset, the descriptions and the offsets
+    let run_bytes = if has_run_containers { (size + 7) / 8 } else { 0 };
+    let offsets_bytes = if has_offsets { size * 4 } else { 0 };
+    for len in [run_bytes, size * 4, offsets_bytes] {
+        let offset = bytes.len() as u64;
+        check(bytes, len, offset)?;
+        read_bytes(reader, bytes, len, None, offset).await?;
+    }
+    Ok(())
+}
+
+/// Reads exactly `len` more bytes at the end of `bytes`.
+pub(crate) async fn read_bytes<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    bytes: &mut Vec<u8>,
+    len: usize,
+    key: Option<u16>,
+    offset: u64,
+) -> Result<(), DeserializeError> {
+    let end = bytes.len();
+    bytes.resize(end + len, 0);
+    reader
+        .read_exact(&mut bytes[end..])
+        .await
+        .map_err(|e| DeserializeError::from_io(e, key, offset))
+}
+
+/// Returns the `N` last bytes of `bytes`.
+pub(crate) fn last_bytes<const N: usize>(bytes: &[u8]) -> [u8; N] {
+    let mut array = [0; N];
+    array.copy_from_slice(&bytes[bytes.len() - N..]);
+    array
+}
@@ -0,0 +1,206 @@
+use std::io::{self, Read};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use crc32fast::Hasher;
+
+use crate::{DeserializeError, RoaringBitmap};
+
+/// The size of the length that precedes the bitmap.
+const LENGTH_SIZE: u64 = 8;
+
+impl RoaringBitmap {
+    /// Serialize this bitmap in [the standard Roaring on-disk format][format], framed by its
+    /// length and a checksum so that [RoaringBitmap::deserialize_with_checksum_from] detects
+    /// corrupted bytes.
+    ///
+    /// The bitmap is preceded by its length as a little-endian `u64` and followed by the
+    /// CRC-32 (IEEE) of the length and the bitmap as a little-endian `u32`.
+    ///
+    /// [format]: https://github.com/RoaringBitmap/RoaringFormatSpec
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringBitmap;
+    ///
+    /// let rb1: RoaringBitmap = (1..4).collect();
+    /// let mut bytes = vec![];
+    /// rb1.serialize_with_checksum_into(&mut bytes).unwrap();
+    /// let rb2 = RoaringBitmap::deserialize_with_checksum_from(&bytes[..]).unwrap();
+    ///
+    /// assert_eq!(rb1, rb2);
+    /// ```
+    pub fn serialize_with_checksum_into<W: io::Write>(&self, writer: W) -> io::Result<()> {
+        write_with_checksum(writer, self.serialized_size() as u64, |w| self.serialize_into(w))
+    }
+
+    /// Deserialize a bitmap written by [RoaringBitmap::serialize_with_checksum_into].
+    ///
+    /// The whole bitmap is read and its checksum verified before it is deserialized with the
+    /// checks of [RoaringBitmap::deserialize_from], corrupted bytes are reported as a
+    /// [`DeserializeError::ChecksumMismatch`]. The offsets of the errors that follow the
+    /// verification are from the start of the bitmap, after its length.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{DeserializeError, RoaringBitmap};
+    ///
+    /// let rb: RoaringBitmap = (1..4).collect();
+    /// let mut bytes = vec![];
+    /// rb.serialize_with_checksum_into(&mut bytes).unwrap();
+    ///
+    /// bytes[12] ^= 1;
+    /// match RoaringBitmap::deserialize_with_checksum_from(&bytes[..]) {
+    ///     Err(DeserializeError::ChecksumMismatch { .. }) => (),
+    ///     _ => panic!("the corruption is not detected"),
+    /// }
+    /// ```
+    pub fn deserialize_with_checksum_from<R: io::Read>(
+        reader: R,
+    ) -> Result<RoaringBitmap, DeserializeError> {
+        let bytes = read_with_checksum(reader)?;
+        let (bitmap, read) = RoaringBitmap::deserialize_from_slice(&bytes)?;
+        check_length(&bytes, read)?;
+        Ok(bitmap)
+    }
+}
+
+/// Writes the length, the bytes written by `serialize` and the checksum of both.
+pub(crate) fn write_with_checksum<W, F>(writer: W, len: u64, serialize: F) -> io::Result<()>
+where
+    W: io::Write,
+    F: FnOnce(&mut ChecksumWriter<W>) -> io::Result<()>,
+{
+    let mut writer = ChecksumWriter { inner: writer, hasher: Hasher::new() };
+    writer.write_u64::<LittleEndian>(len)?;
+    serialize(&mut writer)?;
+    let checksum = writer.hasher.finalize();
+    writer.inner.write_u32::<LittleEndian>(checksum)
+}
+
+/// Reads the bytes framed by a length and a checksum, and verifies the checksum.
+pub(crate) fn read_with_checksum<R: io::Read>(mut reader: R) -> Result<Vec<u8>, DeserializeError> {
+    let mut hasher = Hasher::new();
+    let mut length = [0; LENGTH_SIZE as usize];
+    reader.read_exact(&mut length).map_err(|e| DeserializeError::from_io(e, None, 0))?;
+    hasher.update(&length);
+    let len = u64::from_le_bytes(length);
+
+    // The length isn't trusted yet, the buffer only grows with the bytes actually read
+    let mut bytes = Vec::new();
+    reader
+        .by_ref()
+        .take(len)
+        .read_to_end(&mut bytes)
+        .map_err(|e| DeserializeError::from_io(e, None, LENGTH_SIZE))?;
+    if (bytes.len() as u64) < len {
+        return Err(DeserializeError::Truncated { key: None, offset: LENGTH_SIZE + len });
+    }
+    hasher.update(&bytes);
+
+    let offset = LENGTH_SIZE + len;
+    let expected = reader
+        .read_u32::<LittleEndian>()
+        .map_err(|e| DeserializeError::from_io(e, None, offset))?;
+    let actual = hasher.finalize();
+    if expected != actual {
+        return Err(DeserializeError::ChecksumMismatch { expected, actual, offset });
+    }
+    Ok(bytes)
+}
+
+/// Checks that the bitmap deserialized from `bytes` spans all of them.
+pub(crate) fn check_length(bytes: &[u8], read: usize) -> Result<(), DeserializeError> {
+    if read == bytes.len() {
+        Ok(())
+    } else {
+        Err(DeserializeError::LengthMismatch {
+            expected: bytes.len() as u64,
+            actual: read as u64,
+            offset: 0,
+        })
+    }
+}
+
+/// A writer that computes the checksum of the bytes written through it.
+pub(crate) struct ChecksumWriter<W> {
+    inner: W,
+    hasher: Hasher,
+}
+
+impl<W: io::Write> io::Write for ChecksumWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{DeserializeError, RoaringBitmap};
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn checksum_roundtrip(mut bitmap in RoaringBitmap::arbitrary(), optimize in any::<bool>()) {
+            if optimize {
+                bitmap.run_optimize();
+            }
+            let mut bytes = Vec::new();
+            bitmap.serialize_with_checksum_into(&mut bytes).unwrap();
+            prop_assert_eq!(bytes.len(), bitmap.serialized_size() + 12);
+            prop_assert_eq!(&bytes[8..bytes.len() - 4], &bitmap.serialize_to_vec()[..]);
+            prop_assert_eq!(RoaringBitmap::deserialize_with_checksum_from(&bytes[..]).unwrap(), bitmap);
+        }
+    }
+
+    #[test]
+    fn checksum_detects_corruption() {
+        let bitmap: RoaringBitmap = (0..10).chain(100_000..100_010).collect();
+        let mut bytes = Vec::new();
+        bitmap.serialize_with_checksum_into(&mut bytes).unwrap();
+
+        for i in 0..bytes.len() {
+            let mut corrupted = bytes.clone();
+            corrupted[i] ^= 0x10;
+            let result = RoaringBitmap::deserialize_with_checksum_from(&corrupted[..]);
+            match result {
+                // A corrupted length moves the checksum, possibly past the end
+                Err(DeserializeError::ChecksumMismatch { offset, .. }) => {
+                    assert!(i < 8 || offset == bytes.len() as u64 - 4)
+                }
+                Err(DeserializeError::Truncated { .. }) => assert!(i < 8),
+                result => panic!("unexpected result {:?} for byte {}", result, i),
+            }
+        }
+
+        for len in 0..bytes.len() {
+            let result = RoaringBitmap::deserialize_with_checksum_from(&bytes[..len]);
+            assert!(matches!(result, Err(DeserializeError::Truncated { .. })));
+        }
+    }
+
+    #[test]
+    fn checksum_length_mismatch() {
+        let bitmap = RoaringBitmap::from([1, 2, 3]);
+        let mut bytes = Vec::new();
+        super::write_with_checksum(&mut bytes, bitmap.serialized_size() as u64 + 1, |w| {
+            bitmap.serialize_into(&mut *w)?;
+            std::io::Write::write_all(w, &[0])
+        })
+        .unwrap();
+
+        match RoaringBitmap::deserialize_with_checksum_from(&bytes[..]) {
+            Err(DeserializeError::LengthMismatch { expected, actual, .. }) => {
+                assert_eq!(expected, actual + 1)
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+}
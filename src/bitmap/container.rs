@@ -3,17 +3,51 @@ use std::ops::{
     BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, RangeInclusive, Sub, SubAssign,
 };
 
-use super::store::{self, Store};
+use super::store::{self, ArrayStore, BitmapStore, RunStore, Store};
 use super::util;
 
-const ARRAY_LIMIT: u64 = 4096;
+pub const ARRAY_LIMIT: u64 = 4096;
 
-#[derive(PartialEq, Clone)]
+#[derive(PartialEq)]
 pub struct Container {
     pub key: u16,
     pub store: Store,
 }
 
+/// Returns the container at `index` after making it the one of `key`, to replace a list of
+/// containers in place: the containers before `index` are the new ones, the ones from `index`
+/// on are the previous ones, sorted by key.
+///
+/// The previous containers with smaller keys are dropped and the one with the same key is
+/// returned to reuse its allocations, an empty container is inserted if there is none.
+pub(crate) fn replace_at(
+    containers: &mut Vec<Container>,
+    index: usize,
+    key: u16,
+) -> &mut Container {
+    match containers[index..].binary_search_by_key(&key, |c| c.key) {
+        Ok(position) => {
+            containers.drain(index..index + position);
+        }
+        Err(position) => {
+            containers.drain(index..index + position);
+            containers.insert(index, Container::new(key));
+        }
+    }
+    &mut containers[index]
+}
+
+impl Clone for Container {
+    fn clone(&self) -> Self {
+        Container { key: self.key, store: self.store.clone() }
+    }
+
+    fn clone_from(&mut self, other: &Self) {
+        self.key = other.key;
+        self.store.clone_from(&other.store);
+    }
+}
+
 pub struct Iter<'a> {
     pub key: u16,
     inner: store::Iter<'a>,
@@ -130,6 +164,50 @@ impl Container {
         self.store.rank(index)
     }
 
+    /// Converts the store to its smallest representation, run containers included.
+    ///
+    /// Returns whether the store is a run container afterwards.
+    pub fn optimize(&mut self) -> bool {
+        match &self.store {
+            Store::Array(ref vec) => {
+                let run_size = RunStore::serialized_byte_size(vec.run_amount() as usize);
+                if run_size < ArrayStore::serialized_byte_size(vec.len() as usize) {
+                    self.store = Store::Run(RunStore::from(vec));
+                }
+            }
+            Store::Bitmap(ref bits) => {
+                let run_size = RunStore::serialized_byte_size(bits.run_amount() as usize);
+                if run_size < BitmapStore::serialized_byte_size() {
+                    self.store = Store::Run(RunStore::from(bits));
+                }
+            }
+            Store::Run(..) => self.ensure_correct_store(),
+        }
+        matches!(self.store, Store::Run(..))
+    }
+
+    /// Converts a run container back to an array or a bitmap depending on its cardinality.
+    ///
+    /// Returns whether the store was a run container.
+    pub fn remove_run_compression(&mut self) -> bool {
+        match &self.store {
+            Store::Run(ref runs) => {
+                self.store = if runs.len() <= ARRAY_LIMIT {
+                    Store::Array(runs.to_array_store())
+                } else {
+                    Store::Bitmap(runs.to_bitmap_store())
+                };
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Converts the store to the kind that fits its cardinality.
+    ///
+    /// Arrays and bitmaps are switched on the array limit, counting the runs of those is too
+    /// costly to be done after every operation. Runs are kept only while they are the smallest
+    /// of the three representations.
     pub(crate) fn ensure_correct_store(&mut self) {
         match &self.store {
             Store::Bitmap(ref bits) => {
@@ -142,6 +220,17 @@ impl Container {
                     self.store = Store::Bitmap(vec.to_bitmap_store())
                 }
             }
+            Store::Run(ref runs) => {
+                let len = runs.len();
+                let run_size = RunStore::serialized_byte_size(runs.run_amount() as usize);
+                if len <= ARRAY_LIMIT {
+                    if ArrayStore::serialized_byte_size(len as usize) <= run_size {
+                        self.store = Store::Array(runs.to_array_store())
+                    }
+                } else if BitmapStore::serialized_byte_size() <= run_size {
+                    self.store = Store::Bitmap(runs.to_bitmap_store())
+                }
+            }
         };
     }
 }
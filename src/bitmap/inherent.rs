@@ -3,7 +3,7 @@ use std::ops::RangeBounds;
 
 use crate::RoaringBitmap;
 
-use super::container::Container;
+use super::container::{replace_at, Container};
 use super::util;
 
 impl RoaringBitmap {
@@ -578,6 +578,60 @@ impl RoaringBitmap {
 
         None
     }
+
+    /// Converts every container to its most compact representation, using run containers
+    /// where they are smaller than arrays or bitmaps.
+    ///
+    /// The other methods, such as [`RoaringBitmap::insert`] or [`RoaringBitmap::insert_range`],
+    /// don't convert containers to run containers as keeping track of the runs on every
+    /// insertion is too costly, call this method once the bitmap is built, for example before
+    /// serializing it in the run container format.
+    ///
+    /// Returns `true` if the bitmap contains at least one run container afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringBitmap;
+    ///
+    /// let mut rb: RoaringBitmap = (0..10_000).collect();
+    /// let size = rb.serialized_size();
+    ///
+    /// assert!(rb.run_optimize());
+    /// assert!(rb.serialized_size() < size);
+    /// assert_eq!(rb, (0..10_000).collect());
+    /// ```
+    pub fn run_optimize(&mut self) -> bool {
+        let mut has_runs = false;
+        for container in &mut self.containers {
+            has_runs |= container.optimize();
+        }
+        has_runs
+    }
+
+    /// Converts every run container back to an array or a bitmap container.
+    ///
+    /// Returns `true` if at least one container was converted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringBitmap;
+    ///
+    /// let mut rb: RoaringBitmap = (0..10_000).collect();
+    ///
+    /// assert!(rb.run_optimize());
+    /// assert!(rb.remove_run_compression());
+    /// assert!(!rb.remove_run_compression());
+    /// assert_eq!(rb, (0..10_000).collect());
+    /// ```
+    pub fn remove_run_compression(&mut self) -> bool {
+        let mut removed = false;
+        for container in &mut self.containers {
+            removed |= container.remove_run_compression();
+        }
+        removed
+    }
 }
 
 impl Default for RoaringBitmap {
@@ -591,8 +645,12 @@ impl Clone for RoaringBitmap {
         RoaringBitmap { containers: self.containers.clone() }
     }
 
+    /// Reuses the allocations of the containers sharing their keys with the ones of `other`.
     fn clone_from(&mut self, other: &Self) {
-        self.containers.clone_from(&other.containers);
+        for (i, container) in other.containers.iter().enumerate() {
+            replace_at(&mut self.containers, i, container.key).store.clone_from(&container.store);
+        }
+        self.containers.truncate(other.containers.len());
     }
 }
 
@@ -602,6 +660,7 @@ mod tests {
     use proptest::prelude::*;
 
     use super::*;
+    use crate::bitmap::store::Store;
 
     proptest! {
         #[test]
@@ -634,6 +693,20 @@ mod tests {
                 );
             }
         }
+
+        #[test]
+        fn run_optimize(bitmap in RoaringBitmap::arbitrary()) {
+            let mut optimized = bitmap.clone();
+            optimized.run_optimize();
+            prop_assert_eq!(&optimized, &bitmap);
+
+            optimized.remove_run_compression();
+            prop_assert!(optimized
+                .containers
+                .iter()
+                .all(|container| !matches!(container.store, Store::Run(..))));
+            prop_assert_eq!(&optimized, &bitmap);
+        }
     }
 
     #[test]
@@ -0,0 +1,563 @@
+use std::collections::btree_map::{BTreeMap, Entry as MapEntry};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use memmap2::Mmap;
+
+use super::container::Container;
+use super::serialization::{self, Descriptor, SERIAL_COOKIE_NO_RUNCONTAINER};
+use super::store::Store;
+use super::util;
+use super::view::{ContainerView, RoaringBitmapView, StoreView};
+use crate::DeserializeError;
+
+/// A bitmap stored in a memory-mapped file in [the standard Roaring on-disk format][format].
+///
+/// Only the header is read when the file is opened, the containers are paged in by the
+/// operating system the first time they are queried, so files larger than the available memory
+/// can be used.
+///
+/// [format]: https://github.com/RoaringBitmap/RoaringFormatSpec
+///
+/// # Examples
+///
+/// ```rust
+/// use roaring::{MmapBitmap, RoaringBitmap};
+///
+/// let path = std::env::temp_dir().join("roaring-mmap-bitmap-doc");
+/// let rb: RoaringBitmap = (1..4).chain(100_000..200_000).collect();
+/// rb.serialize_into(std::fs::File::create(&path).unwrap()).unwrap();
+///
+/// // Safety: the file is not modified while it is mapped
+/// let mapped = unsafe { MmapBitmap::open(&path).unwrap() };
+/// assert!(mapped.contains(3));
+/// assert_eq!(mapped.len(), rb.len());
+/// assert_eq!(RoaringBitmap::from(&mapped.as_view()), rb);
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub struct MmapBitmap {
+    mmap: Mmap,
+    entries: Vec<Entry>,
+}
+
+/// A bitmap stored in a memory-mapped file that can be modified.
+///
+/// The containers are decoded in memory the first time they are modified and are written back
+/// to the file by [`MmapBitmapMut::flush`], the changes are lost if the bitmap is dropped
+/// without being flushed. When the modified containers keep the same kind and size they are
+/// overwritten in place, otherwise the whole file is rewritten.
+///
+/// # Examples
+///
+/// ```rust
+/// use roaring::{MmapBitmapMut, RoaringBitmap};
+///
+/// let path = std::env::temp_dir().join("roaring-mmap-bitmap-mut-doc");
+/// let rb: RoaringBitmap = (1..4).collect();
+/// rb.serialize_into(std::fs::File::create(&path).unwrap()).unwrap();
+///
+/// // Safety: the file is not modified by anything else while it is mapped
+/// let mut mapped = unsafe { MmapBitmapMut::open(&path).unwrap() };
+/// assert!(mapped.insert(100_000).unwrap());
+/// assert!(mapped.remove(1).unwrap());
+/// mapped.flush().unwrap();
+///
+/// let file = std::fs::File::open(&path).unwrap();
+/// let rb = RoaringBitmap::deserialize_from(file).unwrap();
+/// assert_eq!(rb, [2, 3, 100_000].iter().collect());
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub struct MmapBitmapMut {
+    path: PathBuf,
+    mmap: Mmap,
+    entries: Vec<Entry>,
+    /// The containers decoded on their first modification, an empty container has been removed.
+    dirty: BTreeMap<u16, Container>,
+}
+
+/// The location of a container in the mapped file.
+struct Entry {
+    key: u16,
+    len: u64,
+    kind: Kind,
+    /// The container data, without the number of runs prefix of run containers.
+    data: Range<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Array,
+    Bitmap,
+    Run,
+}
+
+impl Kind {
+    fn of(store: &Store) -> Kind {
+        match store {
+            Store::Array(..) => Kind::Array,
+            Store::Bitmap(..) => Kind::Bitmap,
+            Store::Run(..) => Kind::Run,
+        }
+    }
+}
+
+impl Entry {
+    /// The serialized container, including the number of runs prefix of run containers.
+    fn serialized_range(&self) -> Range<usize> {
+        match self.kind {
+            Kind::Run => self.data.start - 2..self.data.end,
+            _ => self.data.clone(),
+        }
+    }
+
+    fn view<'a>(&self, bytes: &'a [u8]) -> ContainerView<'a> {
+        let data = &bytes[self.data.clone()];
+        let store = match self.kind {
+            Kind::Array => StoreView::Array(data),
+            Kind::Bitmap => StoreView::Bitmap(data),
+            Kind::Run => StoreView::Run(data),
+        };
+        ContainerView { key: self.key, len: self.len, store }
+    }
+}
+
+/// Reads the layout of the containers, without reading their data.
+fn parse(bytes: &[u8]) -> io::Result<Vec<Entry>> {
+    let view = RoaringBitmapView::from_bytes_unchecked(bytes)?;
+    // The containers are found with binary searches
+    if let Some(i) = view.containers.windows(2).position(|w| w[0].key >= w[1].key) {
+        let key = view.containers[i + 1].key;
+        let offset = (descriptions_offset(bytes) + (i + 1) * 4) as u64;
+        return Err(DeserializeError::UnsortedKeys { key, offset }.into());
+    }
+    let entries = view
+        .containers
+        .iter()
+        .map(|container| {
+            let (kind, data) = match container.store {
+                StoreView::Array(data) => (Kind::Array, data),
+                StoreView::Bitmap(data) => (Kind::Bitmap, data),
+                StoreView::Run(data) => (Kind::Run, data),
+            };
+            let start = data.as_ptr() as usize - bytes.as_ptr() as usize;
+            Entry { key: container.key, len: container.len, kind, data: start..start + data.len() }
+        })
+        .collect();
+    Ok(entries)
+}
+
+/// Returns the position of the descriptive header, which follows the cookie and the run bitset.
+fn descriptions_offset(bytes: &[u8]) -> usize {
+    let cookie = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    if cookie == SERIAL_COOKIE_NO_RUNCONTAINER {
+        8
+    } else {
+        let size = (cookie >> 16) as usize + 1;
+        4 + (size + 7) / 8
+    }
+}
+
+impl MmapBitmap {
+    /// Maps the file at `path` and reads its header. Only the layout of the file is checked,
+    /// the queries are memory safe but will return meaningless results if the data is not a
+    /// valid bitmap.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while it is mapped, see [`memmap2::Mmap::map`].
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> io::Result<MmapBitmap> {
+        let file = File::open(path)?;
+        let mmap = unsafe { Mmap::map(&file)? };
+        let entries = parse(&mmap)?;
+        Ok(MmapBitmap { mmap, entries })
+    }
+
+    /// Returns `true` if this set contains the specified integer.
+    pub fn contains(&self, value: u32) -> bool {
+        let (key, index) = util::split(value);
+        match self.entries.binary_search_by_key(&key, |e| e.key) {
+            Ok(loc) => self.entries[loc].view(&self.mmap).store.contains(index),
+            Err(_) => false,
+        }
+    }
+
+    /// Returns `true` if there are no integers in this set.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the number of distinct integers in the set.
+    pub fn len(&self) -> u64 {
+        self.entries.iter().map(|entry| entry.len).sum()
+    }
+
+    /// Borrows the mapped bitmap as a [`RoaringBitmapView`], to iterate over it or to use it
+    /// in operations with other bitmaps.
+    pub fn as_view(&self) -> RoaringBitmapView<'_> {
+        RoaringBitmapView {
+            containers: self.entries.iter().map(|entry| entry.view(&self.mmap)).collect(),
+        }
+    }
+}
+
+impl MmapBitmapMut {
+    /// Maps the file at `path` and reads its header. Only the layout of the file is checked,
+    /// the data of the containers is checked when they are first modified.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated by anything else than this bitmap while it is
+    /// mapped, see [`memmap2::Mmap::map`].
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> io::Result<MmapBitmapMut> {
+        let path = path.as_ref().to_path_buf();
+        let file = File::open(&path)?;
+        let mmap = unsafe { Mmap::map(&file)? };
+        let entries = parse(&mmap)?;
+        Ok(MmapBitmapMut { path, mmap, entries, dirty: BTreeMap::new() })
+    }
+
+    /// Returns `true` if this set contains the specified integer.
+    pub fn contains(&self, value: u32) -> bool {
+        let (key, index) = util::split(value);
+        if let Some(container) = self.dirty.get(&key) {
+            return container.contains(index);
+        }
+        match self.entries.binary_search_by_key(&key, |e| e.key) {
+            Ok(loc) => self.entries[loc].view(&self.mmap).store.contains(index),
+            Err(_) => false,
+        }
+    }
+
+    /// Returns `true` if there are no integers in this set.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of distinct integers in the set.
+    pub fn len(&self) -> u64 {
+        let clean: u64 = self
+            .entries
+            .iter()
+            .filter(|entry| !self.dirty.contains_key(&entry.key))
+            .map(|entry| entry.len)
+            .sum();
+        clean + self.dirty.values().map(|container| container.len()).sum::<u64>()
+    }
+
+    /// Returns `true` if some modifications have not been written to the file yet.
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Adds a value to the set.
+    ///
+    /// Returns whether the value was absent from the set, or an error if the container of the
+    /// value could not be loaded from the file.
+    pub fn insert(&mut self, value: u32) -> io::Result<bool> {
+        if self.contains(value) {
+            return Ok(false);
+        }
+        let (key, index) = util::split(value);
+        Ok(self.load(key)?.insert(index))
+    }
+
+    /// Removes a value from the set.
+    ///
+    /// Returns whether the value was present in the set, or an error if the container of the
+    /// value could not be loaded from the file.
+    pub fn remove(&mut self, value: u32) -> io::Result<bool> {
+        if !self.contains(value) {
+            return Ok(false);
+        }
+        let (key, index) = util::split(value);
+        Ok(self.load(key)?.remove(index))
+    }
+
+    /// Writes the modified containers back to the file.
+    ///
+    /// Rewriting the whole file goes through a temporary file that replaces it, but the
+    /// containers overwritten in place are not written atomically: the file may be left corrupted
+    /// if the process or the system crashes during the flush.
+    pub fn flush(&mut self) -> io::Result<()> {
+        if self.dirty.is_empty() {
+            return Ok(());
+        }
+
+        if self.fits_in_place() {
+            self.write_in_place()?;
+        } else {
+            self.rewrite()?;
+        }
+        self.dirty.clear();
+        Ok(())
+    }
+
+    /// Returns the container for `key`, decoding it from the file on first touch.
+    fn load(&mut self, key: u16) -> io::Result<&mut Container> {
+        match self.dirty.entry(key) {
+            MapEntry::Occupied(entry) => Ok(entry.into_mut()),
+            MapEntry::Vacant(vacant) => {
+                let container = match self.entries.binary_search_by_key(&key, |e| e.key) {
+                    Ok(loc) => {
+                        let entry = &self.entries[loc];
+                        let view = entry.view(&self.mmap);
+                        let description_offset = descriptions_offset(&self.mmap) + loc * 4;
+                        let offset = entry.serialized_range().start as u64;
+                        view.validate(None, description_offset as u64, offset)?;
+                        view.to_container()
+                    }
+                    Err(_) => Container::new(key),
+                };
+                Ok(vacant.insert(container))
+            }
+        }
+    }
+
+    /// Returns whether every modified container can be overwritten without moving the others.
+    ///
+    /// The kind of a container must not change, an array and a bitmap of 4096 values have the
+    /// same size but the entries read the data according to their kind.
+    fn fits_in_place(&self) -> bool {
+        self.dirty.iter().all(|(key, container)| {
+            match self.entries.binary_search_by_key(key, |e| e.key) {
+                Ok(loc) => {
+                    let entry = &self.entries[loc];
+                    container.len() != 0
+                        && Kind::of(&container.store) == entry.kind
+                        && Descriptor::of(container).size == entry.serialized_range().len()
+                }
+                Err(_) => false,
+            }
+        })
+    }
+
+    fn write_in_place(&mut self) -> io::Result<()> {
+        let mut file = OpenOptions::new().write(true).open(&self.path)?;
+        let descriptions = descriptions_offset(&self.mmap);
+
+        let mut buffer = Vec::new();
+        for (key, container) in &self.dirty {
+            let loc = self.entries.binary_search_by_key(key, |e| e.key).unwrap();
+            let entry = &mut self.entries[loc];
+
+            // The cardinality is the second field of the description
+            let len = (container.len() - 1) as u16;
+            file.seek(SeekFrom::Start((descriptions + loc * 4 + 2) as u64))?;
+            file.write_all(&len.to_le_bytes())?;
+
+            buffer.clear();
+            serialization::serialize_store_into(&container.store, &mut buffer)?;
+            file.seek(SeekFrom::Start(entry.serialized_range().start as u64))?;
+            file.write_all(&buffer)?;
+
+            entry.len = container.len();
+        }
+
+        file.sync_data()
+    }
+
+    fn rewrite(&mut self) -> io::Result<()> {
+        enum Source<'a> {
+            Clean(&'a Entry),
+            Dirty(&'a Container),
+        }
+
+        // Merge the containers of the file with the modified ones, dropping the emptied ones
+        let mut sources = Vec::with_capacity(self.entries.len() + self.dirty.len());
+        let mut entries = self.entries.iter().peekable();
+        for (key, container) in &self.dirty {
+            while let Some(entry) = entries.next_if(|entry| entry.key < *key) {
+                sources.push(Source::Clean(entry));
+            }
+            entries.next_if(|entry| entry.key == *key);
+            if container.len() != 0 {
+                sources.push(Source::Dirty(container));
+            }
+        }
+        sources.extend(entries.map(Source::Clean));
+
+        let descriptors: Vec<_> = sources
+            .iter()
+            .map(|source| match source {
+                Source::Clean(entry) => Descriptor {
+                    key: entry.key,
+                    len: entry.len,
+                    is_run: entry.kind == Kind::Run,
+                    size: entry.serialized_range().len(),
+                },
+                Source::Dirty(container) => Descriptor::of(container),
+            })
+            .collect();
+
+        let mut file_name = self.path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(".tmp");
+        let tmp_path = self.path.with_file_name(file_name);
+
+        // The file is read back to be mapped, which requires it to be readable
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&tmp_path)?;
+        let mut writer = BufWriter::new(file);
+        serialization::serialize_header_into(descriptors.iter().copied(), &mut writer)?;
+        for source in &sources {
+            match source {
+                Source::Clean(entry) => writer.write_all(&self.mmap[entry.serialized_range()])?,
+                Source::Dirty(container) => {
+                    serialization::serialize_store_into(&container.store, &mut writer)?
+                }
+            }
+        }
+        let file = writer.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()?;
+
+        // Safety: the file has just been written and will only be modified through this bitmap
+        let mmap = unsafe { Mmap::map(&file)? };
+        let entries = parse(&mmap)?;
+        fs::rename(&tmp_path, &self.path)?;
+
+        self.mmap = mmap;
+        self.entries = entries;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::{self, File};
+    use std::path::PathBuf;
+
+    use super::{MmapBitmap, MmapBitmapMut};
+    use crate::RoaringBitmap;
+
+    fn write(name: &str, bitmap: &RoaringBitmap) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("roaring-{}-{}", name, std::process::id()));
+        bitmap.serialize_into(File::create(&path).unwrap()).unwrap();
+        path
+    }
+
+    fn read(path: &PathBuf) -> RoaringBitmap {
+        RoaringBitmap::deserialize_from(File::open(path).unwrap()).unwrap()
+    }
+
+    fn test_data_bitmap() -> RoaringBitmap {
+        let mut bitmap = (0..100)
+            .map(|i| i * 1000)
+            .chain((100_000..200_000).map(|i| i * 3))
+            .collect::<RoaringBitmap>();
+        bitmap.insert_range(700_000..800_000);
+        bitmap.run_optimize();
+        bitmap
+    }
+
+    #[test]
+    fn read_only() {
+        let bitmap = test_data_bitmap();
+        let path = write("mmap-read-only", &bitmap);
+
+        let mapped = unsafe { MmapBitmap::open(&path).unwrap() };
+        assert_eq!(mapped.len(), bitmap.len());
+        assert!(mapped.contains(3000));
+        assert!(!mapped.contains(3001));
+        assert!(mapped.contains(750_000));
+        assert_eq!(RoaringBitmap::from(&mapped.as_view()), bitmap);
+
+        drop(mapped);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn write_back_in_place() {
+        let mut bitmap = test_data_bitmap();
+        let path = write("mmap-in-place", &bitmap);
+        let size = fs::metadata(&path).unwrap().len();
+
+        let mut mapped = unsafe { MmapBitmapMut::open(&path).unwrap() };
+        // Inserting or removing nothing leaves the file untouched
+        assert!(!mapped.insert(300_000).unwrap());
+        assert!(!mapped.remove(300_001).unwrap());
+        assert!(!mapped.is_dirty());
+
+        // Bitmap containers keep their size
+        assert!(mapped.insert(300_001).unwrap());
+        assert!(mapped.remove(300_003).unwrap());
+        assert!(!mapped.remove(300_004).unwrap());
+        assert!(mapped.is_dirty());
+        bitmap.insert(300_001);
+        bitmap.remove(300_003);
+        assert_eq!(mapped.len(), bitmap.len());
+
+        mapped.flush().unwrap();
+        assert!(!mapped.is_dirty());
+        assert!(mapped.contains(300_001));
+        assert!(!mapped.contains(300_003));
+        assert_eq!(mapped.len(), bitmap.len());
+        assert_eq!(fs::metadata(&path).unwrap().len(), size);
+        assert_eq!(read(&path), bitmap);
+
+        drop(mapped);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn write_back_kind_change() {
+        // An array of 4096 values and a bitmap have the same size
+        let array: RoaringBitmap = (0..8192).step_by(2).collect();
+        let bitmap: RoaringBitmap = (0..8194).step_by(2).collect();
+
+        for (before, after) in [(&bitmap, &array), (&array, &bitmap)] {
+            let path = write("mmap-kind-change", before);
+            let mut mapped = unsafe { MmapBitmapMut::open(&path).unwrap() };
+            if before.contains(8192) {
+                assert!(mapped.remove(8192).unwrap());
+            } else {
+                assert!(mapped.insert(8192).unwrap());
+            }
+            mapped.flush().unwrap();
+
+            assert!(mapped.contains(2));
+            assert!(!mapped.contains(1));
+            assert_eq!(mapped.contains(8192), after.contains(8192));
+            assert_eq!(mapped.len(), after.len());
+            assert_eq!(read(&path), *after);
+
+            drop(mapped);
+            fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn write_back_rewrite() {
+        let mut bitmap = test_data_bitmap();
+        let path = write("mmap-rewrite", &bitmap);
+
+        let mut mapped = unsafe { MmapBitmapMut::open(&path).unwrap() };
+        // A new container, a grown array container and a removed container
+        assert!(mapped.insert(u32::MAX).unwrap());
+        assert!(mapped.insert(1).unwrap());
+        for value in (65_000..66_000).step_by(1000) {
+            assert!(mapped.remove(value).unwrap());
+            bitmap.remove(value);
+        }
+        bitmap.insert(u32::MAX);
+        bitmap.insert(1);
+        assert_eq!(mapped.len(), bitmap.len());
+
+        mapped.flush().unwrap();
+        assert_eq!(mapped.len(), bitmap.len());
+        assert_eq!(read(&path), bitmap);
+
+        // The new mapping is used after the rewrite
+        assert!(mapped.remove(1).unwrap());
+        mapped.flush().unwrap();
+        bitmap.remove(1);
+        assert_eq!(read(&path), bitmap);
+
+        drop(mapped);
+        fs::remove_file(&path).unwrap();
+    }
+}
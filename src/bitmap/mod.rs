@@ -1,24 +1,43 @@
 mod arbitrary;
-mod container;
+mod archive;
+#[cfg(feature = "futures")]
+pub(crate) mod async_io;
+#[cfg(feature = "checksum")]
+pub(crate) mod checksum;
+pub(crate) mod container;
 mod fmt;
 mod multiops;
 mod proptests;
-mod store;
+pub(crate) mod store;
 mod util;
+mod view;
 
 // Order of these modules matters as it determines the `impl` blocks order in
 // the docs
 mod cmp;
 mod inherent;
 mod iter;
+#[cfg(feature = "mmap")]
+mod mmap;
 mod ops;
 #[cfg(feature = "serde")]
 mod serde;
-mod serialization;
+pub(crate) mod serialization;
+mod serialized;
+mod validate;
 
+pub use self::archive::{BitmapArchive, BitmapArchiveWriter};
 use self::cmp::Pairs;
 pub use self::iter::IntoIter;
 pub use self::iter::Iter;
+#[cfg(feature = "mmap")]
+pub use self::mmap::{MmapBitmap, MmapBitmapMut};
+pub use self::serialized::{
+    ContainerInfo, ContainerKind, SerializedBitmap, SerializedBitmapInfo, SerializedBitmapWriter,
+    SerializedIter,
+};
+pub use self::view::Iter as ViewIter;
+pub use self::view::RoaringBitmapView;
 
 /// A compressed bitmap using the [Roaring bitmap compression scheme](https://roaringbitmap.org/).
 ///
@@ -38,5 +57,5 @@ pub use self::iter::Iter;
 /// ```
 #[derive(PartialEq)]
 pub struct RoaringBitmap {
-    containers: Vec<container::Container>,
+    pub(crate) containers: Vec<container::Container>,
 }
@@ -278,8 +278,12 @@ fn merge_container_owned(
             Ok(loc) => {
                 let lhs = &mut lhs[loc];
                 match (&lhs.store, &rhs.store) {
-                    (Store::Array(..), Store::Array(..)) => lhs.store = lhs.store.to_bitmap(),
-                    (Store::Array(..), Store::Bitmap(..)) => mem::swap(lhs, &mut rhs),
+                    (Store::Array(..) | Store::Run(..), Store::Array(..) | Store::Run(..)) => {
+                        lhs.store = lhs.store.to_bitmap()
+                    }
+                    (Store::Array(..) | Store::Run(..), Store::Bitmap(..)) => {
+                        mem::swap(lhs, &mut rhs)
+                    }
                     _ => (),
                 };
                 op(&mut lhs.store, rhs.store);
@@ -398,14 +402,14 @@ fn merge_container_ref<'a>(
                 // A container that is in containers. Operate on it.
                 let lhs = &mut containers[loc];
                 match (&lhs.store, &rhs.store) {
-                    (Store::Array(..), Store::Array(..)) => {
+                    (Store::Array(..) | Store::Run(..), Store::Array(..) | Store::Run(..)) => {
                         // We had borrowed an array. Without cloning it, create a new bitmap
                         // Add all the elements to the new bitmap
                         let mut store = lhs.store.to_bitmap();
                         op(&mut store, &rhs.store);
                         *lhs = Cow::Owned(Container { key: lhs.key, store });
                     }
-                    (Store::Array(..), Store::Bitmap(..)) => {
+                    (Store::Array(..) | Store::Run(..), Store::Bitmap(..)) => {
                         // We had borrowed an array. Copy the rhs bitmap, add lhs to it
                         let mut store = rhs.store.clone();
                         op(&mut store, &lhs.store);
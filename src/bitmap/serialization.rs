@@ -1,17 +1,27 @@
-use bytemuck::cast_slice_mut;
+use bytemuck::{cast_slice, cast_slice_mut};
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use std::convert::{Infallible, TryFrom};
 use std::error::Error;
-use std::io;
+use std::io::{self, Read};
+use std::mem;
 
-use super::container::Container;
-use crate::bitmap::store::{ArrayStore, BitmapStore, Store};
-use crate::RoaringBitmap;
+use super::container::{replace_at, Container};
+use crate::bitmap::store::{ArrayStore, BitmapStore, Interval, RunStore, Store};
+use crate::{DeserializeError, DeserializeOptions, Limit, RoaringBitmap};
 
-const SERIAL_COOKIE_NO_RUNCONTAINER: u32 = 12346;
-const SERIAL_COOKIE: u16 = 12347;
-// TODO: Need this once run containers are supported
-// const NO_OFFSET_THRESHOLD: u8 = 4;
+pub(crate) const SERIAL_COOKIE_NO_RUNCONTAINER: u32 = 12346;
+pub(crate) const SERIAL_COOKIE: u16 = 12347;
+pub(crate) const NO_OFFSET_THRESHOLD: usize = 4;
+pub(crate) const FROZEN_COOKIE: u32 = 13766;
+
+// The tags of the native format, which prefix either an array of values or the portable format
+const NATIVE_ARRAY: u8 = 1;
+const NATIVE_PORTABLE: u8 = 2;
+
+// The container type codes used by the frozen format
+pub(crate) const FROZEN_BITMAP: u8 = 1;
+pub(crate) const FROZEN_ARRAY: u8 = 2;
+pub(crate) const FROZEN_RUN: u8 = 3;
 
 impl RoaringBitmap {
     /// Return the size in bytes of the serialized output.
@@ -30,22 +40,22 @@ impl RoaringBitmap {
     /// assert_eq!(rb1, rb2);
     /// ```
     pub fn serialized_size(&self) -> usize {
-        let container_sizes: usize = self
-            .containers
-            .iter()
-            .map(|container| match container.store {
-                Store::Array(ref values) => 8 + values.len() as usize * 2,
-                Store::Bitmap(..) => 8 + 8 * 1024,
-            })
-            .sum();
+        let container_sizes: usize =
+            self.containers.iter().map(|container| serialized_store_size(&container.store)).sum();
 
         // header + container sizes
-        8 + container_sizes
+        header_size(self.containers.iter().map(Descriptor::of)) + container_sizes
     }
 
     /// Serialize this bitmap into [the standard Roaring on-disk format][format].
     /// This is compatible with the official C/C++, Java and Go implementations.
     ///
+    /// The run container format is only used for bitmaps holding run containers, which are
+    /// created by [`RoaringBitmap::run_optimize`] or read from bytes written with that format.
+    /// Other bitmaps are written without it, for the implementations that don't support run
+    /// containers, and [`RoaringBitmap::remove_run_compression`] converts the run containers
+    /// back before writing.
+    ///
     /// [format]: https://github.com/RoaringBitmap/RoaringFormatSpec
     ///
     /// # Examples
@@ -61,43 +71,163 @@ impl RoaringBitmap {
     /// assert_eq!(rb1, rb2);
     /// ```
     pub fn serialize_into<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
-        writer.write_u32::<LittleEndian>(SERIAL_COOKIE_NO_RUNCONTAINER)?;
-        writer.write_u32::<LittleEndian>(self.containers.len() as u32)?;
+        serialize_header_into(self.containers.iter().map(Descriptor::of), &mut writer)?;
 
         for container in &self.containers {
-            writer.write_u16::<LittleEndian>(container.key)?;
-            writer.write_u16::<LittleEndian>((container.len() - 1) as u16)?;
+            serialize_store_into(&container.store, &mut writer)?;
         }
 
-        let mut offset = 8 + 8 * self.containers.len() as u32;
-        for container in &self.containers {
-            writer.write_u32::<LittleEndian>(offset)?;
-            match container.store {
-                Store::Array(ref values) => {
-                    offset += values.len() as u32 * 2;
-                }
-                Store::Bitmap(..) => {
-                    offset += 8 * 1024;
-                }
+        Ok(())
+    }
+
+    /// Serialize this bitmap into a new `Vec` in [the standard Roaring on-disk format][format],
+    /// allocated once with the exact size of the output.
+    ///
+    /// [format]: https://github.com/RoaringBitmap/RoaringFormatSpec
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringBitmap;
+    ///
+    /// let rb1: RoaringBitmap = (1..4).collect();
+    /// let bytes = rb1.serialize_to_vec();
+    /// let rb2 = RoaringBitmap::deserialize_from(&bytes[..]).unwrap();
+    ///
+    /// assert_eq!(bytes.len(), rb1.serialized_size());
+    /// assert_eq!(rb1, rb2);
+    /// ```
+    pub fn serialize_to_vec(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.serialized_size());
+        // Writing into a `Vec` can't fail
+        self.serialize_into(&mut bytes).unwrap();
+        bytes
+    }
+
+    /// Serialize this bitmap at the start of `buffer` in [the standard Roaring on-disk
+    /// format][format] and return the number of bytes written, which is the
+    /// [`serialized_size`](RoaringBitmap::serialized_size).
+    ///
+    /// Nothing is written if the buffer is too small and an error of kind
+    /// [`io::ErrorKind::WriteZero`] is returned.
+    ///
+    /// [format]: https://github.com/RoaringBitmap/RoaringFormatSpec
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringBitmap;
+    ///
+    /// let rb1: RoaringBitmap = (1..4).collect();
+    /// let mut buffer = [0; 64];
+    /// let written = rb1.serialize_into_slice(&mut buffer).unwrap();
+    /// let rb2 = RoaringBitmap::deserialize_from(&buffer[..written]).unwrap();
+    ///
+    /// assert_eq!(rb1, rb2);
+    /// assert!(rb1.serialize_into_slice(&mut buffer[..written - 1]).is_err());
+    /// ```
+    pub fn serialize_into_slice(&self, buffer: &mut [u8]) -> io::Result<usize> {
+        let size = self.serialized_size();
+        match buffer.get_mut(..size) {
+            Some(output) => {
+                self.serialize_into(output)?;
+                Ok(size)
             }
+            None => Err(io::Error::new(io::ErrorKind::WriteZero, "buffer is too small")),
         }
+    }
 
+    /// Return the size in bytes of the output of [`RoaringBitmap::serialize_frozen_into`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringBitmap;
+    ///
+    /// let rb: RoaringBitmap = (1..4).collect();
+    /// let mut bytes = Vec::with_capacity(rb.frozen_serialized_size());
+    /// rb.serialize_frozen_into(&mut bytes).unwrap();
+    ///
+    /// assert_eq!(bytes.len(), rb.frozen_serialized_size());
+    /// ```
+    pub fn frozen_serialized_size(&self) -> usize {
+        let container_sizes: usize = self
+            .containers
+            .iter()
+            .map(|container| match &container.store {
+                // The frozen format doesn't prefix the runs with their number
+                Store::Run(runs) => runs.run_amount() as usize * 4,
+                store => serialized_store_size(store),
+            })
+            .sum();
+
+        // container sizes + keys, counts and type codes + header
+        container_sizes + self.containers.len() * 5 + 4
+    }
+
+    /// Serialize this bitmap into the frozen format of CRoaring, as written by
+    /// `roaring_bitmap_frozen_serialize`.
+    ///
+    /// The bitmap containers are written first, then the run and array containers, and the
+    /// container descriptions are at the end. When the output starts at a 32 bytes aligned
+    /// address every container is aligned, which lets `roaring_bitmap_frozen_view` use the
+    /// bytes in place. They can be read with [`RoaringBitmapView::from_frozen_bytes`] which
+    /// doesn't require any alignment.
+    ///
+    /// Unlike CRoaring, the values are always written in little-endian.
+    ///
+    /// [`RoaringBitmapView::from_frozen_bytes`]: crate::RoaringBitmapView::from_frozen_bytes
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{RoaringBitmap, RoaringBitmapView};
+    ///
+    /// let rb1: RoaringBitmap = (1..4).collect();
+    /// let mut bytes = vec![];
+    /// rb1.serialize_frozen_into(&mut bytes).unwrap();
+    /// let view = RoaringBitmapView::from_frozen_bytes(&bytes).unwrap();
+    ///
+    /// assert_eq!(rb1, RoaringBitmap::from(&view));
+    /// ```
+    pub fn serialize_frozen_into<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
         for container in &self.containers {
-            match container.store {
-                Store::Array(ref values) => {
-                    for &value in values.iter() {
-                        writer.write_u16::<LittleEndian>(value)?;
-                    }
-                }
-                Store::Bitmap(ref bits) => {
-                    for &value in bits.as_array() {
-                        writer.write_u64::<LittleEndian>(value)?;
-                    }
-                }
+            if let Store::Bitmap(bits) = &container.store {
+                write_u64s(bits.as_array(), &mut writer)?;
+            }
+        }
+        for container in &self.containers {
+            if let Store::Run(runs) = &container.store {
+                write_runs(runs.as_slice(), &mut writer)?;
+            }
+        }
+        for container in &self.containers {
+            if let Store::Array(values) = &container.store {
+                write_u16s(values.as_slice(), &mut writer)?;
             }
         }
 
-        Ok(())
+        for container in &self.containers {
+            writer.write_u16::<LittleEndian>(container.key)?;
+        }
+        for container in &self.containers {
+            // Run containers record their number of runs instead of their cardinality
+            let count = match &container.store {
+                Store::Run(runs) => runs.run_amount(),
+                _ => container.len() - 1,
+            };
+            writer.write_u16::<LittleEndian>(count as u16)?;
+        }
+        for container in &self.containers {
+            writer.write_u8(match container.store {
+                Store::Array(..) => FROZEN_ARRAY,
+                Store::Bitmap(..) => FROZEN_BITMAP,
+                Store::Run(..) => FROZEN_RUN,
+            })?;
+        }
+
+        let header = ((self.containers.len() as u32) << 15) | FROZEN_COOKIE;
+        writer.write_u32::<LittleEndian>(header)
     }
 
     /// Deserialize a bitmap into memory from [the standard Roaring on-disk
@@ -106,6 +236,9 @@ impl RoaringBitmap {
     /// are valid. If deserializing from a trusted source consider
     /// [RoaringBitmap::deserialize_unchecked_from]
     ///
+    /// On failure, the returned [`io::Error`] wraps a [`DeserializeError`] that tells what is
+    /// wrong and where in the input.
+    ///
     /// [format]: https://github.com/RoaringBitmap/RoaringFormatSpec
     ///
     /// # Examples
@@ -121,7 +254,138 @@ impl RoaringBitmap {
     /// assert_eq!(rb1, rb2);
     /// ```
     pub fn deserialize_from<R: io::Read>(reader: R) -> io::Result<RoaringBitmap> {
-        RoaringBitmap::deserialize_from_impl(reader, ArrayStore::try_from, BitmapStore::try_from)
+        Ok(RoaringBitmap::deserialize_checked(reader)?)
+    }
+
+    /// Deserialize a bitmap into memory from the start of `bytes` in [the
+    /// standard Roaring on-disk format][format] with the checks of
+    /// [RoaringBitmap::deserialize_from], and return it with the number of
+    /// bytes it was read from.
+    ///
+    /// The bytes that follow the bitmap are left unread, so that bitmaps
+    /// written back to back can be read one after the other.
+    ///
+    /// [format]: https://github.com/RoaringBitmap/RoaringFormatSpec
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringBitmap;
+    ///
+    /// let rb1: RoaringBitmap = (1..4).collect();
+    /// let rb2: RoaringBitmap = (100..200).collect();
+    /// let mut bytes = rb1.serialize_to_vec();
+    /// bytes.extend(rb2.serialize_to_vec());
+    ///
+    /// let (first, read) = RoaringBitmap::deserialize_from_slice(&bytes).unwrap();
+    /// let (second, _) = RoaringBitmap::deserialize_from_slice(&bytes[read..]).unwrap();
+    ///
+    /// assert_eq!(first, rb1);
+    /// assert_eq!(second, rb2);
+    /// ```
+    pub fn deserialize_from_slice(
+        bytes: &[u8],
+    ) -> Result<(RoaringBitmap, usize), DeserializeError> {
+        let mut rest = bytes;
+        let bitmap = RoaringBitmap::deserialize_checked(&mut rest)?;
+        Ok((bitmap, bytes.len() - rest.len()))
+    }
+
+    /// Deserialize a bitmap into memory from [the standard Roaring on-disk
+    /// format][format] like [RoaringBitmap::deserialize_from], without
+    /// exceeding the limits of `options`.
+    ///
+    /// The limits are checked before allocating the memory to read each part
+    /// of the input, a [`DeserializeError::LimitExceeded`] is returned as soon
+    /// as the sizes declared by the input exceed one of them.
+    ///
+    /// [format]: https://github.com/RoaringBitmap/RoaringFormatSpec
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{DeserializeOptions, RoaringBitmap};
+    ///
+    /// let rb1: RoaringBitmap = (1..4).collect();
+    /// let mut bytes = vec![];
+    /// rb1.serialize_into(&mut bytes).unwrap();
+    ///
+    /// let options = DeserializeOptions::new().max_bytes(bytes.len() as u64);
+    /// let rb2 = RoaringBitmap::deserialize_from_with_options(&bytes[..], &options).unwrap();
+    /// assert_eq!(rb1, rb2);
+    ///
+    /// let options = DeserializeOptions::new().max_bytes(bytes.len() as u64 - 1);
+    /// assert!(RoaringBitmap::deserialize_from_with_options(&bytes[..], &options).is_err());
+    /// ```
+    pub fn deserialize_from_with_options<R: io::Read>(
+        reader: R,
+        options: &DeserializeOptions,
+    ) -> Result<RoaringBitmap, DeserializeError> {
+        RoaringBitmap::deserialize_with_budget(reader, &mut Budget::new(options))
+    }
+
+    /// Deserializes a bitmap with the checks of [RoaringBitmap::deserialize_from], returning
+    /// the [`DeserializeError`] itself.
+    pub(crate) fn deserialize_checked<R: io::Read>(
+        reader: R,
+    ) -> Result<RoaringBitmap, DeserializeError> {
+        RoaringBitmap::deserialize_with_budget(reader, &mut Budget::unlimited())
+    }
+
+    /// Deserializes a bitmap with the checks of [RoaringBitmap::deserialize_from], consuming
+    /// the resources it uses from `budget`.
+    pub(crate) fn deserialize_with_budget<R: io::Read>(
+        reader: R,
+        budget: &mut Budget,
+    ) -> Result<RoaringBitmap, DeserializeError> {
+        let mut bitmap = RoaringBitmap::new();
+        bitmap.deserialize_into_impl(
+            reader,
+            true,
+            budget,
+            ArrayStore::try_from,
+            BitmapStore::try_from,
+            RunStore::try_from_len,
+        )?;
+        Ok(bitmap)
+    }
+
+    /// Deserialize a bitmap from [the standard Roaring on-disk format][format]
+    /// into this one, with the checks of [RoaringBitmap::deserialize_from].
+    ///
+    /// The previous values are replaced. The allocations of this bitmap are
+    /// reused to read the new values: its list of containers, and the
+    /// containers whose keys are found in the input, when their type matches.
+    /// On error, the bitmap is left empty.
+    ///
+    /// [format]: https://github.com/RoaringBitmap/RoaringFormatSpec
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringBitmap;
+    ///
+    /// let mut scratch = RoaringBitmap::new();
+    /// for i in 0..4 {
+    ///     let rb: RoaringBitmap = (i..i + 100).collect();
+    ///     let bytes = rb.serialize_to_vec();
+    ///     scratch.deserialize_into(&bytes[..]).unwrap();
+    ///     assert_eq!(scratch, rb);
+    /// }
+    /// ```
+    pub fn deserialize_into<R: io::Read>(&mut self, reader: R) -> Result<(), DeserializeError> {
+        let result = self.deserialize_into_impl(
+            reader,
+            true,
+            &mut Budget::unlimited(),
+            ArrayStore::try_from,
+            BitmapStore::try_from,
+            RunStore::try_from_len,
+        );
+        if result.is_err() {
+            self.clear();
+        }
+        result
     }
 
     /// Deserialize a bitmap into memory from [the standard Roaring on-disk
@@ -144,81 +408,614 @@ impl RoaringBitmap {
     /// assert_eq!(rb1, rb2);
     /// ```
     pub fn deserialize_unchecked_from<R: io::Read>(reader: R) -> io::Result<RoaringBitmap> {
-        RoaringBitmap::deserialize_from_impl::<R, _, Infallible, _, Infallible>(
+        Ok(RoaringBitmap::deserialize_unchecked(reader)?)
+    }
+
+    /// Deserializes a bitmap like [RoaringBitmap::deserialize_unchecked_from], returning the
+    /// [`DeserializeError`] itself.
+    pub(crate) fn deserialize_unchecked<R: io::Read>(
+        reader: R,
+    ) -> Result<RoaringBitmap, DeserializeError> {
+        let mut bitmap = RoaringBitmap::new();
+        bitmap.deserialize_into_impl::<R, _, Infallible, _, Infallible, _, Infallible>(
             reader,
+            false,
+            &mut Budget::unlimited(),
             |values| Ok(ArrayStore::from_vec_unchecked(values)),
             |len, values| Ok(BitmapStore::from_unchecked(len, values)),
-        )
+            |_, runs| Ok(RunStore::from_vec_unchecked(runs)),
+        )?;
+        Ok(bitmap)
     }
 
-    fn deserialize_from_impl<R, A, AErr, B, BErr>(
+    /// Return the size in bytes of the output of [`RoaringBitmap::serialize_native_into`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringBitmap;
+    ///
+    /// let rb: RoaringBitmap = (1..4).collect();
+    /// let mut bytes = Vec::with_capacity(rb.native_serialized_size());
+    /// rb.serialize_native_into(&mut bytes).unwrap();
+    ///
+    /// assert_eq!(bytes.len(), rb.native_serialized_size());
+    /// ```
+    pub fn native_serialized_size(&self) -> usize {
+        let portable_size = self.serialized_size();
+        let array_size = self.len() as usize * 4 + 4;
+        portable_size.min(array_size) + 1
+    }
+
+    /// Serialize this bitmap into the native format of CRoaring, as written by
+    /// `roaring_bitmap_serialize`.
+    ///
+    /// The output starts with a tag byte, followed by either the number of values and the
+    /// values as `u32`s, or [the standard Roaring on-disk format][format], whichever is the
+    /// smallest. Unlike CRoaring, the values are always written in little-endian.
+    ///
+    /// [format]: https://github.com/RoaringBitmap/RoaringFormatSpec
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringBitmap;
+    ///
+    /// let rb1: RoaringBitmap = (1..4).collect();
+    /// let mut bytes = vec![];
+    /// rb1.serialize_native_into(&mut bytes).unwrap();
+    /// let rb2 = RoaringBitmap::deserialize_native_from(&bytes[..]).unwrap();
+    ///
+    /// assert_eq!(rb1, rb2);
+    /// ```
+    pub fn serialize_native_into<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        let len = self.len();
+        if self.serialized_size() < len as usize * 4 + 4 {
+            writer.write_u8(NATIVE_PORTABLE)?;
+            return self.serialize_into(writer);
+        }
+
+        writer.write_u8(NATIVE_ARRAY)?;
+        writer.write_u32::<LittleEndian>(len as u32)?;
+        for value in self {
+            writer.write_u32::<LittleEndian>(value)?;
+        }
+        Ok(())
+    }
+
+    /// Deserialize a bitmap into memory from the native format of CRoaring, as written by
+    /// `roaring_bitmap_serialize`. This method checks that all of the internal values are
+    /// valid. If deserializing from a trusted source consider
+    /// [RoaringBitmap::deserialize_native_unchecked_from]
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringBitmap;
+    ///
+    /// let rb1: RoaringBitmap = (1..4).collect();
+    /// let mut bytes = vec![];
+    /// rb1.serialize_native_into(&mut bytes).unwrap();
+    /// let rb2 = RoaringBitmap::deserialize_native_from(&bytes[..]).unwrap();
+    ///
+    /// assert_eq!(rb1, rb2);
+    /// ```
+    pub fn deserialize_native_from<R: io::Read>(
+        reader: R,
+    ) -> Result<RoaringBitmap, DeserializeError> {
+        RoaringBitmap::deserialize_native_from_impl(reader, RoaringBitmap::deserialize_checked)
+    }
+
+    /// Deserialize a bitmap into memory from the native format of CRoaring, as written by
+    /// `roaring_bitmap_serialize`. This method is memory safe but will not check if the data
+    /// is a valid bitmap.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringBitmap;
+    ///
+    /// let rb1: RoaringBitmap = (1..4).collect();
+    /// let mut bytes = vec![];
+    /// rb1.serialize_native_into(&mut bytes).unwrap();
+    /// let rb2 = RoaringBitmap::deserialize_native_unchecked_from(&bytes[..]).unwrap();
+    ///
+    /// assert_eq!(rb1, rb2);
+    /// ```
+    pub fn deserialize_native_unchecked_from<R: io::Read>(
+        reader: R,
+    ) -> Result<RoaringBitmap, DeserializeError> {
+        RoaringBitmap::deserialize_native_from_impl(reader, RoaringBitmap::deserialize_unchecked)
+    }
+
+    fn deserialize_native_from_impl<R: io::Read>(
         mut reader: R,
+        portable: impl FnOnce(R) -> Result<RoaringBitmap, DeserializeError>,
+    ) -> Result<RoaringBitmap, DeserializeError> {
+        let eof = |offset| move |e| DeserializeError::from_io(e, None, offset);
+        match reader.read_u8().map_err(eof(0))? {
+            // The portable bitmap follows the tag
+            NATIVE_PORTABLE => portable(reader).map_err(|e| e.shifted(1)),
+            NATIVE_ARRAY => {
+                let len = reader.read_u32::<LittleEndian>().map_err(eof(1))?;
+                // The values are sorted when written by CRoaring but it doesn't require it
+                let mut bitmap = RoaringBitmap::new();
+                for i in 0..u64::from(len) {
+                    bitmap.insert(reader.read_u32::<LittleEndian>().map_err(eof(5 + i * 4))?);
+                }
+                Ok(bitmap)
+            }
+            tag => Err(DeserializeError::UnknownCookie { cookie: u32::from(tag), offset: 0 }),
+        }
+    }
+
+    /// Replaces the containers of this bitmap with the ones read, reusing the allocations of
+    /// the containers sharing their keys.
+    fn deserialize_into_impl<R, A, AErr, B, BErr, C, CErr>(
+        &mut self,
+        reader: R,
+        check_keys: bool,
+        budget: &mut Budget,
         a: A,
         b: B,
-    ) -> io::Result<RoaringBitmap>
+        c: C,
+    ) -> Result<(), DeserializeError>
     where
         R: io::Read,
         A: Fn(Vec<u16>) -> Result<ArrayStore, AErr>,
-        AErr: Error + Send + Sync + 'static,
+        AErr: StoreError,
         B: Fn(u64, Box<[u64; 1024]>) -> Result<BitmapStore, BErr>,
-        BErr: Error + Send + Sync + 'static,
+        BErr: StoreError,
+        C: Fn(u64, Vec<Interval>) -> Result<RunStore, CErr>,
+        CErr: StoreError,
     {
-        let (size, has_offsets) = {
-            let cookie = reader.read_u32::<LittleEndian>()?;
+        let mut reader = CountingReader { inner: reader, offset: 0 };
+        let header = Header::read_from(&mut reader, budget)?;
+
+        // The containers read replace the previous ones from the start of the list, which is
+        // followed by the previous containers not replaced yet, still sorted by key
+        let containers = &mut self.containers;
+
+        // Read each container
+        for i in 0..header.size() {
+            let key = header.key(i);
+
+            // The binary searches rely on the keys being strictly increasing
+            if check_keys && i > 0 && containers[i - 1].key >= key {
+                let offset = header.description_offset(i);
+                return Err(DeserializeError::UnsortedKeys { key, offset });
+            }
+
+            // Reuse the allocations of the previous container with the same key
+            let previous = mem::take(&mut replace_at(containers, i, key).store);
+            let store = read_store(
+                &mut reader,
+                key,
+                header.len(i),
+                header.is_run(i),
+                previous,
+                budget,
+                (&a, &b, &c),
+            )?;
+            containers[i].store = store;
+        }
+        containers.truncate(header.size());
+
+        Ok(())
+    }
+}
+
+/// The header of the portable format, read before the containers data.
+pub(crate) struct Header {
+    run_container_bitmap: Option<Vec<u8>>,
+    description_bytes: Vec<u8>,
+    descriptions_offset: u64,
+}
+
+impl Header {
+    /// Reads the header up to the containers data, consuming the resources it declares from
+    /// `budget`.
+    pub fn read_from<R: io::Read>(
+        reader: &mut CountingReader<R>,
+        budget: &mut Budget,
+    ) -> Result<Header, DeserializeError> {
+        let eof = |offset| move |e| DeserializeError::from_io(e, None, offset);
+
+        // First read the cookie to determine which version of the format we are reading
+        budget.spend(Limit::Bytes, 4, 0)?;
+        let (size, size_offset, has_offsets, has_run_containers) = {
+            let cookie = reader.read_u32::<LittleEndian>().map_err(eof(0))?;
             if cookie == SERIAL_COOKIE_NO_RUNCONTAINER {
-                (reader.read_u32::<LittleEndian>()? as usize, true)
+                budget.spend(Limit::Bytes, 4, 4)?;
+                let size = reader.read_u32::<LittleEndian>().map_err(eof(4))?;
+                (size as usize, 4, true, false)
             } else if (cookie as u16) == SERIAL_COOKIE {
-                return Err(io::Error::new(io::ErrorKind::Other, "run containers are unsupported"));
+                let size = ((cookie >> 16) + 1) as usize;
+                (size, 0, size >= NO_OFFSET_THRESHOLD, true)
             } else {
-                return Err(io::Error::new(io::ErrorKind::Other, "unknown cookie value"));
+                return Err(DeserializeError::UnknownCookie { cookie, offset: 0 });
             }
         };
 
         if size > u16::MAX as usize + 1 {
-            return Err(io::Error::new(io::ErrorKind::Other, "size is greater than supported"));
+            return Err(DeserializeError::TooManyContainers {
+                count: size as u64,
+                offset: size_offset,
+            });
         }
+        budget.spend(Limit::Containers, size as u64, size_offset)?;
 
+        // Read the run container bitmap if necessary
+        let run_container_bitmap = if has_run_containers {
+            let offset = reader.offset;
+            budget.spend(Limit::Bytes, (size as u64 + 7) / 8, offset)?;
+            let mut bitmap = vec![0u8; (size + 7) / 8];
+            reader.read_exact(&mut bitmap).map_err(eof(offset))?;
+            Some(bitmap)
+        } else {
+            None
+        };
+
+        // Read the container descriptions
+        let descriptions_offset = reader.offset;
+        budget.spend(Limit::Bytes, size as u64 * 4, descriptions_offset)?;
         let mut description_bytes = vec![0u8; size * 4];
-        reader.read_exact(&mut description_bytes)?;
-        let mut description_bytes = &description_bytes[..];
+        reader.read_exact(&mut description_bytes).map_err(eof(descriptions_offset))?;
+        let header = Header { run_container_bitmap, description_bytes, descriptions_offset };
+        let cardinality = (0..size).map(|i| header.len(i)).sum();
+        budget.spend(Limit::Cardinality, cardinality, descriptions_offset)?;
 
         if has_offsets {
+            let offset = reader.offset;
+            budget.spend(Limit::Bytes, size as u64 * 4, offset)?;
             let mut offsets = vec![0u8; size * 4];
-            reader.read_exact(&mut offsets)?;
-            drop(offsets); // Not useful when deserializing into memory
+            reader.read_exact(&mut offsets).map_err(eof(offset))?;
+            drop(offsets); // Not useful when reading the containers in order
         }
 
-        let mut containers = Vec::with_capacity(size);
+        Ok(header)
+    }
+
+    /// Returns the number of containers.
+    pub fn size(&self) -> usize {
+        self.description_bytes.len() / 4
+    }
 
-        for _ in 0..size {
-            let key = description_bytes.read_u16::<LittleEndian>()?;
-            let len = u64::from(description_bytes.read_u16::<LittleEndian>()?) + 1;
+    pub fn key(&self, i: usize) -> u16 {
+        let bytes = &self.description_bytes[i * 4..];
+        u16::from_le_bytes([bytes[0], bytes[1]])
+    }
 
-            let store = if len <= 4096 {
-                let mut values = vec![0; len as usize];
-                reader.read_exact(cast_slice_mut(&mut values))?;
-                values.iter_mut().for_each(|n| *n = u16::from_le(*n));
-                let array = a(values).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-                Store::Array(array)
-            } else {
-                let mut values = Box::new([0; 1024]);
-                reader.read_exact(cast_slice_mut(&mut values[..]))?;
-                values.iter_mut().for_each(|n| *n = u64::from_le(*n));
-                let bitmap =
-                    b(len, values).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-                Store::Bitmap(bitmap)
-            };
+    /// Returns the cardinality of the `i`th container.
+    pub fn len(&self, i: usize) -> u64 {
+        let bytes = &self.description_bytes[i * 4..];
+        u64::from(u16::from_le_bytes([bytes[2], bytes[3]])) + 1
+    }
+
+    pub fn is_run(&self, i: usize) -> bool {
+        self.run_container_bitmap.as_ref().map_or(false, |bm| bm[i / 8] & (1 << (i % 8)) != 0)
+    }
+
+    /// Returns the position of the description of the `i`th container in the input.
+    pub fn description_offset(&self, i: usize) -> u64 {
+        self.descriptions_offset + i as u64 * 4
+    }
+}
+
+/// Reads the data of a container described by `key`, `len` and `is_run_container`, with the
+/// store constructors `a`, `b` and `c`, reusing the allocation of `previous` if its type
+/// matches.
+pub(crate) fn read_store<R, A, AErr, B, BErr, C, CErr>(
+    reader: &mut CountingReader<R>,
+    key: u16,
+    len: u64,
+    is_run_container: bool,
+    previous: Store,
+    budget: &mut Budget,
+    (a, b, c): (&A, &B, &C),
+) -> Result<Store, DeserializeError>
+where
+    R: io::Read,
+    A: Fn(Vec<u16>) -> Result<ArrayStore, AErr>,
+    AErr: StoreError,
+    B: Fn(u64, Box<[u64; 1024]>) -> Result<BitmapStore, BErr>,
+    BErr: StoreError,
+    C: Fn(u64, Vec<Interval>) -> Result<RunStore, CErr>,
+    CErr: StoreError,
+{
+    let offset = reader.offset;
+    let eof = |e| DeserializeError::from_io(e, Some(key), offset);
+    let invalid = |cardinality, source| match cardinality {
+        Some((expected, actual)) => {
+            DeserializeError::BadCardinality { key, offset, expected, actual }
+        }
+        None => DeserializeError::InvalidContainer { key, offset, source },
+    };
+
+    if is_run_container {
+        budget.spend(Limit::Bytes, 2, offset)?;
+        let runs = reader.read_u16::<LittleEndian>().map_err(eof)?;
+        budget.spend(Limit::Bytes, u64::from(runs) * 4, offset + 2)?;
+        let count = runs as usize;
+        let mut runs = match previous {
+            Store::Run(runs) => runs.into_vec(),
+            _ => Vec::new(),
+        };
+        runs.clear();
+        runs.resize(count, Interval { start: 0, end: 0 });
+        reader.read_exact(cast_slice_mut(&mut runs)).map_err(eof)?;
+
+        // The runs are read as (start, length) pairs and turned into (start, end) in place
+        for run in &mut runs {
+            let start = u16::from_le(run.start);
+            let len = u16::from_le(run.end);
+            match start.checked_add(len) {
+                Some(end) => *run = Interval::new(start, end),
+                None => {
+                    return Err(DeserializeError::InvalidContainer {
+                        key,
+                        offset,
+                        source: "run container with invalid run".into(),
+                    })
+                }
+            }
+        }
+
+        let store = c(len, runs).map_err(|e| invalid(e.cardinality(), Box::new(e)))?;
+        Ok(Store::Run(store))
+    } else if len <= 4096 {
+        budget.spend(Limit::Bytes, len * 2, offset)?;
+        let mut values = match previous {
+            Store::Array(values) => values.into_vec(),
+            _ => Vec::new(),
+        };
+        values.clear();
+        values.resize(len as usize, 0);
+        reader.read_exact(cast_slice_mut(&mut values)).map_err(eof)?;
+        if cfg!(target_endian = "big") {
+            values.iter_mut().for_each(|n| *n = u16::from_le(*n));
+        }
+        let array = a(values).map_err(|e| invalid(e.cardinality(), Box::new(e)))?;
+        Ok(Store::Array(array))
+    } else {
+        budget.spend(Limit::Bytes, 8 * 1024, offset)?;
+        let mut values = match previous {
+            Store::Bitmap(bits) => bits.into_bits(),
+            _ => Box::new([0; 1024]),
+        };
+        reader.read_exact(cast_slice_mut(&mut values[..])).map_err(eof)?;
+        if cfg!(target_endian = "big") {
+            values.iter_mut().for_each(|n| *n = u64::from_le(*n));
+        }
+        let bitmap = b(len, values).map_err(|e| invalid(e.cardinality(), Box::new(e)))?;
+        Ok(Store::Bitmap(bitmap))
+    }
+}
+
+/// The errors of the checked store constructors, which tell apart the cardinality mismatches.
+pub(crate) trait StoreError: Error + Send + Sync + 'static {
+    /// Returns the expected and actual cardinalities of the store if they don't match.
+    fn cardinality(&self) -> Option<(u64, u64)> {
+        None
+    }
+}
+
+impl StoreError for Infallible {}
+
+/// What remains of the limits of some [`DeserializeOptions`] during a deserialization.
+pub(crate) struct Budget {
+    containers: u64,
+    bytes: u64,
+    cardinality: u64,
+    treemap_entries: u64,
+}
+
+impl Budget {
+    pub fn new(options: &DeserializeOptions) -> Budget {
+        Budget {
+            containers: options.max_containers,
+            bytes: options.max_bytes,
+            cardinality: options.max_cardinality,
+            treemap_entries: options.max_treemap_entries,
+        }
+    }
+
+    pub fn unlimited() -> Budget {
+        Budget::new(&DeserializeOptions::new())
+    }
+
+    /// Checks that `amount` of a limit is left without consuming it, for the part of the input
+    /// starting at `offset` that is read before being parsed.
+    #[cfg(feature = "futures")]
+    pub fn check(&self, limit: Limit, amount: u64, offset: u64) -> Result<(), DeserializeError> {
+        let remaining = match limit {
+            Limit::Containers => self.containers,
+            Limit::Bytes => self.bytes,
+            Limit::Cardinality => self.cardinality,
+            Limit::TreemapEntries => self.treemap_entries,
+        };
+        if amount <= remaining {
+            Ok(())
+        } else {
+            Err(DeserializeError::LimitExceeded { limit, offset })
+        }
+    }
+
+    /// Consumes `amount` of a limit, for the part of the input starting at `offset`.
+    pub fn spend(
+        &mut self,
+        limit: Limit,
+        amount: u64,
+        offset: u64,
+    ) -> Result<(), DeserializeError> {
+        let remaining = match limit {
+            Limit::Containers => &mut self.containers,
+            Limit::Bytes => &mut self.bytes,
+            Limit::Cardinality => &mut self.cardinality,
+            Limit::TreemapEntries => &mut self.treemap_entries,
+        };
+        match remaining.checked_sub(amount) {
+            Some(rest) => {
+                *remaining = rest;
+                Ok(())
+            }
+            None => Err(DeserializeError::LimitExceeded { limit, offset }),
+        }
+    }
+}
+
+/// A reader keeping track of the number of bytes read, to locate the errors.
+pub(crate) struct CountingReader<R> {
+    pub inner: R,
+    pub offset: u64,
+}
+
+impl<R: io::Read> io::Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.offset += n as u64;
+        Ok(n)
+    }
+}
 
-            containers.push(Container { key, store });
+/// What the header of the serialized format records about a container.
+#[derive(Clone, Copy)]
+pub(crate) struct Descriptor {
+    pub key: u16,
+    pub len: u64,
+    pub is_run: bool,
+    /// The number of bytes of the container data.
+    pub size: usize,
+}
+
+impl Descriptor {
+    pub fn of(container: &Container) -> Descriptor {
+        Descriptor {
+            key: container.key,
+            len: container.len(),
+            is_run: matches!(container.store, Store::Run(..)),
+            size: serialized_store_size(&container.store),
         }
+    }
+}
 
-        Ok(RoaringBitmap { containers })
+/// The size of everything preceding the containers data:
+/// the cookie, the run bitset, the descriptive header and the offset header.
+pub(crate) fn header_size<I>(descriptors: I) -> usize
+where
+    I: IntoIterator<Item = Descriptor>,
+{
+    let (size, has_run_containers) = descriptors
+        .into_iter()
+        .fold((0, false), |(size, has_runs), d| (size + 1, has_runs || d.is_run));
+    if has_run_containers {
+        let offsets = if size >= NO_OFFSET_THRESHOLD { 4 * size } else { 0 };
+        4 + (size + 7) / 8 + 4 * size + offsets
+    } else {
+        8 + 8 * size
     }
 }
 
+/// Writes the header of the serialized format, the containers data must follow in the same order.
+pub(crate) fn serialize_header_into<I, W>(descriptors: I, writer: &mut W) -> io::Result<()>
+where
+    I: IntoIterator<Item = Descriptor>,
+    I::IntoIter: Clone,
+    W: io::Write,
+{
+    let descriptors = descriptors.into_iter();
+    let size = descriptors.clone().count();
+    let has_run_containers = descriptors.clone().any(|d| d.is_run);
+
+    let has_offsets = if has_run_containers {
+        let cookie = u32::from(SERIAL_COOKIE) | ((size as u32 - 1) << 16);
+        writer.write_u32::<LittleEndian>(cookie)?;
+
+        let mut run_bitset = vec![0u8; (size + 7) / 8];
+        for (i, descriptor) in descriptors.clone().enumerate() {
+            if descriptor.is_run {
+                run_bitset[i / 8] |= 1 << (i % 8);
+            }
+        }
+        writer.write_all(&run_bitset)?;
+        size >= NO_OFFSET_THRESHOLD
+    } else {
+        writer.write_u32::<LittleEndian>(SERIAL_COOKIE_NO_RUNCONTAINER)?;
+        writer.write_u32::<LittleEndian>(size as u32)?;
+        true
+    };
+
+    for descriptor in descriptors.clone() {
+        writer.write_u16::<LittleEndian>(descriptor.key)?;
+        writer.write_u16::<LittleEndian>((descriptor.len - 1) as u16)?;
+    }
+
+    if has_offsets {
+        let mut offset = header_size(descriptors.clone()) as u32;
+        for descriptor in descriptors {
+            writer.write_u32::<LittleEndian>(offset)?;
+            offset += descriptor.size as u32;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the number of bytes the store data takes in the serialized format.
+pub(crate) fn serialized_store_size(store: &Store) -> usize {
+    match store {
+        Store::Array(values) => ArrayStore::serialized_byte_size(values.len() as usize),
+        Store::Bitmap(..) => BitmapStore::serialized_byte_size(),
+        Store::Run(runs) => RunStore::serialized_byte_size(runs.run_amount() as usize),
+    }
+}
+
+/// Writes the data of a store in the serialized format.
+pub(crate) fn serialize_store_into<W: io::Write>(store: &Store, writer: &mut W) -> io::Result<()> {
+    match store {
+        Store::Array(values) => write_u16s(values.as_slice(), writer),
+        Store::Bitmap(bits) => write_u64s(bits.as_array(), writer),
+        Store::Run(runs) => {
+            writer.write_u16::<LittleEndian>(runs.run_amount() as u16)?;
+            write_runs(runs.as_slice(), writer)
+        }
+    }
+}
+
+/// Writes the values in little-endian, in a single write on little-endian targets.
+fn write_u16s<W: io::Write>(values: &[u16], writer: &mut W) -> io::Result<()> {
+    if cfg!(target_endian = "little") {
+        writer.write_all(cast_slice(values))
+    } else {
+        values.iter().try_for_each(|&value| writer.write_u16::<LittleEndian>(value))
+    }
+}
+
+/// Writes the values in little-endian, in a single write on little-endian targets.
+fn write_u64s<W: io::Write>(values: &[u64], writer: &mut W) -> io::Result<()> {
+    if cfg!(target_endian = "little") {
+        writer.write_all(cast_slice(values))
+    } else {
+        values.iter().try_for_each(|&value| writer.write_u64::<LittleEndian>(value))
+    }
+}
+
+/// Writes the runs as pairs of their start and their length minus one, through a buffer on
+/// the stack so that no allocation is made.
+fn write_runs<W: io::Write>(runs: &[Interval], writer: &mut W) -> io::Result<()> {
+    let mut pairs = [[0u16; 2]; 256];
+    for chunk in runs.chunks(pairs.len()) {
+        for (pair, run) in pairs.iter_mut().zip(chunk) {
+            *pair = [run.start.to_le(), (run.end - run.start).to_le()];
+        }
+        writer.write_all(cast_slice(&pairs[..chunk.len()]))?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
-    use crate::RoaringBitmap;
+    use crate::{RoaringBitmap, RoaringBitmapView};
     use proptest::prelude::*;
 
     proptest! {
@@ -230,5 +1027,52 @@ mod test {
             bitmap.serialize_into(&mut buffer).unwrap();
             prop_assert_eq!(bitmap, RoaringBitmap::deserialize_from(buffer.as_slice()).unwrap());
         }
+
+        #[test]
+        fn test_frozen_serialization(
+            bitmap in RoaringBitmap::arbitrary(),
+        ) {
+            let mut buffer = Vec::new();
+            bitmap.serialize_frozen_into(&mut buffer).unwrap();
+            prop_assert_eq!(buffer.len(), bitmap.frozen_serialized_size());
+            let view = RoaringBitmapView::from_frozen_bytes(&buffer).unwrap();
+            prop_assert_eq!(bitmap, RoaringBitmap::from(&view));
+        }
+
+        #[test]
+        fn test_native_serialization(
+            bitmap in RoaringBitmap::arbitrary(),
+        ) {
+            let mut buffer = Vec::new();
+            bitmap.serialize_native_into(&mut buffer).unwrap();
+            prop_assert_eq!(buffer.len(), bitmap.native_serialized_size());
+            prop_assert_eq!(bitmap, RoaringBitmap::deserialize_native_from(buffer.as_slice()).unwrap());
+        }
+
+        #[test]
+        fn test_deserialize_into(
+            previous in RoaringBitmap::arbitrary(),
+            bitmap in RoaringBitmap::arbitrary(),
+        ) {
+            let mut scratch = previous.clone();
+            scratch.deserialize_into(bitmap.serialize_to_vec().as_slice()).unwrap();
+            prop_assert_eq!(&scratch, &bitmap);
+
+            let mut scratch = previous;
+            scratch.clone_from(&bitmap);
+            prop_assert_eq!(scratch, bitmap);
+        }
+    }
+
+    #[test]
+    fn test_many_runs_serialization() {
+        // More runs than are written at once, in a single run container
+        let mut bitmap: RoaringBitmap = (0..2000).flat_map(|i| i * 5..i * 5 + 4).collect();
+        assert!(bitmap.run_optimize());
+
+        let mut buffer = vec![0; bitmap.serialized_size()];
+        assert_eq!(bitmap.serialize_into_slice(&mut buffer).unwrap(), buffer.len());
+        assert_eq!(buffer, bitmap.serialize_to_vec());
+        assert_eq!(RoaringBitmap::deserialize_from_slice(&buffer).unwrap(), (bitmap, buffer.len()));
     }
 }
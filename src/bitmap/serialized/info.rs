@@ -0,0 +1,284 @@
+use std::ops::RangeBounds;
+
+use crate::bitmap::util;
+use crate::bitmap::view::{HeaderView, StoreView};
+use crate::DeserializeError;
+
+/// The layout of a bitmap serialized in [the standard Roaring on-disk format][format], read
+/// from its header without deserializing the values.
+///
+/// [format]: https://github.com/RoaringBitmap/RoaringFormatSpec
+///
+/// # Examples
+///
+/// ```rust
+/// use roaring::bitmap::ContainerKind;
+/// use roaring::{RoaringBitmap, SerializedBitmapInfo};
+///
+/// let rb: RoaringBitmap = (1..4).chain(100_000..200_000).collect();
+/// let bytes = rb.serialize_to_vec();
+///
+/// let info = SerializedBitmapInfo::parse(&bytes).unwrap();
+/// assert_eq!(info.len(), rb.len());
+/// assert_eq!(info.min(), Some(1));
+/// assert_eq!(info.max(), Some(199_999));
+/// assert_eq!(info.serialized_size(), bytes.len());
+/// assert_eq!(info.keys().collect::<Vec<_>>(), [0, 1, 2, 3]);
+/// assert_eq!(info.containers()[0].kind(), ContainerKind::Array);
+/// assert_eq!(info.containers()[2].kind(), ContainerKind::Bitmap);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SerializedBitmapInfo {
+    containers: Vec<ContainerInfo>,
+    min: Option<u32>,
+    max: Option<u32>,
+    serialized_size: usize,
+}
+
+/// The description of a container of a serialized bitmap, see [`SerializedBitmapInfo`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ContainerInfo {
+    key: u16,
+    len: u64,
+    kind: ContainerKind,
+}
+
+/// The representation of the values of a container.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ContainerKind {
+    /// A sorted array of up to 4096 values.
+    Array,
+    /// A bitset of 65536 bits.
+    Bitmap,
+    /// A sorted list of runs of consecutive values.
+    Run,
+}
+
+impl SerializedBitmapInfo {
+    /// Reads the layout of a bitmap from the start of `bytes`, which must contain the whole
+    /// bitmap. Only the header and the number of runs of the run containers are read, with the
+    /// first value of the first container and the last value of the last one, the other values
+    /// are not checked.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{RoaringBitmap, SerializedBitmapInfo};
+    ///
+    /// let a = RoaringBitmap::from([1, 2, 3]).serialize_to_vec();
+    /// let b = RoaringBitmap::from([100_000, 100_001]).serialize_to_vec();
+    ///
+    /// // The bitmaps have no key in common, their intersection is empty
+    /// let a = SerializedBitmapInfo::parse(&a).unwrap();
+    /// let b = SerializedBitmapInfo::parse(&b).unwrap();
+    /// assert!(a.keys().all(|key| !b.keys().any(|other| other == key)));
+    /// ```
+    pub fn parse(bytes: &[u8]) -> Result<SerializedBitmapInfo, DeserializeError> {
+        let header = HeaderView::parse(bytes)?;
+        let mut containers = Vec::with_capacity(header.size());
+        let mut positions = Vec::with_capacity(header.size());
+
+        // Without offsets the containers are laid out one after the other
+        let mut position = header.data_start;
+        let mut serialized_size = position;
+
+        for i in 0..header.size() {
+            let key = header.key(i);
+            let len = header.len(i);
+            if containers.last().map_or(false, |previous: &ContainerInfo| previous.key >= key) {
+                let offset = header.description_offset(i);
+                return Err(DeserializeError::UnsortedKeys { key, offset });
+            }
+            if let Some(offset) = header.offset(i) {
+                position = offset;
+            }
+            let truncated =
+                || DeserializeError::Truncated { key: Some(key), offset: position as u64 };
+
+            // Only the number of runs is read to find the size of a run container
+            let (kind, size) = if header.is_run(i) {
+                let runs = bytes.get(position..position + 2).ok_or_else(truncated)?;
+                (ContainerKind::Run, 2 + usize::from(u16::from_le_bytes([runs[0], runs[1]])) * 4)
+            } else if len <= 4096 {
+                (ContainerKind::Array, len as usize * 2)
+            } else {
+                (ContainerKind::Bitmap, 8 * 1024)
+            };
+            if position + size > bytes.len() {
+                return Err(truncated());
+            }
+
+            containers.push(ContainerInfo { key, len, kind });
+            positions.push(position);
+            position += size;
+            serialized_size = serialized_size.max(position);
+        }
+
+        let store = |i: usize| {
+            let mut data = &bytes[positions[i]..];
+            StoreView::read(&mut data, header.is_run(i), header.len(i))
+        };
+        let last = containers.len().checked_sub(1);
+        let min = last.and_then(|_| store(0)?.min().map(|min| util::join(header.key(0), min)));
+        let max = last.and_then(|i| store(i)?.max().map(|max| util::join(header.key(i), max)));
+
+        Ok(SerializedBitmapInfo { containers, min, max, serialized_size })
+    }
+
+    /// Returns the descriptions of the containers, sorted by key.
+    pub fn containers(&self) -> &[ContainerInfo] {
+        &self.containers
+    }
+
+    /// Returns the number of containers.
+    pub fn container_count(&self) -> usize {
+        self.containers.len()
+    }
+
+    /// Iterator over the keys of the containers, the 16 most significant bits of their values,
+    /// in ascending order.
+    pub fn keys(&self) -> impl Iterator<Item = u16> + '_ {
+        self.containers.iter().map(|container| container.key)
+    }
+
+    /// Returns the number of values of the bitmap.
+    pub fn len(&self) -> u64 {
+        self.containers.iter().map(|container| container.len).sum()
+    }
+
+    /// Returns `true` if the bitmap has no values.
+    pub fn is_empty(&self) -> bool {
+        self.containers.is_empty()
+    }
+
+    /// Returns the minimum value of the bitmap (if it is non-empty).
+    pub fn min(&self) -> Option<u32> {
+        self.min
+    }
+
+    /// Returns the maximum value of the bitmap (if it is non-empty).
+    pub fn max(&self) -> Option<u32> {
+        self.max
+    }
+
+    /// Returns the number of bytes of the serialized bitmap, the position of the data that
+    /// follows it.
+    pub fn serialized_size(&self) -> usize {
+        self.serialized_size
+    }
+
+    /// Returns `true` if the bitmap may have values in `range`, judging from its keys.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{RoaringBitmap, SerializedBitmapInfo};
+    ///
+    /// let bytes = RoaringBitmap::from([1, 2, 3, 200_000]).serialize_to_vec();
+    /// let info = SerializedBitmapInfo::parse(&bytes).unwrap();
+    ///
+    /// assert!(info.may_intersect(0..10));
+    /// assert!(!info.may_intersect(70_000..130_000));
+    /// ```
+    pub fn may_intersect<R>(&self, range: R) -> bool
+    where
+        R: RangeBounds<u32>,
+    {
+        let (start, end) = match util::convert_range_to_inclusive(range) {
+            Some(range) => (*range.start(), *range.end()),
+            None => return false,
+        };
+        let (start_key, _) = util::split(start);
+        let (end_key, _) = util::split(end);
+        let first = self.containers.partition_point(|container| container.key < start_key);
+        self.containers.get(first).map_or(false, |container| container.key <= end_key)
+    }
+}
+
+impl ContainerInfo {
+    /// Returns the key of the container, the 16 most significant bits of its values.
+    pub fn key(&self) -> u16 {
+        self.key
+    }
+
+    /// Returns the number of values of the container.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the container has no values, which the format never stores.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the representation of the values of the container.
+    pub fn kind(&self) -> ContainerKind {
+        self.kind
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ContainerKind, SerializedBitmapInfo};
+    use crate::bitmap::store::Store;
+    use crate::RoaringBitmap;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn info_matches_bitmap(
+            mut bitmap in RoaringBitmap::arbitrary(),
+            optimize in any::<bool>(),
+            start in 0u32..=262143,
+            len in 0u32..=131072,
+        ) {
+            if optimize {
+                bitmap.run_optimize();
+            }
+            let mut bytes = bitmap.serialize_to_vec();
+            let size = bytes.len();
+            bytes.extend_from_slice(&[0xff; 16]);
+            let info = SerializedBitmapInfo::parse(&bytes).unwrap();
+
+            prop_assert_eq!(info.serialized_size(), size);
+            prop_assert_eq!(info.len(), bitmap.len());
+            prop_assert_eq!(info.is_empty(), bitmap.is_empty());
+            prop_assert_eq!(info.min(), bitmap.min());
+            prop_assert_eq!(info.max(), bitmap.max());
+            prop_assert_eq!(info.container_count(), bitmap.containers.len());
+            for (info, container) in info.containers().iter().zip(&bitmap.containers) {
+                prop_assert_eq!(info.key(), container.key);
+                prop_assert_eq!(info.len(), container.len());
+                let kind = match container.store {
+                    Store::Array(..) => ContainerKind::Array,
+                    Store::Bitmap(..) => ContainerKind::Bitmap,
+                    Store::Run(..) => ContainerKind::Run,
+                };
+                prop_assert_eq!(info.kind(), kind);
+            }
+
+            let end = start.saturating_add(len);
+            let keys = (start >> 16)..=(end.saturating_sub(1) >> 16);
+            let expected =
+                start < end && bitmap.containers.iter().any(|c| keys.contains(&u32::from(c.key)));
+            prop_assert_eq!(info.may_intersect(start..end), expected);
+        }
+    }
+
+    #[test]
+    fn info_errors() {
+        let bytes = RoaringBitmap::from([1, 100_000]).serialize_to_vec();
+        assert!(SerializedBitmapInfo::parse(&bytes[..bytes.len() - 1]).is_err());
+
+        // Two containers with the keys 1 and 0
+        let unsorted = [0x3b, 0x30, 1, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 5, 0, 5, 0];
+        assert!(SerializedBitmapInfo::parse(&unsorted).is_err());
+
+        // The values are not read, only the positions of the containers
+        let mut bytes = RoaringBitmap::from([1, 2, 3]).serialize_to_vec();
+        bytes[16..].fill(0);
+        let info = SerializedBitmapInfo::parse(&bytes).unwrap();
+        assert_eq!(info.serialized_size(), bytes.len());
+        assert_eq!(info.min(), Some(0));
+    }
+}
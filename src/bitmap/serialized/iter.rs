@@ -0,0 +1,197 @@
+use std::convert::TryFrom;
+use std::io;
+use std::iter::FusedIterator;
+
+use crate::bitmap::container::{self, Container};
+use crate::bitmap::serialization::{read_store, Budget, CountingReader, Header};
+use crate::bitmap::store::{ArrayStore, BitmapStore, RunStore, Store};
+use crate::{DeserializeError, RoaringBitmap};
+
+/// An iterator over the values of a bitmap in [the standard Roaring on-disk format][format],
+/// decoding it from a reader one container at a time.
+///
+/// Only the header and the container being iterated are kept in memory. The containers are
+/// checked like with [`RoaringBitmap::deserialize_from`] as they are read, the iteration stops
+/// after the first error.
+///
+/// [format]: https://github.com/RoaringBitmap/RoaringFormatSpec
+///
+/// # Examples
+///
+/// ```rust
+/// use roaring::{RoaringBitmap, SerializedIter};
+///
+/// let rb: RoaringBitmap = (1..4).chain(100_000..100_003).collect();
+/// let bytes = rb.serialize_to_vec();
+///
+/// let iter = SerializedIter::new(&bytes[..]).unwrap();
+/// let values: Result<Vec<u32>, _> = iter.collect();
+/// assert_eq!(values.unwrap(), [1, 2, 3, 100_000, 100_001, 100_002]);
+/// ```
+pub struct SerializedIter<R> {
+    reader: CountingReader<R>,
+    header: Header,
+    next: usize,
+    current: Option<container::Iter<'static>>,
+    failed: bool,
+}
+
+impl<R: io::Read> SerializedIter<R> {
+    /// Reads the header of a bitmap from `reader`, leaving the containers to the iteration.
+    pub fn new(reader: R) -> Result<SerializedIter<R>, DeserializeError> {
+        let mut reader = CountingReader { inner: reader, offset: 0 };
+        let header = Header::read_from(&mut reader, &mut Budget::unlimited())?;
+        Ok(SerializedIter { reader, header, next: 0, current: None, failed: false })
+    }
+
+    /// Returns the number of values of the bitmap, from its header.
+    pub fn bitmap_len(&self) -> u64 {
+        (0..self.header.size()).map(|i| self.header.len(i)).sum()
+    }
+
+    /// Reads the next container and returns its key, the 16 most significant bits of its
+    /// values, with a bitmap of its values.
+    ///
+    /// The values remaining in the container being iterated, if any, are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{RoaringBitmap, SerializedIter};
+    ///
+    /// let rb: RoaringBitmap = (1..4).chain(100_000..100_003).collect();
+    /// let bytes = rb.serialize_to_vec();
+    ///
+    /// let mut iter = SerializedIter::new(&bytes[..]).unwrap();
+    /// let (key, container) = iter.next_container().unwrap().unwrap();
+    /// assert_eq!(key, 0);
+    /// assert_eq!(container, (1..4).collect());
+    ///
+    /// let (key, container) = iter.next_container().unwrap().unwrap();
+    /// assert_eq!(key, 1);
+    /// assert_eq!(container, (100_000..100_003).collect());
+    ///
+    /// assert!(iter.next_container().is_none());
+    /// ```
+    pub fn next_container(&mut self) -> Option<Result<(u16, RoaringBitmap), DeserializeError>> {
+        self.current = None;
+        self.read_container()
+            .map(|result| result.map(|c| (c.key, RoaringBitmap { containers: vec![c] })))
+    }
+
+    /// Reads the next container from the reader, independently of the values being iterated.
+    pub(super) fn read_container(&mut self) -> Option<Result<Container, DeserializeError>> {
+        if self.failed || self.next == self.header.size() {
+            return None;
+        }
+        let i = self.next;
+        self.next += 1;
+
+        let key = self.header.key(i);
+        // The keys are checked like when deserializing, for the values to be sorted
+        if i > 0 && self.header.key(i - 1) >= key {
+            self.failed = true;
+            let offset = self.header.description_offset(i);
+            return Some(Err(DeserializeError::UnsortedKeys { key, offset }));
+        }
+
+        let result = read_store(
+            &mut self.reader,
+            key,
+            self.header.len(i),
+            self.header.is_run(i),
+            Store::new(),
+            &mut Budget::unlimited(),
+            (&ArrayStore::try_from, &BitmapStore::try_from, &RunStore::try_from_len),
+        );
+        match result {
+            Ok(store) => Some(Ok(Container { key, store })),
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl<R: io::Read> Iterator for SerializedIter<R> {
+    type Item = Result<u32, DeserializeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(value) = self.current.as_mut().and_then(Iterator::next) {
+                return Some(Ok(value));
+            }
+            match self.read_container()? {
+                Ok(container) => self.current = Some(container.into_iter()),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+impl<R: io::Read> FusedIterator for SerializedIter<R> {}
+
+#[cfg(test)]
+mod test {
+    use super::SerializedIter;
+    use crate::{DeserializeError, RoaringBitmap};
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn iter_matches_bitmap(mut bitmap in RoaringBitmap::arbitrary(), optimize in any::<bool>()) {
+            if optimize {
+                bitmap.run_optimize();
+            }
+            let bytes = bitmap.serialize_to_vec();
+
+            let iter = SerializedIter::new(&bytes[..]).unwrap();
+            prop_assert_eq!(iter.bitmap_len(), bitmap.len());
+            let values: Vec<u32> = iter.map(Result::unwrap).collect();
+            prop_assert!(values.into_iter().eq(bitmap.iter()));
+
+            let mut iter = SerializedIter::new(&bytes[..]).unwrap();
+            let mut union = RoaringBitmap::new();
+            let mut count = 0;
+            while let Some(result) = iter.next_container() {
+                let (key, container) = result.unwrap();
+                prop_assert_eq!(container.containers.len(), 1);
+                prop_assert_eq!(container.containers[0].key, key);
+                union |= container;
+                count += 1;
+            }
+            prop_assert_eq!(count, bitmap.containers.len());
+            prop_assert_eq!(union, bitmap);
+        }
+    }
+
+    #[test]
+    fn iter_errors() {
+        let bitmap: RoaringBitmap = (0..10).chain(100_000..100_010).collect();
+        let bytes = bitmap.serialize_to_vec();
+
+        let mut iter = SerializedIter::new(&bytes[..bytes.len() - 1]).unwrap();
+        assert_eq!(
+            iter.by_ref().take(10).map(Result::unwrap).collect::<Vec<_>>(),
+            bitmap.iter().take(10).collect::<Vec<_>>()
+        );
+        match iter.next() {
+            Some(Err(DeserializeError::Truncated { key: Some(1), .. })) => (),
+            result => panic!("unexpected result {:?}", result),
+        }
+        assert!(iter.next().is_none());
+
+        // Two containers with the keys 1 and 0
+        let unsorted = [0x3b, 0x30, 1, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 5, 0, 5, 0];
+        let mut iter = SerializedIter::new(&unsorted[..]).unwrap();
+        assert_eq!(iter.next().unwrap().unwrap(), 65541);
+        match iter.next() {
+            Some(Err(DeserializeError::UnsortedKeys { key: 0, .. })) => (),
+            result => panic!("unexpected result {:?}", result),
+        }
+        assert!(iter.next().is_none());
+
+        assert!(SerializedIter::new(&[0, 0, 0, 0][..]).is_err());
+    }
+}
@@ -0,0 +1,275 @@
+use std::ops::RangeBounds;
+
+use crate::bitmap::util;
+use crate::bitmap::view::{partition_point, HeaderView, StoreView};
+use crate::DeserializeError;
+
+/// A bitmap serialized in [the standard Roaring on-disk format][format], queried in place.
+///
+/// Unlike a [`RoaringBitmapView`](crate::RoaringBitmapView), creating it only reads the
+/// fixed-size start of the header. Each query binary-searches the container descriptions and
+/// decodes at most the containers holding the bounds of the queried values, which are found
+/// with the offset table of the format.
+///
+/// The layout of a container is checked when a query reads it, the queries are memory safe
+/// but will return meaningless results if the bytes are not a valid bitmap.
+///
+/// [format]: https://github.com/RoaringBitmap/RoaringFormatSpec
+///
+/// # Examples
+///
+/// ```rust
+/// use roaring::{RoaringBitmap, SerializedBitmap};
+///
+/// let rb: RoaringBitmap = (1..4).chain(100_000..200_000).collect();
+/// let bytes = rb.serialize_to_vec();
+///
+/// let serialized = SerializedBitmap::from_bytes(&bytes).unwrap();
+/// assert_eq!(serialized.contains(3).unwrap(), true);
+/// assert_eq!(serialized.contains(4).unwrap(), false);
+/// assert_eq!(serialized.rank(100_000).unwrap(), 4);
+/// assert_eq!(serialized.range_cardinality(2..150_000).unwrap(), 50_002);
+/// ```
+#[derive(Clone, Copy)]
+pub struct SerializedBitmap<'a> {
+    bytes: &'a [u8],
+    header: HeaderView<'a>,
+}
+
+impl<'a> SerializedBitmap<'a> {
+    /// Reads the header of a bitmap serialized in [the standard Roaring on-disk format][format]
+    /// at the start of `bytes`, as written by [`RoaringBitmap::serialize_into`].
+    ///
+    /// [format]: https://github.com/RoaringBitmap/RoaringFormatSpec
+    /// [`RoaringBitmap::serialize_into`]: crate::RoaringBitmap::serialize_into
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<SerializedBitmap<'a>, DeserializeError> {
+        Ok(SerializedBitmap { bytes, header: HeaderView::parse(bytes)? })
+    }
+
+    /// Returns `true` if this set contains the specified integer.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{RoaringBitmap, SerializedBitmap};
+    ///
+    /// let bytes = RoaringBitmap::from([1, 2, 3]).serialize_to_vec();
+    /// let serialized = SerializedBitmap::from_bytes(&bytes).unwrap();
+    ///
+    /// assert_eq!(serialized.contains(0).unwrap(), false);
+    /// assert_eq!(serialized.contains(1).unwrap(), true);
+    /// assert_eq!(serialized.contains(100).unwrap(), false);
+    /// ```
+    pub fn contains(&self, value: u32) -> Result<bool, DeserializeError> {
+        let (key, index) = util::split(value);
+        match self.find(key) {
+            Ok(i) => Ok(self.store(i)?.contains(index)),
+            Err(_) => Ok(false),
+        }
+    }
+
+    /// Returns the number of integers that are <= value. rank(u32::MAX) == len()
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{RoaringBitmap, SerializedBitmap};
+    ///
+    /// let bytes = RoaringBitmap::from([3, 4]).serialize_to_vec();
+    /// let serialized = SerializedBitmap::from_bytes(&bytes).unwrap();
+    ///
+    /// assert_eq!(serialized.rank(0).unwrap(), 0);
+    /// assert_eq!(serialized.rank(3).unwrap(), 1);
+    /// assert_eq!(serialized.rank(10).unwrap(), 2);
+    /// ```
+    pub fn rank(&self, value: u32) -> Result<u64, DeserializeError> {
+        let (key, index) = util::split(value);
+        match self.find(key) {
+            Ok(i) => Ok(self.len_before(i) + self.store(i)?.rank(index)),
+            Err(i) => Ok(self.len_before(i)),
+        }
+    }
+
+    /// Returns the number of integers of this set that are in `range`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{RoaringBitmap, SerializedBitmap};
+    ///
+    /// let rb: RoaringBitmap = (10..20).chain(100_000..200_000).collect();
+    /// let bytes = rb.serialize_to_vec();
+    /// let serialized = SerializedBitmap::from_bytes(&bytes).unwrap();
+    ///
+    /// assert_eq!(serialized.range_cardinality(..).unwrap(), 100_010);
+    /// assert_eq!(serialized.range_cardinality(15..100_005).unwrap(), 10);
+    /// assert_eq!(serialized.range_cardinality(20..100_000).unwrap(), 0);
+    /// ```
+    pub fn range_cardinality<R>(&self, range: R) -> Result<u64, DeserializeError>
+    where
+        R: RangeBounds<u32>,
+    {
+        let (start, end) = match util::convert_range_to_inclusive(range) {
+            Some(range) => (*range.start(), *range.end()),
+            None => return Ok(0),
+        };
+        let (start_key, start_index) = util::split(start);
+        let (end_key, end_index) = util::split(end);
+
+        let first = partition_point(self.size(), |i| self.key(i) < start_key);
+        let last = partition_point(self.size(), |i| self.key(i) <= end_key);
+
+        let mut cardinality = 0;
+        for i in first..last {
+            let key = self.key(i);
+            if key != start_key && key != end_key {
+                cardinality += self.len_at(i);
+                continue;
+            }
+
+            // Only the containers holding the bounds are partially in the range
+            let store = self.store(i)?;
+            let low = if key == start_key { start_index } else { 0 };
+            let high = if key == end_key { end_index } else { u16::MAX };
+            let below = match low.checked_sub(1) {
+                Some(below) => store.rank(below),
+                None => 0,
+            };
+            cardinality += store.rank(high).saturating_sub(below);
+        }
+        Ok(cardinality)
+    }
+
+    /// Returns the number of distinct integers in the set, from the container descriptions.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{RoaringBitmap, SerializedBitmap};
+    ///
+    /// let bytes = RoaringBitmap::from([1, 2, 3]).serialize_to_vec();
+    /// let serialized = SerializedBitmap::from_bytes(&bytes).unwrap();
+    ///
+    /// assert_eq!(serialized.len(), 3);
+    /// ```
+    pub fn len(&self) -> u64 {
+        self.len_before(self.size())
+    }
+
+    /// Returns `true` if there are no integers in this set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{RoaringBitmap, SerializedBitmap};
+    ///
+    /// let bytes = RoaringBitmap::new().serialize_to_vec();
+    /// let serialized = SerializedBitmap::from_bytes(&bytes).unwrap();
+    ///
+    /// assert_eq!(serialized.is_empty(), true);
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.size() == 0
+    }
+
+    /// Returns the number of containers.
+    fn size(&self) -> usize {
+        self.header.size()
+    }
+
+    fn key(&self, i: usize) -> u16 {
+        self.header.key(i)
+    }
+
+    fn len_at(&self, i: usize) -> u64 {
+        self.header.len(i)
+    }
+
+    fn len_before(&self, i: usize) -> u64 {
+        (0..i).map(|i| self.len_at(i)).sum()
+    }
+
+    /// Binary-searches the container descriptions for `key`.
+    fn find(&self, key: u16) -> Result<usize, usize> {
+        let i = partition_point(self.size(), |i| self.key(i) < key);
+        if i < self.size() && self.key(i) == key {
+            Ok(i)
+        } else {
+            Err(i)
+        }
+    }
+
+    /// Decodes the layout of the `i`th container.
+    fn store(&self, i: usize) -> Result<StoreView<'a>, DeserializeError> {
+        let read = |j: usize, position: usize| {
+            let truncated =
+                || DeserializeError::Truncated { key: Some(self.key(j)), offset: position as u64 };
+            let mut data = self.bytes.get(position..).ok_or_else(truncated)?;
+            let store = StoreView::read(&mut data, self.header.is_run(j), self.len_at(j))
+                .ok_or_else(truncated)?;
+            Ok((store, self.bytes.len() - data.len()))
+        };
+
+        let position = match self.header.offset(i) {
+            Some(position) => position,
+            None => {
+                // Without offsets the containers are laid out one after the other, this only
+                // happens for fewer than 4 containers
+                let mut position = self.header.data_start;
+                for j in 0..i {
+                    position = read(j, position)?.1;
+                }
+                position
+            }
+        };
+
+        read(i, position).map(|(store, _)| store)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SerializedBitmap;
+    use crate::RoaringBitmap;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn lookup_queries(
+            mut bitmap in RoaringBitmap::arbitrary(),
+            optimize in any::<bool>(),
+            values in proptest::collection::vec(0u32..=262143, 64),
+            lens in proptest::collection::vec(0u32..=131072, 64),
+        ) {
+            if optimize {
+                bitmap.run_optimize();
+            }
+            let bytes = bitmap.serialize_to_vec();
+            let serialized = SerializedBitmap::from_bytes(&bytes).unwrap();
+
+            prop_assert_eq!(serialized.len(), bitmap.len());
+            prop_assert_eq!(serialized.is_empty(), bitmap.is_empty());
+            for (value, len) in values.into_iter().zip(lens) {
+                prop_assert_eq!(serialized.contains(value).unwrap(), bitmap.contains(value));
+                prop_assert_eq!(serialized.rank(value).unwrap(), bitmap.rank(value));
+                let range = value..=value.saturating_add(len);
+                prop_assert_eq!(
+                    serialized.range_cardinality(range.clone()).unwrap(),
+                    bitmap.range_cardinality(range)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn lookup_out_of_bounds() {
+        let bitmap: RoaringBitmap = (0..4).map(|key| key << 16).collect();
+        let mut bytes = bitmap.serialize_to_vec();
+        bytes.truncate(bytes.len() - 1);
+        let serialized = SerializedBitmap::from_bytes(&bytes).unwrap();
+
+        assert!(serialized.contains(0).unwrap());
+        assert!(serialized.contains(3 << 16).is_err());
+        assert!(SerializedBitmap::from_bytes(&bytes[..8]).is_err());
+    }
+}
@@ -0,0 +1,213 @@
+use std::io;
+use std::mem;
+
+use super::{SerializedBitmapWriter, SerializedIter};
+use crate::bitmap::container::Container;
+use crate::RoaringBitmap;
+
+impl RoaringBitmap {
+    /// Serializes the union of bitmaps read from `readers` into `writer`, all in [the standard
+    /// Roaring on-disk format][format], without deserializing the bitmaps.
+    ///
+    /// The inputs are merged by container key: only the containers sharing the smallest key
+    /// not merged yet are in memory at once, one per input. The output is written with a
+    /// [`SerializedBitmapWriter`], which requires the writer to be readable and seekable.
+    /// The inputs are checked like with [`RoaringBitmap::deserialize_from`].
+    ///
+    /// [format]: https://github.com/RoaringBitmap/RoaringFormatSpec
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringBitmap;
+    /// use std::io::Cursor;
+    ///
+    /// let a: RoaringBitmap = (1..4).chain(100_000..100_010).collect();
+    /// let b: RoaringBitmap = (3..6).chain(300_000..300_010).collect();
+    /// let (a_bytes, b_bytes) = (a.serialize_to_vec(), b.serialize_to_vec());
+    ///
+    /// let readers = [&a_bytes[..], &b_bytes[..]];
+    /// let output = RoaringBitmap::serialize_union_into(readers, Cursor::new(Vec::new())).unwrap();
+    /// let union = RoaringBitmap::deserialize_from(&output.into_inner()[..]).unwrap();
+    ///
+    /// assert_eq!(union, a | b);
+    /// ```
+    pub fn serialize_union_into<I, R, W>(readers: I, writer: W) -> io::Result<W>
+    where
+        I: IntoIterator<Item = R>,
+        R: io::Read,
+        W: io::Read + io::Write + io::Seek,
+    {
+        let mut sources = open_sources(readers)?;
+        let mut output = SerializedBitmapWriter::new(writer)?;
+
+        while let Some(key) = sources.iter().filter_map(Source::key).min() {
+            let mut union: Option<Container> = None;
+            for source in sources.iter_mut().filter(|source| source.key() == Some(key)) {
+                let container = source.take()?;
+                match union.as_mut() {
+                    Some(union) => *union |= container,
+                    None => union = Some(container),
+                }
+            }
+            // At least one source had a container with this key
+            output.push_container(union.unwrap())?;
+        }
+
+        output.finish()
+    }
+
+    /// Serializes the intersection of bitmaps read from `readers` into `writer`, all in [the
+    /// standard Roaring on-disk format][format], without deserializing the bitmaps.
+    ///
+    /// The inputs are merged by container key like with
+    /// [`RoaringBitmap::serialize_union_into`], the containers of an input whose key is not in
+    /// all the other inputs are read and dropped. The intersection of no bitmaps is empty.
+    ///
+    /// [format]: https://github.com/RoaringBitmap/RoaringFormatSpec
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringBitmap;
+    /// use std::io::Cursor;
+    ///
+    /// let a: RoaringBitmap = (1..4).chain(100_000..100_010).collect();
+    /// let b: RoaringBitmap = (3..6).chain(300_000..300_010).collect();
+    /// let (a_bytes, b_bytes) = (a.serialize_to_vec(), b.serialize_to_vec());
+    ///
+    /// let readers = [&a_bytes[..], &b_bytes[..]];
+    /// let writer = Cursor::new(Vec::new());
+    /// let output = RoaringBitmap::serialize_intersection_into(readers, writer).unwrap();
+    /// let intersection = RoaringBitmap::deserialize_from(&output.into_inner()[..]).unwrap();
+    ///
+    /// assert_eq!(intersection, a & b);
+    /// ```
+    pub fn serialize_intersection_into<I, R, W>(readers: I, writer: W) -> io::Result<W>
+    where
+        I: IntoIterator<Item = R>,
+        R: io::Read,
+        W: io::Read + io::Write + io::Seek,
+    {
+        let mut sources = open_sources(readers)?;
+        let mut output = SerializedBitmapWriter::new(writer)?;
+        if sources.is_empty() {
+            return output.finish();
+        }
+
+        loop {
+            // The intersection ends with the first exhausted source
+            let mut key = 0;
+            for source in &sources {
+                match source.key() {
+                    Some(k) => key = key.max(k),
+                    None => return output.finish(),
+                }
+            }
+
+            // Skip the containers that can't be in every source
+            let mut aligned = true;
+            for source in &mut sources {
+                while source.key().map_or(false, |k| k < key) {
+                    source.take()?;
+                }
+                aligned &= source.key() == Some(key);
+            }
+            if !aligned {
+                continue;
+            }
+
+            let (first, rest) = sources.split_first_mut().unwrap();
+            let mut intersection = first.take()?;
+            for source in rest {
+                intersection &= source.take()?;
+            }
+            if intersection.len() > 0 {
+                output.push_container(intersection)?;
+            }
+        }
+    }
+}
+
+/// An input of a merge, with its container of smallest key not merged yet.
+struct Source<R> {
+    iter: SerializedIter<R>,
+    next: Option<Container>,
+}
+
+impl<R: io::Read> Source<R> {
+    fn key(&self) -> Option<u16> {
+        self.next.as_ref().map(|container| container.key)
+    }
+
+    /// Returns the next container of this source and reads the one after it.
+    fn take(&mut self) -> io::Result<Container> {
+        let following = self.iter.read_container().transpose()?;
+        // Only called when there is a next container
+        Ok(mem::replace(&mut self.next, following).unwrap())
+    }
+}
+
+fn open_sources<I, R>(readers: I) -> io::Result<Vec<Source<R>>>
+where
+    I: IntoIterator<Item = R>,
+    R: io::Read,
+{
+    readers
+        .into_iter()
+        .map(|reader| {
+            let mut iter = SerializedIter::new(reader)?;
+            let next = iter.read_container().transpose()?;
+            Ok(Source { iter, next })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::RoaringBitmap;
+    use proptest::prelude::*;
+    use std::io::Cursor;
+
+    proptest! {
+        #[test]
+        fn merge_matches_ops(
+            mut a in RoaringBitmap::arbitrary(),
+            b in RoaringBitmap::arbitrary(),
+            c in RoaringBitmap::arbitrary(),
+        ) {
+            a.run_optimize();
+            let inputs: Vec<Vec<u8>> = [&a, &b, &c].iter().map(|bitmap| bitmap.serialize_to_vec()).collect();
+            let readers = || inputs.iter().map(|bytes| &bytes[..]);
+
+            let union = RoaringBitmap::serialize_union_into(readers(), Cursor::new(Vec::new()));
+            let union = RoaringBitmap::deserialize_from(&union.unwrap().into_inner()[..]).unwrap();
+            prop_assert!(union.validate().is_ok());
+            prop_assert_eq!(union, &(&a | &b) | &c);
+
+            let intersection =
+                RoaringBitmap::serialize_intersection_into(readers(), Cursor::new(Vec::new()));
+            let intersection =
+                RoaringBitmap::deserialize_from(&intersection.unwrap().into_inner()[..]).unwrap();
+            prop_assert!(intersection.validate().is_ok());
+            prop_assert_eq!(intersection, &(&a & &b) & &c);
+        }
+    }
+
+    #[test]
+    fn merge_edge_cases() {
+        let none: [&[u8]; 0] = [];
+        let empty = RoaringBitmap::new().serialize_to_vec();
+        for output in [
+            RoaringBitmap::serialize_union_into(none, Cursor::new(Vec::new())),
+            RoaringBitmap::serialize_intersection_into(none, Cursor::new(Vec::new())),
+        ] {
+            assert_eq!(output.unwrap().into_inner(), empty);
+        }
+
+        let bytes = RoaringBitmap::from([1, 100_000]).serialize_to_vec();
+        let truncated = &bytes[..bytes.len() - 1];
+        let writer = Cursor::new(Vec::new());
+        assert!(RoaringBitmap::serialize_union_into([&bytes[..], truncated], writer).is_err());
+    }
+}
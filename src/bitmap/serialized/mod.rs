@@ -0,0 +1,10 @@
+mod info;
+mod iter;
+mod lookup;
+mod merge;
+mod writer;
+
+pub use self::info::{ContainerInfo, ContainerKind, SerializedBitmapInfo};
+pub use self::iter::SerializedIter;
+pub use self::lookup::SerializedBitmap;
+pub use self::writer::SerializedBitmapWriter;
@@ -0,0 +1,207 @@
+use std::io::{self, SeekFrom};
+
+use crate::bitmap::container::Container;
+use crate::bitmap::serialization::{
+    header_size, serialize_header_into, serialize_store_into, Descriptor,
+};
+use crate::bitmap::util;
+
+/// The size of the chunks in which the containers data is moved after the header.
+const MOVE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Writes a bitmap in [the standard Roaring on-disk format][format] from values pushed in
+/// ascending order, without building the bitmap in memory.
+///
+/// The data of each container is written as soon as the next container starts, only the
+/// description of the containers written so far and the container being filled are kept in
+/// memory. As the header of the format precedes the data and depends on every container,
+/// [`SerializedBitmapWriter::finish`] moves the data after the header once all the values
+/// are pushed, which is why the writer must also be readable and seekable.
+///
+/// The output is the same as the one of [`RoaringBitmap::serialize_into`] for a bitmap built
+/// with the same values and not run optimized, the writer doesn't create run containers.
+///
+/// [format]: https://github.com/RoaringBitmap/RoaringFormatSpec
+/// [`RoaringBitmap::serialize_into`]: crate::RoaringBitmap::serialize_into
+///
+/// # Examples
+///
+/// ```rust
+/// use roaring::{RoaringBitmap, SerializedBitmapWriter};
+/// use std::io::Cursor;
+///
+/// let mut writer = SerializedBitmapWriter::new(Cursor::new(Vec::new())).unwrap();
+/// for value in (0..10).chain(100_000..200_000) {
+///     writer.push(value).unwrap();
+/// }
+/// let bytes = writer.finish().unwrap().into_inner();
+///
+/// let rb = RoaringBitmap::deserialize_from(&bytes[..]).unwrap();
+/// assert_eq!(rb, (0..10).chain(100_000..200_000).collect());
+/// ```
+pub struct SerializedBitmapWriter<W> {
+    writer: W,
+    start: u64,
+    descriptors: Vec<Descriptor>,
+    current: Option<Container>,
+    data_size: u64,
+}
+
+impl<W: io::Read + io::Write + io::Seek> SerializedBitmapWriter<W> {
+    /// Creates a writer of a bitmap starting at the current position of `writer`.
+    pub fn new(mut writer: W) -> io::Result<SerializedBitmapWriter<W>> {
+        let start = writer.stream_position()?;
+        Ok(SerializedBitmapWriter {
+            writer,
+            start,
+            descriptors: Vec::new(),
+            current: None,
+            data_size: 0,
+        })
+    }
+
+    /// Pushes `value` in the bitmap, if it is greater than all the values pushed before.
+    ///
+    /// Returns whether the value was pushed, like [`RoaringBitmap::push`].
+    ///
+    /// [`RoaringBitmap::push`]: crate::RoaringBitmap::push
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::SerializedBitmapWriter;
+    /// use std::io::Cursor;
+    ///
+    /// let mut writer = SerializedBitmapWriter::new(Cursor::new(Vec::new())).unwrap();
+    /// assert!(writer.push(1).unwrap());
+    /// assert!(writer.push(3).unwrap());
+    /// assert!(!writer.push(3).unwrap());
+    /// assert!(!writer.push(2).unwrap());
+    /// assert!(writer.push(5).unwrap());
+    /// ```
+    pub fn push(&mut self, value: u32) -> io::Result<bool> {
+        let (key, index) = util::split(value);
+
+        match self.current.as_mut() {
+            Some(container) if container.key == key => Ok(container.push(index)),
+            Some(container) if container.key > key => Ok(false),
+            _otherwise => {
+                self.write_current()?;
+                let mut container = Container::new(key);
+                container.push(index);
+                self.current = Some(container);
+                Ok(true)
+            }
+        }
+    }
+
+    /// Returns the number of values pushed so far.
+    pub fn len(&self) -> u64 {
+        let written: u64 = self.descriptors.iter().map(|descriptor| descriptor.len).sum();
+        written + self.current.as_ref().map_or(0, |container| container.len())
+    }
+
+    /// Returns `true` if no value was pushed yet.
+    pub fn is_empty(&self) -> bool {
+        self.descriptors.is_empty() && self.current.is_none()
+    }
+
+    /// Writes the last container and the header, and returns the writer positioned at the end
+    /// of the bitmap. The bitmap is incomplete until this method is called.
+    ///
+    /// The data of the containers is moved after the header in chunks of 64 KiB, by reading
+    /// it back from the writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_current()?;
+        let header_size = header_size(self.descriptors.iter().copied()) as u64;
+
+        // Move the data starting from the end, so that it is not overwritten before being read
+        let mut buffer = vec![0; MOVE_CHUNK_SIZE];
+        let mut remaining = self.data_size;
+        while remaining > 0 {
+            let len = remaining.min(MOVE_CHUNK_SIZE as u64);
+            remaining -= len;
+            let chunk = &mut buffer[..len as usize];
+            self.writer.seek(SeekFrom::Start(self.start + remaining))?;
+            self.writer.read_exact(chunk)?;
+            self.writer.seek(SeekFrom::Start(self.start + header_size + remaining))?;
+            self.writer.write_all(chunk)?;
+        }
+
+        self.writer.seek(SeekFrom::Start(self.start))?;
+        serialize_header_into(self.descriptors.iter().copied(), &mut self.writer)?;
+        self.writer.seek(SeekFrom::Start(self.start + header_size + self.data_size))?;
+        Ok(self.writer)
+    }
+
+    /// Pushes a whole non-empty container, its key must be greater than the keys of the values
+    /// pushed before.
+    pub(crate) fn push_container(&mut self, container: Container) -> io::Result<()> {
+        debug_assert!(container.len() > 0);
+        debug_assert!(self.current.as_ref().map_or(true, |current| current.key < container.key));
+        self.write_current()?;
+        self.current = Some(container);
+        Ok(())
+    }
+
+    /// Writes the data of the container being filled.
+    fn write_current(&mut self) -> io::Result<()> {
+        if let Some(container) = self.current.take() {
+            let descriptor = Descriptor::of(&container);
+            serialize_store_into(&container.store, &mut self.writer)?;
+            self.data_size += descriptor.size as u64;
+            self.descriptors.push(descriptor);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SerializedBitmapWriter;
+    use crate::RoaringBitmap;
+    use proptest::prelude::*;
+    use std::io::{Cursor, Seek, SeekFrom};
+
+    proptest! {
+        #[test]
+        fn writer_matches_serialize_into(
+            bitmap in RoaringBitmap::arbitrary(),
+            prefix in proptest::collection::vec(any::<u8>(), 0..16),
+        ) {
+            let mut cursor = Cursor::new(prefix.clone());
+            cursor.seek(SeekFrom::End(0)).unwrap();
+            let mut writer = SerializedBitmapWriter::new(cursor).unwrap();
+            for value in &bitmap {
+                prop_assert!(writer.push(value).unwrap());
+            }
+            prop_assert_eq!(writer.len(), bitmap.len());
+            let mut cursor = writer.finish().unwrap();
+            let end = cursor.position() as usize;
+            let bytes = cursor.get_mut();
+
+            prop_assert_eq!(end, bytes.len());
+            prop_assert_eq!(&bytes[..prefix.len()], &prefix[..]);
+            // Pushing the values never creates run containers
+            let expected = bitmap.iter().collect::<RoaringBitmap>().serialize_to_vec();
+            prop_assert_eq!(&bytes[prefix.len()..], &expected[..]);
+        }
+    }
+
+    #[test]
+    fn writer_rejects_unsorted() {
+        let mut writer = SerializedBitmapWriter::new(Cursor::new(Vec::new())).unwrap();
+        assert!(writer.is_empty());
+        assert!(writer.push(70_000).unwrap());
+        assert!(!writer.push(70_000).unwrap());
+        assert!(!writer.push(5).unwrap());
+        assert!(writer.push(u32::MAX).unwrap());
+        assert_eq!(writer.len(), 2);
+
+        let bytes = writer.finish().unwrap().into_inner();
+        assert_eq!(
+            RoaringBitmap::deserialize_from(&bytes[..]).unwrap(),
+            RoaringBitmap::from([70_000, u32::MAX])
+        );
+    }
+}
@@ -10,12 +10,24 @@ use std::fmt::{Display, Formatter};
 use std::ops::{BitAnd, BitAndAssign, BitOr, BitXor, RangeInclusive, Sub, SubAssign};
 
 use super::bitmap_store::{bit, key, BitmapStore, BITMAP_LENGTH};
+use super::RunStore;
+use crate::bitmap::serialization::StoreError;
 
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Eq, PartialEq)]
 pub struct ArrayStore {
     vec: Vec<u16>,
 }
 
+impl Clone for ArrayStore {
+    fn clone(&self) -> Self {
+        ArrayStore { vec: self.vec.clone() }
+    }
+
+    fn clone_from(&mut self, other: &Self) {
+        self.vec.clone_from(&other.vec);
+    }
+}
+
 impl ArrayStore {
     pub fn new() -> ArrayStore {
         ArrayStore { vec: vec![] }
@@ -182,6 +194,19 @@ impl ArrayStore {
         self.vec.len() as u64
     }
 
+    /// Returns the number of bytes this store uses in the serialized format.
+    pub fn serialized_byte_size(len: usize) -> usize {
+        2 * len
+    }
+
+    /// Returns the number of runs of consecutive values in this store.
+    pub fn run_amount(&self) -> u64 {
+        match self.vec.first() {
+            None => 0,
+            Some(_) => 1 + self.vec.windows(2).filter(|pair| pair[0] + 1 != pair[1]).count() as u64,
+        }
+    }
+
     pub fn min(&self) -> Option<u16> {
         self.vec.first().copied()
     }
@@ -201,7 +226,7 @@ impl ArrayStore {
         self.vec.get(n as usize).cloned()
     }
 
-    pub fn iter(&self) -> std::slice::Iter<u16> {
+    pub fn iter(&self) -> std::slice::Iter<'_, u16> {
         self.vec.iter()
     }
 
@@ -213,6 +238,10 @@ impl ArrayStore {
         &self.vec
     }
 
+    pub fn into_vec(self) -> Vec<u16> {
+        self.vec
+    }
+
     /// Retains only the elements specified by the predicate.
     pub fn retain(&mut self, mut f: impl FnMut(u16) -> bool) {
         // Idea to avoid branching from "Engineering Fast Indexes for Big Data
@@ -231,6 +260,30 @@ impl ArrayStore {
     }
 }
 
+impl ArrayStore {
+    /// Checks that the values are sorted and deduplicated.
+    pub fn validate(&self) -> Result<(), Error> {
+        let mut iter = self.vec.iter().enumerate();
+        if let Some((_, mut prev)) = iter.next() {
+            for (i, cur) in iter {
+                match cur.cmp(prev) {
+                    Ordering::Less => return Err(Error { index: i, kind: ErrorKind::OutOfOrder }),
+                    Ordering::Equal => return Err(Error { index: i, kind: ErrorKind::Duplicate }),
+                    Ordering::Greater => (),
+                }
+                prev = cur;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sorts and deduplicates the values.
+    pub fn normalize(&mut self) {
+        self.vec.sort_unstable();
+        self.vec.dedup();
+    }
+}
+
 impl Default for ArrayStore {
     fn default() -> Self {
         ArrayStore::new()
@@ -264,23 +317,15 @@ impl Display for Error {
 
 impl std::error::Error for Error {}
 
+impl StoreError for Error {}
+
 impl TryFrom<Vec<u16>> for ArrayStore {
     type Error = Error;
 
     fn try_from(value: Vec<u16>) -> Result<Self, Self::Error> {
-        let mut iter = value.iter().enumerate();
-        if let Some((_, mut prev)) = iter.next() {
-            for (i, cur) in iter {
-                match cur.cmp(prev) {
-                    Ordering::Less => return Err(Error { index: i, kind: ErrorKind::OutOfOrder }),
-                    Ordering::Equal => return Err(Error { index: i, kind: ErrorKind::Duplicate }),
-                    Ordering::Greater => (),
-                }
-                prev = cur;
-            }
-        }
-
-        Ok(ArrayStore { vec: value })
+        let store = ArrayStore { vec: value };
+        store.validate()?;
+        Ok(store)
     }
 }
 
@@ -338,6 +383,12 @@ impl BitAndAssign<&BitmapStore> for ArrayStore {
     }
 }
 
+impl BitAndAssign<&RunStore> for ArrayStore {
+    fn bitand_assign(&mut self, rhs: &RunStore) {
+        self.retain(|x| rhs.contains(x));
+    }
+}
+
 impl Sub<Self> for &ArrayStore {
     type Output = ArrayStore;
 
@@ -377,6 +428,12 @@ impl SubAssign<&BitmapStore> for ArrayStore {
     }
 }
 
+impl SubAssign<&RunStore> for ArrayStore {
+    fn sub_assign(&mut self, rhs: &RunStore) {
+        self.retain(|x| !rhs.contains(x));
+    }
+}
+
 impl BitXor<Self> for &ArrayStore {
     type Output = ArrayStore;
 
@@ -401,6 +458,7 @@ mod tests {
         match s {
             Store::Array(vec) => vec.vec,
             Store::Bitmap(bits) => bits.to_array_store().vec,
+            Store::Run(runs) => runs.to_array_store().vec,
         }
     }
 
@@ -408,6 +466,7 @@ mod tests {
         match s {
             Store::Array(vec) => Store::Bitmap(vec.to_bitmap_store()),
             Store::Bitmap(..) => s,
+            Store::Run(runs) => Store::Bitmap(runs.to_bitmap_store()),
         }
     }
 
@@ -558,4 +617,13 @@ mod tests {
 
         assert_eq!(into_vec(store), want);
     }
+
+    #[test]
+    fn test_array_normalize() {
+        let mut store = ArrayStore { vec: vec![5, 1, 5, 3, 1] };
+        assert!(store.validate().is_err());
+        store.normalize();
+        assert!(store.validate().is_ok());
+        assert_eq!(store.vec, vec![1, 3, 5]);
+    }
 }
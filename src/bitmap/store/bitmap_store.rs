@@ -3,16 +3,28 @@ use std::cmp::Ordering;
 use std::fmt::{Display, Formatter};
 use std::ops::{BitAndAssign, BitOrAssign, BitXorAssign, RangeInclusive, SubAssign};
 
-use super::ArrayStore;
+use super::{ArrayStore, RunStore};
+use crate::bitmap::serialization::StoreError;
 
 pub const BITMAP_LENGTH: usize = 1024;
 
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Eq, PartialEq)]
 pub struct BitmapStore {
     len: u64,
     bits: Box<[u64; BITMAP_LENGTH]>,
 }
 
+impl Clone for BitmapStore {
+    fn clone(&self) -> Self {
+        BitmapStore { len: self.len, bits: self.bits.clone() }
+    }
+
+    fn clone_from(&mut self, other: &Self) {
+        self.len = other.len;
+        self.bits.copy_from_slice(&other.bits[..]);
+    }
+}
+
 impl BitmapStore {
     pub fn new() -> BitmapStore {
         BitmapStore { len: 0, bits: Box::new([0; BITMAP_LENGTH]) }
@@ -23,14 +35,30 @@ impl BitmapStore {
     }
 
     pub fn try_from(len: u64, bits: Box<[u64; BITMAP_LENGTH]>) -> Result<BitmapStore, Error> {
-        let actual_len = bits.iter().map(|v| v.count_ones() as u64).sum();
-        if len != actual_len {
-            Err(Error { kind: ErrorKind::Cardinality { expected: len, actual: actual_len } })
+        let store = BitmapStore { len, bits };
+        store.validate()?;
+        Ok(store)
+    }
+
+    /// Checks that the recorded cardinality is the number of bits set.
+    pub fn validate(&self) -> Result<(), Error> {
+        let actual = self.count_ones();
+        if self.len != actual {
+            Err(Error { kind: ErrorKind::Cardinality { expected: self.len, actual } })
         } else {
-            Ok(BitmapStore { len, bits })
+            Ok(())
         }
     }
 
+    /// Recomputes the recorded cardinality from the bits.
+    pub fn normalize(&mut self) {
+        self.len = self.count_ones();
+    }
+
+    fn count_ones(&self) -> u64 {
+        self.bits.iter().map(|v| v.count_ones() as u64).sum()
+    }
+
     ///
     /// Create a new BitmapStore from a given len and bits array
     /// It is up to the caller to ensure len == cardinality of bits
@@ -236,6 +264,40 @@ impl BitmapStore {
         self.len
     }
 
+    /// Returns the number of bytes this store uses in the serialized format.
+    pub fn serialized_byte_size() -> usize {
+        8 * BITMAP_LENGTH
+    }
+
+    /// Returns the number of runs of consecutive values in this store.
+    pub fn run_amount(&self) -> u64 {
+        let mut carry = 0;
+        self.bits
+            .iter()
+            .map(|&word| {
+                // A run starts on a set bit whose lower neighbour is unset
+                let starts = word & !((word << 1) | carry);
+                carry = word >> 63;
+                starts.count_ones() as u64
+            })
+            .sum()
+    }
+
+    /// Returns the number of values in the range.
+    pub fn range_len(&self, range: RangeInclusive<u16>) -> u64 {
+        range_masks(range).map(|(key, mask)| (self.bits[key] & mask).count_ones() as u64).sum()
+    }
+
+    /// Flips every bit in the range.
+    pub fn flip_range(&mut self, range: RangeInclusive<u16>) {
+        for (key, mask) in range_masks(range) {
+            let old_w = self.bits[key];
+            let new_w = old_w ^ mask;
+            self.len = self.len + new_w.count_ones() as u64 - old_w.count_ones() as u64;
+            self.bits[key] = new_w;
+        }
+    }
+
     pub fn min(&self) -> Option<u16> {
         self.bits
             .iter()
@@ -291,6 +353,10 @@ impl BitmapStore {
             .sum::<u64>()
     }
 
+    pub fn intersection_len_run(&self, other: &RunStore) -> u64 {
+        other.as_slice().iter().map(|run| self.range_len(run.start..=run.end)).sum()
+    }
+
     pub fn iter(&self) -> BitmapIter<&[u64; BITMAP_LENGTH]> {
         BitmapIter::new(&self.bits)
     }
@@ -302,6 +368,10 @@ impl BitmapStore {
     pub fn as_array(&self) -> &[u64; BITMAP_LENGTH] {
         &self.bits
     }
+
+    pub fn into_bits(self) -> Box<[u64; BITMAP_LENGTH]> {
+        self.bits
+    }
 }
 
 // this can be done in 3 instructions on x86-64 with bmi2 with: tzcnt(pdep(1 << rank, value))
@@ -342,6 +412,14 @@ impl Display for Error {
 
 impl std::error::Error for Error {}
 
+impl StoreError for Error {
+    fn cardinality(&self) -> Option<(u64, u64)> {
+        match self.kind {
+            ErrorKind::Cardinality { expected, actual } => Some((expected, actual)),
+        }
+    }
+}
+
 pub struct BitmapIter<B: Borrow<[u64; BITMAP_LENGTH]>> {
     key: usize,
     value: u64,
@@ -408,6 +486,22 @@ impl<B: Borrow<[u64; BITMAP_LENGTH]>> DoubleEndedIterator for BitmapIter<B> {
     }
 }
 
+/// Returns the words covered by the range along with the mask of the bits in the range.
+fn range_masks(range: RangeInclusive<u16>) -> impl Iterator<Item = (usize, u64)> {
+    let (start_key, start_bit) = (key(*range.start()), bit(*range.start()));
+    let (end_key, end_bit) = (key(*range.end()), bit(*range.end()));
+    (start_key..=end_key).map(move |key| {
+        let mut mask = u64::MAX;
+        if key == start_key {
+            mask &= u64::MAX << start_bit;
+        }
+        if key == end_key {
+            mask &= u64::MAX >> (63 - end_bit);
+        }
+        (key, mask)
+    })
+}
+
 #[inline]
 pub fn key(index: u16) -> usize {
     index as usize / 64
@@ -445,12 +539,36 @@ impl BitOrAssign<&ArrayStore> for BitmapStore {
     }
 }
 
+impl BitOrAssign<&RunStore> for BitmapStore {
+    fn bitor_assign(&mut self, rhs: &RunStore) {
+        for run in rhs.as_slice() {
+            self.insert_range(run.start..=run.end);
+        }
+    }
+}
+
 impl BitAndAssign<&Self> for BitmapStore {
     fn bitand_assign(&mut self, rhs: &Self) {
         op_bitmaps(self, rhs, BitAndAssign::bitand_assign);
     }
 }
 
+impl BitAndAssign<&RunStore> for BitmapStore {
+    fn bitand_assign(&mut self, rhs: &RunStore) {
+        // Clear the gaps between the runs
+        let mut next = 0u32;
+        for run in rhs.as_slice() {
+            if u32::from(run.start) > next {
+                self.remove_range(next as u16..=run.start - 1);
+            }
+            next = u32::from(run.end) + 1;
+        }
+        if next <= u32::from(u16::MAX) {
+            self.remove_range(next as u16..=u16::MAX);
+        }
+    }
+}
+
 impl SubAssign<&Self> for BitmapStore {
     #[allow(clippy::suspicious_op_assign_impl)]
     fn sub_assign(&mut self, rhs: &Self) {
@@ -471,6 +589,14 @@ impl SubAssign<&ArrayStore> for BitmapStore {
     }
 }
 
+impl SubAssign<&RunStore> for BitmapStore {
+    fn sub_assign(&mut self, rhs: &RunStore) {
+        for run in rhs.as_slice() {
+            self.remove_range(run.start..=run.end);
+        }
+    }
+}
+
 impl BitXorAssign<&Self> for BitmapStore {
     fn bitxor_assign(&mut self, rhs: &Self) {
         op_bitmaps(self, rhs, BitXorAssign::bitxor_assign);
@@ -490,3 +616,11 @@ impl BitXorAssign<&ArrayStore> for BitmapStore {
         self.len = len as u64;
     }
 }
+
+impl BitXorAssign<&RunStore> for BitmapStore {
+    fn bitxor_assign(&mut self, rhs: &RunStore) {
+        for run in rhs.as_slice() {
+            self.flip_range(run.start..=run.end);
+        }
+    }
+}
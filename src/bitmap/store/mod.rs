@@ -1,6 +1,8 @@
 mod array_store;
 mod bitmap_store;
+mod run_store;
 
+use std::error::Error;
 use std::mem;
 use std::ops::{
     BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, RangeInclusive, Sub, SubAssign,
@@ -8,15 +10,36 @@ use std::ops::{
 use std::{slice, vec};
 
 use self::bitmap_store::BITMAP_LENGTH;
-use self::Store::{Array, Bitmap};
+use self::Store::{Array, Bitmap, Run};
 
 pub use self::array_store::ArrayStore;
 pub use self::bitmap_store::{BitmapIter, BitmapStore};
+pub use self::run_store::{Interval, RunIter, RunStore};
 
-#[derive(Clone)]
 pub enum Store {
     Array(ArrayStore),
     Bitmap(BitmapStore),
+    Run(RunStore),
+}
+
+impl Clone for Store {
+    fn clone(&self) -> Self {
+        match self {
+            Array(vec) => Array(vec.clone()),
+            Bitmap(bits) => Bitmap(bits.clone()),
+            Run(runs) => Run(runs.clone()),
+        }
+    }
+
+    /// Reuses the allocation of the store when the other one has the same type.
+    fn clone_from(&mut self, other: &Self) {
+        match (self, other) {
+            (Array(vec1), Array(vec2)) => vec1.clone_from(vec2),
+            (Bitmap(bits1), Bitmap(bits2)) => bits1.clone_from(bits2),
+            (Run(runs1), Run(runs2)) => runs1.clone_from(runs2),
+            (this, other) => *this = other.clone(),
+        }
+    }
 }
 
 pub enum Iter<'a> {
@@ -24,6 +47,8 @@ pub enum Iter<'a> {
     Vec(vec::IntoIter<u16>),
     BitmapBorrowed(BitmapIter<&'a [u64; BITMAP_LENGTH]>),
     BitmapOwned(BitmapIter<Box<[u64; BITMAP_LENGTH]>>),
+    RunBorrowed(RunIter<&'a [Interval]>),
+    RunOwned(RunIter<Vec<Interval>>),
 }
 
 impl Store {
@@ -39,6 +64,7 @@ impl Store {
         match self {
             Array(vec) => vec.insert(index),
             Bitmap(bits) => bits.insert(index),
+            Run(runs) => runs.insert(index),
         }
     }
 
@@ -51,6 +77,7 @@ impl Store {
         match self {
             Array(vec) => vec.insert_range(range),
             Bitmap(bits) => bits.insert_range(range),
+            Run(runs) => runs.insert_range(range),
         }
     }
 
@@ -61,6 +88,7 @@ impl Store {
         match self {
             Array(vec) => vec.push(index),
             Bitmap(bits) => bits.push(index),
+            Run(runs) => runs.push(index),
         }
     }
 
@@ -75,6 +103,7 @@ impl Store {
         match self {
             Array(vec) => vec.push_unchecked(index),
             Bitmap(bits) => bits.push_unchecked(index),
+            Run(runs) => runs.push_unchecked(index),
         }
     }
 
@@ -82,6 +111,7 @@ impl Store {
         match self {
             Array(vec) => vec.remove(index),
             Bitmap(bits) => bits.remove(index),
+            Run(runs) => runs.remove(index),
         }
     }
 
@@ -93,6 +123,7 @@ impl Store {
         match self {
             Array(vec) => vec.remove_range(range),
             Bitmap(bits) => bits.remove_range(range),
+            Run(runs) => runs.remove_range(range),
         }
     }
 
@@ -100,6 +131,7 @@ impl Store {
         match self {
             Array(vec) => vec.contains(index),
             Bitmap(bits) => bits.contains(index),
+            Run(runs) => runs.contains(index),
         }
     }
 
@@ -107,6 +139,7 @@ impl Store {
         match self {
             Array(vec) => vec.contains_range(range),
             Bitmap(bits) => bits.contains_range(range),
+            Run(runs) => runs.contains_range(range),
         }
     }
 
@@ -121,6 +154,13 @@ impl Store {
             (Array(vec), Bitmap(bits)) | (Bitmap(bits), Array(vec)) => {
                 vec.iter().all(|&i| !bits.contains(i))
             }
+            (Run(runs1), Run(runs2)) => runs1.is_disjoint(runs2),
+            (Array(vec), Run(runs)) | (Run(runs), Array(vec)) => {
+                vec.iter().all(|&i| !runs.contains(i))
+            }
+            (Bitmap(bits), Run(runs)) | (Run(runs), Bitmap(bits)) => {
+                bits.intersection_len_run(runs) == 0
+            }
         }
     }
 
@@ -130,6 +170,13 @@ impl Store {
             (Bitmap(bits1), Bitmap(bits2)) => bits1.is_subset(bits2),
             (Array(vec), Bitmap(bits)) => vec.iter().all(|&i| bits.contains(i)),
             (Bitmap(..), &Array(..)) => false,
+            (Run(runs1), Run(runs2)) => runs1.is_subset(runs2),
+            (Array(vec), Run(runs)) => vec.iter().all(|&i| runs.contains(i)),
+            (Bitmap(bits), Run(runs)) => bits.intersection_len_run(runs) == bits.len(),
+            (Run(runs), Array(vec)) => runs.iter().all(|i| vec.contains(i)),
+            (Run(runs), Bitmap(bits)) => {
+                runs.as_slice().iter().all(|run| bits.contains_range(run.start..=run.end))
+            }
         }
     }
 
@@ -139,6 +186,30 @@ impl Store {
             (Bitmap(bits1), Bitmap(bits2)) => bits1.intersection_len_bitmap(bits2),
             (Array(vec), Bitmap(bits)) => bits.intersection_len_array(vec),
             (Bitmap(bits), Array(vec)) => bits.intersection_len_array(vec),
+            (Run(runs1), Run(runs2)) => runs1.intersection_len(runs2),
+            (Run(runs), Array(vec)) | (Array(vec), Run(runs)) => runs.intersection_len_array(vec),
+            (Run(runs), Bitmap(bits)) | (Bitmap(bits), Run(runs)) => {
+                bits.intersection_len_run(runs)
+            }
+        }
+    }
+
+    /// Checks the invariants the operations on the store rely on.
+    pub fn validate(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match self {
+            Array(vec) => vec.validate()?,
+            Bitmap(bits) => bits.validate()?,
+            Run(runs) => runs.validate()?,
+        }
+        Ok(())
+    }
+
+    /// Repairs the store so that it upholds its invariants, keeping its values.
+    pub fn normalize(&mut self) {
+        match self {
+            Array(vec) => vec.normalize(),
+            Bitmap(bits) => bits.normalize(),
+            Run(runs) => runs.normalize(),
         }
     }
 
@@ -146,6 +217,7 @@ impl Store {
         match self {
             Array(vec) => vec.len(),
             Bitmap(bits) => bits.len(),
+            Run(runs) => runs.len(),
         }
     }
 
@@ -153,6 +225,7 @@ impl Store {
         match self {
             Array(vec) => vec.min(),
             Bitmap(bits) => bits.min(),
+            Run(runs) => runs.min(),
         }
     }
 
@@ -160,6 +233,7 @@ impl Store {
         match self {
             Array(vec) => vec.max(),
             Bitmap(bits) => bits.max(),
+            Run(runs) => runs.max(),
         }
     }
 
@@ -167,6 +241,7 @@ impl Store {
         match self {
             Array(vec) => vec.rank(index),
             Bitmap(bits) => bits.rank(index),
+            Run(runs) => runs.rank(index),
         }
     }
 
@@ -174,6 +249,7 @@ impl Store {
         match self {
             Array(vec) => vec.select(n),
             Bitmap(bits) => bits.select(n),
+            Run(runs) => runs.select(n),
         }
     }
 
@@ -181,6 +257,7 @@ impl Store {
         match self {
             Array(arr) => Bitmap(arr.to_bitmap_store()),
             Bitmap(_) => self.clone(),
+            Run(runs) => Bitmap(runs.to_bitmap_store()),
         }
     }
 }
@@ -196,18 +273,17 @@ impl BitOr<&Store> for &Store {
 
     fn bitor(self, rhs: &Store) -> Store {
         match (self, rhs) {
-            (&Array(ref vec1), &Array(ref vec2)) => Array(BitOr::bitor(vec1, vec2)),
-            (&Bitmap(..), &Array(..)) => {
-                let mut lhs = self.clone();
-                BitOrAssign::bitor_assign(&mut lhs, rhs);
-                lhs
+            (Array(vec1), Array(vec2)) => Array(BitOr::bitor(vec1, vec2)),
+            (Run(runs1), Run(runs2)) => Run(runs1.union(runs2)),
+            (Run(runs), Array(vec)) | (Array(vec), Run(runs)) => {
+                Run(runs.union(&RunStore::from(vec)))
             }
-            (&Bitmap(..), &Bitmap(..)) => {
+            (&Bitmap(..), _) => {
                 let mut lhs = self.clone();
                 BitOrAssign::bitor_assign(&mut lhs, rhs);
                 lhs
             }
-            (&Array(..), &Bitmap(..)) => {
+            (_, &Bitmap(..)) => {
                 let mut rhs = rhs.clone();
                 BitOrAssign::bitor_assign(&mut rhs, self);
                 rhs
@@ -219,19 +295,25 @@ impl BitOr<&Store> for &Store {
 impl BitOrAssign<Store> for Store {
     fn bitor_assign(&mut self, mut rhs: Store) {
         match (self, &mut rhs) {
-            (&mut Array(ref mut vec1), &mut Array(ref vec2)) => {
+            (Array(vec1), &mut Array(ref vec2)) => {
                 *vec1 = BitOr::bitor(&*vec1, vec2);
             }
-            (&mut Bitmap(ref mut bits1), &mut Array(ref vec2)) => {
+            (Bitmap(bits1), &mut Array(ref vec2)) => {
                 BitOrAssign::bitor_assign(bits1, vec2);
             }
-            (&mut Bitmap(ref mut bits1), &mut Bitmap(ref bits2)) => {
+            (Bitmap(bits1), &mut Bitmap(ref bits2)) => {
                 BitOrAssign::bitor_assign(bits1, bits2);
             }
-            (this @ &mut Array(..), &mut Bitmap(..)) => {
+            (Bitmap(bits1), &mut Run(ref runs2)) => {
+                BitOrAssign::bitor_assign(bits1, runs2);
+            }
+            (this @ &mut Array(..), &mut Bitmap(..)) | (this @ &mut Run(..), &mut Bitmap(..)) => {
                 mem::swap(this, &mut rhs);
                 BitOrAssign::bitor_assign(this, rhs);
             }
+            (this @ &mut Run(..), _) | (this @ &mut Array(..), &mut Run(..)) => {
+                *this = BitOr::bitor(&*this, &rhs);
+            }
         }
     }
 }
@@ -239,21 +321,28 @@ impl BitOrAssign<Store> for Store {
 impl BitOrAssign<&Store> for Store {
     fn bitor_assign(&mut self, rhs: &Store) {
         match (self, rhs) {
-            (&mut Array(ref mut vec1), &Array(ref vec2)) => {
+            (Array(vec1), Array(vec2)) => {
                 let this = mem::take(vec1);
                 *vec1 = BitOr::bitor(&this, vec2);
             }
-            (&mut Bitmap(ref mut bits1), &Array(ref vec2)) => {
+            (Bitmap(bits1), Array(vec2)) => {
                 BitOrAssign::bitor_assign(bits1, vec2);
             }
-            (&mut Bitmap(ref mut bits1), &Bitmap(ref bits2)) => {
+            (Bitmap(bits1), Bitmap(bits2)) => {
                 BitOrAssign::bitor_assign(bits1, bits2);
             }
-            (this @ &mut Array(..), &Bitmap(ref bits2)) => {
+            (Bitmap(bits1), Run(runs2)) => {
+                BitOrAssign::bitor_assign(bits1, runs2);
+            }
+            (this @ &mut Array(..), &Bitmap(ref bits2))
+            | (this @ &mut Run(..), &Bitmap(ref bits2)) => {
                 let mut lhs: Store = Bitmap(bits2.clone());
                 BitOrAssign::bitor_assign(&mut lhs, &*this);
                 *this = lhs;
             }
+            (this @ &mut Run(..), _) | (this @ &mut Array(..), &Run(..)) => {
+                *this = BitOr::bitor(&*this, rhs);
+            }
         }
     }
 }
@@ -263,8 +352,9 @@ impl BitAnd<&Store> for &Store {
 
     fn bitand(self, rhs: &Store) -> Store {
         match (self, rhs) {
-            (&Array(ref vec1), &Array(ref vec2)) => Array(BitAnd::bitand(vec1, vec2)),
-            (&Bitmap(..), &Array(..)) => {
+            (Array(vec1), Array(vec2)) => Array(BitAnd::bitand(vec1, vec2)),
+            (Run(runs1), Run(runs2)) => Run(runs1.intersection(runs2)),
+            (Bitmap(..), Array(..)) | (Run(..), Array(..)) | (Run(..), Bitmap(..)) => {
                 let mut rhs = rhs.clone();
                 BitAndAssign::bitand_assign(&mut rhs, self);
                 rhs
@@ -282,19 +372,28 @@ impl BitAndAssign<Store> for Store {
     #[allow(clippy::suspicious_op_assign_impl)]
     fn bitand_assign(&mut self, mut rhs: Store) {
         match (self, &mut rhs) {
-            (&mut Array(ref mut vec1), &mut Array(ref mut vec2)) => {
+            (Array(vec1), &mut Array(ref mut vec2)) => {
                 if vec2.len() < vec1.len() {
                     mem::swap(vec1, vec2);
                 }
                 BitAndAssign::bitand_assign(vec1, &*vec2);
             }
-            (&mut Bitmap(ref mut bits1), &mut Bitmap(ref bits2)) => {
+            (Bitmap(bits1), &mut Bitmap(ref bits2)) => {
                 BitAndAssign::bitand_assign(bits1, bits2);
             }
-            (&mut Array(ref mut vec1), &mut Bitmap(ref bits2)) => {
+            (Array(vec1), &mut Bitmap(ref bits2)) => {
                 BitAndAssign::bitand_assign(vec1, bits2);
             }
-            (this @ &mut Bitmap(..), &mut Array(..)) => {
+            (Array(vec1), &mut Run(ref runs2)) => {
+                BitAndAssign::bitand_assign(vec1, runs2);
+            }
+            (Bitmap(bits1), &mut Run(ref runs2)) => {
+                BitAndAssign::bitand_assign(bits1, runs2);
+            }
+            (Run(runs1), &mut Run(ref runs2)) => {
+                *runs1 = runs1.intersection(runs2);
+            }
+            (this @ &mut Bitmap(..), &mut Array(..)) | (this @ &mut Run(..), _) => {
                 mem::swap(this, &mut rhs);
                 BitAndAssign::bitand_assign(this, rhs);
             }
@@ -306,7 +405,7 @@ impl BitAndAssign<&Store> for Store {
     #[allow(clippy::suspicious_op_assign_impl)]
     fn bitand_assign(&mut self, rhs: &Store) {
         match (self, rhs) {
-            (&mut Array(ref mut vec1), &Array(ref vec2)) => {
+            (Array(vec1), Array(vec2)) => {
                 let (mut lhs, rhs) = if vec2.len() < vec1.len() {
                     (vec2.clone(), &*vec1)
                 } else {
@@ -316,13 +415,22 @@ impl BitAndAssign<&Store> for Store {
                 BitAndAssign::bitand_assign(&mut lhs, rhs);
                 *vec1 = lhs;
             }
-            (&mut Bitmap(ref mut bits1), &Bitmap(ref bits2)) => {
+            (Bitmap(bits1), Bitmap(bits2)) => {
                 BitAndAssign::bitand_assign(bits1, bits2);
             }
-            (&mut Array(ref mut vec1), &Bitmap(ref bits2)) => {
+            (Array(vec1), Bitmap(bits2)) => {
                 BitAndAssign::bitand_assign(vec1, bits2);
             }
-            (this @ &mut Bitmap(..), &Array(..)) => {
+            (Array(vec1), Run(runs2)) => {
+                BitAndAssign::bitand_assign(vec1, runs2);
+            }
+            (Bitmap(bits1), Run(runs2)) => {
+                BitAndAssign::bitand_assign(bits1, runs2);
+            }
+            (Run(runs1), Run(runs2)) => {
+                *runs1 = runs1.intersection(runs2);
+            }
+            (this @ &mut Bitmap(..), &Array(..)) | (this @ &mut Run(..), _) => {
                 let mut new = rhs.clone();
                 BitAndAssign::bitand_assign(&mut new, &*this);
                 *this = new;
@@ -336,7 +444,14 @@ impl Sub<&Store> for &Store {
 
     fn sub(self, rhs: &Store) -> Store {
         match (self, rhs) {
-            (&Array(ref vec1), &Array(ref vec2)) => Array(Sub::sub(vec1, vec2)),
+            (Array(vec1), Array(vec2)) => Array(Sub::sub(vec1, vec2)),
+            (Run(runs1), Run(runs2)) => Run(runs1.difference(runs2)),
+            (Run(runs1), Array(vec2)) => Run(runs1.difference(&RunStore::from(vec2))),
+            (Run(runs1), Bitmap(..)) => {
+                let mut lhs = Bitmap(runs1.to_bitmap_store());
+                SubAssign::sub_assign(&mut lhs, rhs);
+                lhs
+            }
             _ => {
                 let mut lhs = self.clone();
                 SubAssign::sub_assign(&mut lhs, rhs);
@@ -349,18 +464,27 @@ impl Sub<&Store> for &Store {
 impl SubAssign<&Store> for Store {
     fn sub_assign(&mut self, rhs: &Store) {
         match (self, rhs) {
-            (&mut Array(ref mut vec1), &Array(ref vec2)) => {
+            (Array(vec1), Array(vec2)) => {
                 SubAssign::sub_assign(vec1, vec2);
             }
-            (&mut Bitmap(ref mut bits1), &Array(ref vec2)) => {
+            (Bitmap(bits1), Array(vec2)) => {
                 SubAssign::sub_assign(bits1, vec2);
             }
-            (&mut Bitmap(ref mut bits1), &Bitmap(ref bits2)) => {
+            (Bitmap(bits1), Bitmap(bits2)) => {
                 SubAssign::sub_assign(bits1, bits2);
             }
-            (&mut Array(ref mut vec1), &Bitmap(ref bits2)) => {
+            (Array(vec1), Bitmap(bits2)) => {
                 SubAssign::sub_assign(vec1, bits2);
             }
+            (Array(vec1), Run(runs2)) => {
+                SubAssign::sub_assign(vec1, runs2);
+            }
+            (Bitmap(bits1), Run(runs2)) => {
+                SubAssign::sub_assign(bits1, runs2);
+            }
+            (this @ &mut Run(..), _) => {
+                *this = Sub::sub(&*this, rhs);
+            }
         }
     }
 }
@@ -370,8 +494,12 @@ impl BitXor<&Store> for &Store {
 
     fn bitxor(self, rhs: &Store) -> Store {
         match (self, rhs) {
-            (&Array(ref vec1), &Array(ref vec2)) => Array(BitXor::bitxor(vec1, vec2)),
-            (&Array(..), &Bitmap(..)) => {
+            (Array(vec1), Array(vec2)) => Array(BitXor::bitxor(vec1, vec2)),
+            (Run(runs1), Run(runs2)) => Run(runs1.symmetric_difference(runs2)),
+            (Run(runs), Array(vec)) | (Array(vec), Run(runs)) => {
+                Run(runs.symmetric_difference(&RunStore::from(vec)))
+            }
+            (Array(..), Bitmap(..)) | (Run(..), Bitmap(..)) => {
                 let mut lhs = rhs.clone();
                 BitXorAssign::bitxor_assign(&mut lhs, self);
                 lhs
@@ -388,19 +516,25 @@ impl BitXor<&Store> for &Store {
 impl BitXorAssign<Store> for Store {
     fn bitxor_assign(&mut self, mut rhs: Store) {
         match (self, &mut rhs) {
-            (&mut Array(ref mut vec1), &mut Array(ref vec2)) => {
+            (Array(vec1), &mut Array(ref vec2)) => {
                 *vec1 = BitXor::bitxor(&*vec1, vec2);
             }
-            (&mut Bitmap(ref mut bits1), &mut Array(ref vec2)) => {
+            (Bitmap(bits1), &mut Array(ref vec2)) => {
                 BitXorAssign::bitxor_assign(bits1, vec2);
             }
-            (&mut Bitmap(ref mut bits1), &mut Bitmap(ref bits2)) => {
+            (Bitmap(bits1), &mut Bitmap(ref bits2)) => {
                 BitXorAssign::bitxor_assign(bits1, bits2);
             }
-            (this @ &mut Array(..), &mut Bitmap(..)) => {
+            (Bitmap(bits1), &mut Run(ref runs2)) => {
+                BitXorAssign::bitxor_assign(bits1, runs2);
+            }
+            (this @ &mut Array(..), &mut Bitmap(..)) | (this @ &mut Run(..), &mut Bitmap(..)) => {
                 mem::swap(this, &mut rhs);
                 BitXorAssign::bitxor_assign(this, rhs);
             }
+            (this @ &mut Run(..), _) | (this @ &mut Array(..), &mut Run(..)) => {
+                *this = BitXor::bitxor(&*this, &rhs);
+            }
         }
     }
 }
@@ -408,21 +542,28 @@ impl BitXorAssign<Store> for Store {
 impl BitXorAssign<&Store> for Store {
     fn bitxor_assign(&mut self, rhs: &Store) {
         match (self, rhs) {
-            (&mut Array(ref mut vec1), &Array(ref vec2)) => {
+            (Array(vec1), Array(vec2)) => {
                 let this = mem::take(vec1);
                 *vec1 = BitXor::bitxor(&this, vec2);
             }
-            (&mut Bitmap(ref mut bits1), &Array(ref vec2)) => {
+            (Bitmap(bits1), Array(vec2)) => {
                 BitXorAssign::bitxor_assign(bits1, vec2);
             }
-            (&mut Bitmap(ref mut bits1), &Bitmap(ref bits2)) => {
+            (Bitmap(bits1), Bitmap(bits2)) => {
                 BitXorAssign::bitxor_assign(bits1, bits2);
             }
-            (this @ &mut Array(..), &Bitmap(ref bits2)) => {
+            (Bitmap(bits1), Run(runs2)) => {
+                BitXorAssign::bitxor_assign(bits1, runs2);
+            }
+            (this @ &mut Array(..), &Bitmap(ref bits2))
+            | (this @ &mut Run(..), &Bitmap(ref bits2)) => {
                 let mut lhs: Store = Bitmap(bits2.clone());
                 BitXorAssign::bitxor_assign(&mut lhs, &*this);
                 *this = lhs;
             }
+            (this @ &mut Run(..), _) | (this @ &mut Array(..), &Run(..)) => {
+                *this = BitXor::bitxor(&*this, rhs);
+            }
         }
     }
 }
@@ -434,6 +575,7 @@ impl<'a> IntoIterator for &'a Store {
         match self {
             Array(vec) => Iter::Array(vec.iter()),
             Bitmap(bits) => Iter::BitmapBorrowed(bits.iter()),
+            Run(runs) => Iter::RunBorrowed(runs.iter()),
         }
     }
 }
@@ -445,6 +587,7 @@ impl IntoIterator for Store {
         match self {
             Array(vec) => Iter::Vec(vec.into_iter()),
             Bitmap(bits) => Iter::BitmapOwned(bits.into_iter()),
+            Run(runs) => Iter::RunOwned(runs.into_iter()),
         }
     }
 }
@@ -457,7 +600,9 @@ impl PartialEq for Store {
                 bits1.len() == bits2.len()
                     && bits1.iter().zip(bits2.iter()).all(|(i1, i2)| i1 == i2)
             }
-            _ => false,
+            (Run(runs1), Run(runs2)) => runs1 == runs2,
+            // The same values may be held by stores of different kinds
+            _ => self.len() == other.len() && self.into_iter().eq(other),
         }
     }
 }
@@ -471,6 +616,8 @@ impl<'a> Iterator for Iter<'a> {
             Iter::Vec(inner) => inner.next(),
             Iter::BitmapBorrowed(inner) => inner.next(),
             Iter::BitmapOwned(inner) => inner.next(),
+            Iter::RunBorrowed(inner) => inner.next(),
+            Iter::RunOwned(inner) => inner.next(),
         }
     }
 }
@@ -482,6 +629,8 @@ impl DoubleEndedIterator for Iter<'_> {
             Iter::Vec(inner) => inner.next_back(),
             Iter::BitmapBorrowed(inner) => inner.next_back(),
             Iter::BitmapOwned(inner) => inner.next_back(),
+            Iter::RunBorrowed(inner) => inner.next_back(),
+            Iter::RunOwned(inner) => inner.next_back(),
         }
     }
 }
@@ -0,0 +1,785 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+use std::ops::RangeInclusive;
+
+use super::{ArrayStore, BitmapStore};
+use crate::bitmap::serialization::StoreError;
+
+/// A run of consecutive values, `start..=end`.
+///
+/// It has the layout of a `[u16; 2]` so that runs can be read in place.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(C)]
+pub struct Interval {
+    pub start: u16,
+    pub end: u16,
+}
+
+// Safety: two `u16` fields without padding, any bit pattern is a valid `Interval`
+unsafe impl bytemuck::Zeroable for Interval {}
+unsafe impl bytemuck::Pod for Interval {}
+
+impl Interval {
+    pub fn new(start: u16, end: u16) -> Interval {
+        debug_assert!(start <= end);
+        Interval { start, end }
+    }
+
+    pub fn len(&self) -> u64 {
+        u64::from(self.end - self.start) + 1
+    }
+}
+
+/// A store of sorted, non-overlapping and non-adjacent runs of values.
+#[derive(Eq, PartialEq)]
+pub struct RunStore {
+    runs: Vec<Interval>,
+}
+
+impl Clone for RunStore {
+    fn clone(&self) -> Self {
+        RunStore { runs: self.runs.clone() }
+    }
+
+    fn clone_from(&mut self, other: &Self) {
+        self.runs.clone_from(&other.runs);
+    }
+}
+
+impl RunStore {
+    pub fn new() -> RunStore {
+        RunStore { runs: vec![] }
+    }
+
+    ///
+    /// Create a new RunStore from a given vec of runs
+    /// It is up to the caller to ensure the runs are sorted, non-overlapping and non-adjacent
+    /// Favor `try_from` / `try_into` for cases in which these invariants should be checked
+    ///
+    /// # Panics
+    ///
+    /// When debug_assertions are enabled and the above invariants are not met
+    #[inline]
+    pub fn from_vec_unchecked(runs: Vec<Interval>) -> RunStore {
+        if cfg!(debug_assertions) {
+            RunStore::try_from(runs).unwrap()
+        } else {
+            RunStore { runs }
+        }
+    }
+
+    ///
+    /// Create a new RunStore from a given vec of runs, also checking that their total
+    /// length matches the expected cardinality `len`
+    pub fn try_from_len(len: u64, runs: Vec<Interval>) -> Result<RunStore, Error> {
+        let store = RunStore::try_from(runs)?;
+        let actual = store.len();
+        if len != actual {
+            let index = store.runs.len();
+            Err(Error { index, kind: ErrorKind::Cardinality { expected: len, actual } })
+        } else {
+            Ok(store)
+        }
+    }
+
+    /// Checks that the runs are non-empty, sorted, non-overlapping and non-adjacent.
+    pub fn validate(&self) -> Result<(), Error> {
+        let mut prev: Option<&Interval> = None;
+        for (i, run) in self.runs.iter().enumerate() {
+            if run.start > run.end {
+                return Err(Error { index: i, kind: ErrorKind::Empty });
+            }
+            if let Some(prev) = prev {
+                if run.start < prev.start {
+                    return Err(Error { index: i, kind: ErrorKind::OutOfOrder });
+                }
+                if u32::from(run.start) <= u32::from(prev.end) + 1 {
+                    return Err(Error { index: i, kind: ErrorKind::Overlapping });
+                }
+            }
+            prev = Some(run);
+        }
+        Ok(())
+    }
+
+    /// Drops the empty runs, sorts the others and merges the overlapping or adjacent ones.
+    pub fn normalize(&mut self) {
+        self.runs.retain(|run| run.start <= run.end);
+        self.runs.sort_unstable_by_key(|run| run.start);
+        let mut runs: Vec<Interval> = Vec::with_capacity(self.runs.len());
+        for run in self.runs.drain(..) {
+            match runs.last_mut() {
+                Some(last) if u32::from(run.start) <= u32::from(last.end) + 1 => {
+                    last.end = last.end.max(run.end);
+                }
+                _ => runs.push(run),
+            }
+        }
+        self.runs = runs;
+    }
+
+    /// Returns the number of bytes this store uses in the serialized format.
+    pub fn serialized_byte_size(runs: usize) -> usize {
+        2 + 4 * runs
+    }
+
+    pub fn insert(&mut self, index: u16) -> bool {
+        // The first run that starts strictly after `index`.
+        let next = self.runs.partition_point(|run| run.start <= index);
+
+        if next > 0 {
+            let prev = &mut self.runs[next - 1];
+            if index <= prev.end {
+                return false;
+            }
+            if u32::from(prev.end) + 1 == u32::from(index) {
+                prev.end = index;
+                // The run may now touch the following run, merge them.
+                if next < self.runs.len()
+                    && u32::from(self.runs[next].start) == u32::from(index) + 1
+                {
+                    self.runs[next - 1].end = self.runs[next].end;
+                    self.runs.remove(next);
+                }
+                return true;
+            }
+        }
+
+        if next < self.runs.len() && u32::from(self.runs[next].start) == u32::from(index) + 1 {
+            self.runs[next].start = index;
+        } else {
+            self.runs.insert(next, Interval::new(index, index));
+        }
+        true
+    }
+
+    pub fn insert_range(&mut self, range: RangeInclusive<u16>) -> u64 {
+        let start = *range.start();
+        let end = *range.end();
+
+        // The runs overlapping or adjacent to the range are merged with it.
+        let first = self.runs.partition_point(|run| u32::from(run.end) + 1 < u32::from(start));
+        let last = self.runs.partition_point(|run| u32::from(run.start) <= u32::from(end) + 1);
+
+        let existed: u64 = self.runs[first..last]
+            .iter()
+            .map(|run| {
+                let lo = run.start.max(start);
+                let hi = run.end.min(end);
+                if lo <= hi {
+                    u64::from(hi - lo) + 1
+                } else {
+                    0
+                }
+            })
+            .sum();
+
+        let merged = match (self.runs[first..last].first(), self.runs[first..last].last()) {
+            (Some(head), Some(tail)) => Interval::new(head.start.min(start), tail.end.max(end)),
+            _ => Interval::new(start, end),
+        };
+        self.runs.splice(first..last, Some(merged));
+
+        u64::from(end - start) + 1 - existed
+    }
+
+    pub fn push(&mut self, index: u16) -> bool {
+        if self.max().map_or(true, |max| max < index) {
+            self.push_unchecked(index);
+            true
+        } else {
+            false
+        }
+    }
+
+    ///
+    /// Pushes `index` at the end of the store.
+    /// It is up to the caller to have validated index > self.max()
+    ///
+    /// # Panics
+    ///
+    /// If debug_assertions enabled and index is > self.max()
+    pub(crate) fn push_unchecked(&mut self, index: u16) {
+        if cfg!(debug_assertions) {
+            if let Some(max) = self.max() {
+                assert!(index > max, "store max >= index")
+            }
+        }
+        match self.runs.last_mut() {
+            Some(last) if u32::from(last.end) + 1 == u32::from(index) => last.end = index,
+            _ => self.runs.push(Interval::new(index, index)),
+        }
+    }
+
+    pub fn remove(&mut self, index: u16) -> bool {
+        let loc = match self.find_run(index) {
+            Some(loc) => loc,
+            None => return false,
+        };
+
+        let run = self.runs[loc];
+        match (run.start == index, run.end == index) {
+            (true, true) => {
+                self.runs.remove(loc);
+            }
+            (true, false) => self.runs[loc].start = index + 1,
+            (false, true) => self.runs[loc].end = index - 1,
+            (false, false) => {
+                self.runs[loc].end = index - 1;
+                self.runs.insert(loc + 1, Interval::new(index + 1, run.end));
+            }
+        }
+        true
+    }
+
+    pub fn remove_range(&mut self, range: RangeInclusive<u16>) -> u64 {
+        let start = *range.start();
+        let end = *range.end();
+
+        // The runs overlapping the range.
+        let first = self.runs.partition_point(|run| run.end < start);
+        let last = self.runs.partition_point(|run| run.start <= end);
+        if first >= last {
+            return 0;
+        }
+
+        let head = self.runs[first];
+        let tail = self.runs[last - 1];
+        let removed: u64 = self.runs[first..last]
+            .iter()
+            .map(|run| u64::from(run.end.min(end) - run.start.max(start)) + 1)
+            .sum();
+
+        let mut kept = Vec::with_capacity(2);
+        if head.start < start {
+            kept.push(Interval::new(head.start, start - 1));
+        }
+        if tail.end > end {
+            kept.push(Interval::new(end + 1, tail.end));
+        }
+        self.runs.splice(first..last, kept);
+
+        removed
+    }
+
+    pub fn contains(&self, index: u16) -> bool {
+        self.find_run(index).is_some()
+    }
+
+    pub fn contains_range(&self, range: RangeInclusive<u16>) -> bool {
+        match self.find_run(*range.start()) {
+            Some(loc) => *range.end() <= self.runs[loc].end,
+            None => false,
+        }
+    }
+
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        let (mut i1, mut i2) = (self.runs.iter(), other.runs.iter());
+        let (mut run1, mut run2) = (i1.next(), i2.next());
+        while let (Some(r1), Some(r2)) = (run1, run2) {
+            if r1.end < r2.start {
+                run1 = i1.next();
+            } else if r2.end < r1.start {
+                run2 = i2.next();
+            } else {
+                return false;
+            }
+        }
+        true
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        // Runs are maximal, every run of self must be inside a single run of other.
+        let mut others = other.runs.iter();
+        let mut current = others.next();
+        for run in &self.runs {
+            loop {
+                match current {
+                    None => return false,
+                    Some(o) if o.end < run.start => current = others.next(),
+                    Some(o) if o.start <= run.start && run.end <= o.end => break,
+                    Some(_) => return false,
+                }
+            }
+        }
+        true
+    }
+
+    pub fn intersection_len(&self, other: &Self) -> u64 {
+        let (mut i1, mut i2) = (self.runs.iter(), other.runs.iter());
+        let (mut run1, mut run2) = (i1.next(), i2.next());
+        let mut len = 0;
+        while let (Some(r1), Some(r2)) = (run1, run2) {
+            let lo = r1.start.max(r2.start);
+            let hi = r1.end.min(r2.end);
+            if lo <= hi {
+                len += u64::from(hi - lo) + 1;
+            }
+            if r1.end < r2.end {
+                run1 = i1.next();
+            } else {
+                run2 = i2.next();
+            }
+        }
+        len
+    }
+
+    pub fn intersection_len_array(&self, other: &ArrayStore) -> u64 {
+        other.iter().filter(|&&index| self.contains(index)).count() as u64
+    }
+
+    pub fn len(&self) -> u64 {
+        self.runs.iter().map(Interval::len).sum()
+    }
+
+    /// Returns the number of runs in this store.
+    pub fn run_amount(&self) -> u64 {
+        self.runs.len() as u64
+    }
+
+    pub fn min(&self) -> Option<u16> {
+        self.runs.first().map(|run| run.start)
+    }
+
+    pub fn max(&self) -> Option<u16> {
+        self.runs.last().map(|run| run.end)
+    }
+
+    pub fn rank(&self, index: u16) -> u64 {
+        let mut rank = 0;
+        for run in &self.runs {
+            if run.end <= index {
+                rank += run.len();
+            } else {
+                if run.start <= index {
+                    rank += u64::from(index - run.start) + 1;
+                }
+                break;
+            }
+        }
+        rank
+    }
+
+    pub fn select(&self, n: u16) -> Option<u16> {
+        let mut n = u64::from(n);
+        for run in &self.runs {
+            let len = run.len();
+            if n < len {
+                return Some(run.start + n as u16);
+            }
+            n -= len;
+        }
+        None
+    }
+
+    pub fn to_array_store(&self) -> ArrayStore {
+        let mut vec = Vec::with_capacity(self.len() as usize);
+        for run in &self.runs {
+            vec.extend(run.start..=run.end);
+        }
+        ArrayStore::from_vec_unchecked(vec)
+    }
+
+    pub fn to_bitmap_store(&self) -> BitmapStore {
+        let mut bits = BitmapStore::new();
+        for run in &self.runs {
+            bits.insert_range(run.start..=run.end);
+        }
+        bits
+    }
+
+    pub fn iter(&self) -> RunIter<&[Interval]> {
+        RunIter::new(&self.runs)
+    }
+
+    pub fn into_iter(self) -> RunIter<Vec<Interval>> {
+        RunIter::new(self.runs)
+    }
+
+    pub fn as_slice(&self) -> &[Interval] {
+        &self.runs
+    }
+
+    pub fn into_vec(self) -> Vec<Interval> {
+        self.runs
+    }
+
+    /// Returns the location of the run that contains `index`.
+    fn find_run(&self, index: u16) -> Option<usize> {
+        self.runs
+            .binary_search_by(|run| {
+                if run.end < index {
+                    Ordering::Less
+                } else if run.start > index {
+                    Ordering::Greater
+                } else {
+                    Ordering::Equal
+                }
+            })
+            .ok()
+    }
+}
+
+impl Default for RunStore {
+    fn default() -> Self {
+        RunStore::new()
+    }
+}
+
+impl From<&ArrayStore> for RunStore {
+    fn from(array: &ArrayStore) -> Self {
+        let mut runs = RunStore::new();
+        for &index in array.iter() {
+            runs.push_unchecked(index);
+        }
+        runs
+    }
+}
+
+impl From<&BitmapStore> for RunStore {
+    fn from(bits: &BitmapStore) -> Self {
+        let mut runs = Vec::with_capacity(bits.run_amount() as usize);
+        let mut start = None;
+        for (key, &word) in bits.as_array().iter().enumerate() {
+            let base = 64 * key as u32;
+            let mut word = word;
+            let mut offset = 0;
+            while offset < 64 {
+                match start {
+                    None => {
+                        if word == 0 {
+                            break;
+                        }
+                        let zeros = word.trailing_zeros();
+                        start = Some(base + offset + zeros);
+                        word >>= zeros;
+                        offset += zeros;
+                    }
+                    Some(s) => {
+                        let ones = (!word).trailing_zeros();
+                        if offset + ones >= 64 {
+                            break;
+                        }
+                        runs.push(Interval::new(s as u16, (base + offset + ones - 1) as u16));
+                        start = None;
+                        word >>= ones;
+                        offset += ones;
+                    }
+                }
+            }
+        }
+        if let Some(s) = start {
+            runs.push(Interval::new(s as u16, u16::MAX));
+        }
+        RunStore { runs }
+    }
+}
+
+#[derive(Debug)]
+pub struct Error {
+    index: usize,
+    kind: ErrorKind,
+}
+
+#[derive(Debug)]
+pub enum ErrorKind {
+    Empty,
+    Overlapping,
+    OutOfOrder,
+    Cardinality { expected: u64, actual: u64 },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            ErrorKind::Empty => write!(f, "An empty run was found at index: {}", self.index),
+            ErrorKind::Overlapping => {
+                write!(f, "A run overlapped or touched the previous one at index: {}", self.index)
+            }
+            ErrorKind::OutOfOrder => {
+                write!(f, "A run was out of order at index: {}", self.index)
+            }
+            ErrorKind::Cardinality { expected, actual } => {
+                write!(f, "Expected cardinality was {} but was {}", expected, actual)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl StoreError for Error {
+    fn cardinality(&self) -> Option<(u64, u64)> {
+        match self.kind {
+            ErrorKind::Cardinality { expected, actual } => Some((expected, actual)),
+            _ => None,
+        }
+    }
+}
+
+impl TryFrom<Vec<Interval>> for RunStore {
+    type Error = Error;
+
+    fn try_from(value: Vec<Interval>) -> Result<Self, Self::Error> {
+        let store = RunStore { runs: value };
+        store.validate()?;
+        Ok(store)
+    }
+}
+
+/// Merges two sorted lists of runs, keeping the values for which `op` holds given whether
+/// they belong to the left and right runs.
+///
+/// Each run is seen as a pair of boundaries where the membership flips, sweeping over the
+/// boundaries of both sides in order gives the output boundaries.
+fn merge(lhs: &[Interval], rhs: &[Interval], op: impl Fn(bool, bool) -> bool) -> RunStore {
+    fn boundaries(runs: &[Interval]) -> impl Iterator<Item = u32> + '_ {
+        runs.iter().flat_map(|run| [u32::from(run.start), u32::from(run.end) + 1])
+    }
+
+    let mut runs = Vec::with_capacity(lhs.len().max(rhs.len()));
+    let (mut left, mut right) = (boundaries(lhs).peekable(), boundaries(rhs).peekable());
+    let (mut in_left, mut in_right) = (false, false);
+    let mut start = None;
+
+    loop {
+        let point = match (left.peek(), right.peek()) {
+            (None, None) => break,
+            (Some(&l), None) => l,
+            (None, Some(&r)) => r,
+            (Some(&l), Some(&r)) => l.min(r),
+        };
+        if left.next_if_eq(&point).is_some() {
+            in_left = !in_left;
+        }
+        if right.next_if_eq(&point).is_some() {
+            in_right = !in_right;
+        }
+
+        match (start, op(in_left, in_right)) {
+            (None, true) => start = Some(point),
+            (Some(s), false) => {
+                runs.push(Interval::new(s as u16, (point - 1) as u16));
+                start = None;
+            }
+            _ => (),
+        }
+    }
+
+    RunStore { runs }
+}
+
+impl RunStore {
+    pub fn union(&self, other: &Self) -> RunStore {
+        merge(&self.runs, &other.runs, |l, r| l || r)
+    }
+
+    pub fn intersection(&self, other: &Self) -> RunStore {
+        merge(&self.runs, &other.runs, |l, r| l && r)
+    }
+
+    pub fn difference(&self, other: &Self) -> RunStore {
+        merge(&self.runs, &other.runs, |l, r| l && !r)
+    }
+
+    pub fn symmetric_difference(&self, other: &Self) -> RunStore {
+        merge(&self.runs, &other.runs, |l, r| l ^ r)
+    }
+}
+
+pub struct RunIter<R: Borrow<[Interval]>> {
+    // The position of the next value from the front, as a run and a value in that run
+    run: usize,
+    value: u32,
+    // The position of the next value from the back, as a run and a value in that run
+    run_back: usize,
+    value_back: u32,
+    runs: R,
+}
+
+impl<R: Borrow<[Interval]>> RunIter<R> {
+    fn new(runs: R) -> RunIter<R> {
+        let slice = runs.borrow();
+        let value = slice.first().map_or(1, |run| u32::from(run.start));
+        let (run_back, value_back) = match slice.last() {
+            Some(run) => (slice.len() - 1, u32::from(run.end)),
+            None => (0, 0),
+        };
+        RunIter { run: 0, value, run_back, value_back, runs }
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.run > self.run_back || (self.run == self.run_back && self.value > self.value_back)
+    }
+}
+
+impl<R: Borrow<[Interval]>> Iterator for RunIter<R> {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<u16> {
+        if self.is_exhausted() {
+            return None;
+        }
+        let value = self.value as u16;
+        let runs = self.runs.borrow();
+        if self.value < u32::from(runs[self.run].end) {
+            self.value += 1;
+        } else {
+            self.run += 1;
+            self.value = runs.get(self.run).map_or(0, |run| u32::from(run.start));
+        }
+        Some(value)
+    }
+}
+
+impl<R: Borrow<[Interval]>> DoubleEndedIterator for RunIter<R> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.is_exhausted() {
+            return None;
+        }
+        let value = self.value_back as u16;
+        let runs = self.runs.borrow();
+        if self.value_back > u32::from(runs[self.run_back].start) {
+            self.value_back -= 1;
+        } else if self.run_back == 0 {
+            // Make the iterator exhausted without underflowing
+            self.run = 1;
+        } else {
+            self.run_back -= 1;
+            self.value_back = u32::from(runs[self.run_back].end);
+        }
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitmap::store::Store;
+
+    fn runs(runs: &[(u16, u16)]) -> RunStore {
+        RunStore::from_vec_unchecked(runs.iter().map(|&(s, e)| Interval::new(s, e)).collect())
+    }
+
+    #[test]
+    fn test_run_insert_merges_neighbours() {
+        let mut store = runs(&[(1, 3), (5, 7)]);
+        assert!(store.insert(4));
+        assert!(!store.insert(4));
+        assert_eq!(store, runs(&[(1, 7)]));
+
+        assert!(store.insert(0));
+        assert!(store.insert(9));
+        assert_eq!(store, runs(&[(0, 7), (9, 9)]));
+    }
+
+    #[test]
+    fn test_run_remove_splits() {
+        let mut store = runs(&[(1, 10)]);
+        assert!(store.remove(5));
+        assert!(!store.remove(5));
+        assert_eq!(store, runs(&[(1, 4), (6, 10)]));
+
+        assert!(store.remove(1));
+        assert!(store.remove(10));
+        assert_eq!(store, runs(&[(2, 4), (6, 9)]));
+    }
+
+    #[test]
+    fn test_run_insert_range() {
+        let mut store = runs(&[(1, 3), (10, 12), (20, 22)]);
+        assert_eq!(store.insert_range(4..=15), 9);
+        assert_eq!(store, runs(&[(1, 15), (20, 22)]));
+
+        assert_eq!(store.insert_range(30..=u16::MAX), u64::from(u16::MAX) - 29);
+        assert_eq!(store, runs(&[(1, 15), (20, 22), (30, u16::MAX)]));
+    }
+
+    #[test]
+    fn test_run_remove_range() {
+        let mut store = runs(&[(1, 3), (10, 12), (20, 22)]);
+        assert_eq!(store.remove_range(2..=20), 6);
+        assert_eq!(store, runs(&[(1, 1), (21, 22)]));
+        assert_eq!(store.remove_range(0..=u16::MAX), 3);
+        assert_eq!(store, runs(&[]));
+    }
+
+    #[test]
+    fn test_run_normalize() {
+        let mut store = RunStore {
+            runs: vec![
+                Interval { start: 10, end: 12 },
+                Interval { start: 5, end: 4 },
+                Interval { start: 1, end: 3 },
+                Interval { start: 13, end: 15 },
+                Interval { start: 2, end: 6 },
+            ],
+        };
+        assert!(store.validate().is_err());
+        store.normalize();
+        assert!(store.validate().is_ok());
+        assert_eq!(store, runs(&[(1, 6), (10, 15)]));
+    }
+
+    #[test]
+    fn test_run_rank_select() {
+        let store = runs(&[(1, 3), (10, 12)]);
+        assert_eq!(store.rank(0), 0);
+        assert_eq!(store.rank(2), 2);
+        assert_eq!(store.rank(9), 3);
+        assert_eq!(store.rank(u16::MAX), 6);
+        assert_eq!(store.select(0), Some(1));
+        assert_eq!(store.select(3), Some(10));
+        assert_eq!(store.select(6), None);
+    }
+
+    #[test]
+    fn test_run_iter_both_ends() {
+        let store = runs(&[(0, 1), (5, 5), (u16::MAX - 1, u16::MAX)]);
+        assert_eq!(store.iter().collect::<Vec<_>>(), vec![0, 1, 5, u16::MAX - 1, u16::MAX]);
+        assert_eq!(store.iter().rev().collect::<Vec<_>>(), vec![u16::MAX, u16::MAX - 1, 5, 1, 0]);
+
+        let mut iter = store.iter();
+        assert_eq!(iter.next(), Some(0));
+        assert_eq!(iter.next_back(), Some(u16::MAX));
+        assert_eq!(iter.next_back(), Some(u16::MAX - 1));
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.next(), Some(5));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+
+        assert_eq!(RunStore::new().iter().next(), None);
+        assert_eq!(RunStore::new().iter().next_back(), None);
+    }
+
+    #[test]
+    fn test_run_from_bitmap() {
+        let store = runs(&[(0, 0), (62, 130), (1000, 1000), (u16::MAX - 64, u16::MAX)]);
+        let bits = store.to_bitmap_store();
+        assert_eq!(bits.run_amount(), 4);
+        assert_eq!(RunStore::from(&bits), store);
+        assert_eq!(RunStore::from(&store.to_array_store()), store);
+    }
+
+    #[test]
+    fn test_run_merge() {
+        let a = runs(&[(0, 4), (10, 14)]);
+        let b = runs(&[(5, 9), (12, 20)]);
+        assert_eq!(a.union(&b), runs(&[(0, 20)]));
+        assert_eq!(a.intersection(&b), runs(&[(12, 14)]));
+        assert_eq!(a.difference(&b), runs(&[(0, 4), (10, 11)]));
+        assert_eq!(a.symmetric_difference(&b), runs(&[(0, 11), (15, 20)]));
+        assert_eq!(a.intersection_len(&b), 3);
+    }
+
+    #[test]
+    fn test_run_store_eq_other_stores() {
+        let store = runs(&[(1, 3), (10, 12)]);
+        let array = Store::Array(store.to_array_store());
+        let bits = Store::Bitmap(store.to_bitmap_store());
+        assert_eq!(Store::Run(store.clone()), array);
+        assert_eq!(Store::Run(store), bits);
+    }
+}
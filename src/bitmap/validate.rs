@@ -0,0 +1,161 @@
+use std::mem;
+
+use super::container::ARRAY_LIMIT;
+use super::store::Store;
+use crate::{InvariantError, RoaringBitmap};
+
+impl RoaringBitmap {
+    /// Checks that the internal representation of this bitmap upholds the invariants the
+    /// operations rely on: the containers are sorted by strictly increasing keys, none of them
+    /// is empty, their values are sorted and deduplicated, the recorded cardinalities are exact
+    /// and only the containers of more than 4096 values are bitmaps.
+    ///
+    /// A bitmap built with the methods of this crate always upholds them, one deserialized with
+    /// [RoaringBitmap::deserialize_unchecked_from] may not.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{InvariantError, RoaringBitmap};
+    ///
+    /// let rb: RoaringBitmap = (1..4).chain(100_000..200_000).collect();
+    /// assert!(rb.validate().is_ok());
+    ///
+    /// // Two containers with the keys 1 and 0
+    /// let bytes = [0x3b, 0x30, 1, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 5, 0, 5, 0];
+    /// let rb = RoaringBitmap::deserialize_unchecked_from(&bytes[..]).unwrap();
+    /// match rb.validate() {
+    ///     Err(InvariantError::UnsortedKeys { index: 1, key: 0 }) => (),
+    ///     _ => panic!("the keys are sorted"),
+    /// }
+    /// ```
+    pub fn validate(&self) -> Result<(), InvariantError> {
+        let mut previous = None;
+        for (index, container) in self.containers.iter().enumerate() {
+            let key = container.key;
+            if previous.map_or(false, |previous| previous >= key) {
+                return Err(InvariantError::UnsortedKeys { index, key });
+            }
+            previous = Some(key);
+
+            if let Err(source) = container.store.validate() {
+                return Err(InvariantError::InvalidContainer { index, key, source });
+            }
+
+            let len = container.len();
+            if len == 0 {
+                return Err(InvariantError::EmptyContainer { index, key });
+            }
+
+            let type_matches = match container.store {
+                Store::Array(..) => len <= ARRAY_LIMIT,
+                Store::Bitmap(..) => len > ARRAY_LIMIT,
+                Store::Run(..) => true,
+            };
+            if !type_matches {
+                return Err(InvariantError::WrongContainerType { index, key, len });
+            }
+        }
+        Ok(())
+    }
+
+    /// Repairs the internal representation of this bitmap so that it upholds the invariants
+    /// checked by [RoaringBitmap::validate], keeping all of its values.
+    ///
+    /// The containers are sorted, the ones sharing a key are merged and the empty ones are
+    /// removed. The values of each container are sorted and deduplicated, and arrays and bitmaps
+    /// are converted to the type matching their cardinality.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringBitmap;
+    ///
+    /// // Two containers with the keys 1 and 0
+    /// let bytes = [0x3b, 0x30, 1, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 5, 0, 5, 0];
+    /// let mut rb = RoaringBitmap::deserialize_unchecked_from(&bytes[..]).unwrap();
+    /// rb.normalize();
+    ///
+    /// assert!(rb.validate().is_ok());
+    /// assert_eq!(rb, RoaringBitmap::from([5, 65541]));
+    /// ```
+    pub fn normalize(&mut self) {
+        for container in &mut self.containers {
+            container.store.normalize();
+        }
+
+        // The stable sort keeps the order of the containers sharing a key
+        let mut containers = mem::take(&mut self.containers);
+        containers.sort_by_key(|container| container.key);
+        for container in containers {
+            match self.containers.last_mut() {
+                Some(last) if last.key == container.key => *last |= container,
+                _ => self.containers.push(container),
+            }
+        }
+
+        self.containers.retain(|container| container.len() != 0);
+        for container in &mut self.containers {
+            if !matches!(container.store, Store::Run(..)) {
+                container.ensure_correct_store();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bitmap::container::Container;
+    use crate::{InvariantError, RoaringBitmap};
+
+    #[test]
+    fn test_validate() {
+        let mut rb = (0..10_000).chain(70_000..70_010).collect::<RoaringBitmap>();
+        assert!(rb.validate().is_ok());
+        rb.run_optimize();
+        assert!(rb.validate().is_ok());
+
+        let mut empty = rb.clone();
+        empty.containers.push(Container::new(5));
+        match empty.validate() {
+            Err(InvariantError::EmptyContainer { index: 2, key: 5 }) => (),
+            result => panic!("unexpected result {:?}", result),
+        }
+
+        let mut unsorted = rb.clone();
+        unsorted.containers.swap(0, 1);
+        match unsorted.validate() {
+            Err(InvariantError::UnsortedKeys { index: 1, key: 0 }) => (),
+            result => panic!("unexpected result {:?}", result),
+        }
+
+        let mut wrong_type = (0..10_000).collect::<RoaringBitmap>();
+        wrong_type.containers[0].store.remove_range(10..=9_999);
+        match wrong_type.validate() {
+            Err(InvariantError::WrongContainerType { index: 0, key: 0, len: 10 }) => (),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_normalize() {
+        let expected = (0..10_000).chain(70_000..70_010).collect::<RoaringBitmap>();
+
+        let mut rb = expected.clone();
+        rb.containers.push(Container::new(5));
+        rb.containers.swap(0, 1);
+        let mut duplicate = Container::new(1);
+        duplicate.insert(4464);
+        duplicate.insert(4470);
+        rb.containers.push(duplicate);
+        rb.normalize();
+        assert!(rb.validate().is_ok());
+        assert_eq!(rb, expected);
+
+        let mut wrong_type = (0..10_000).collect::<RoaringBitmap>();
+        wrong_type.containers[0].store.remove_range(10..=9_999);
+        wrong_type.normalize();
+        assert!(wrong_type.validate().is_ok());
+        assert_eq!(wrong_type, (0..10).collect());
+    }
+}
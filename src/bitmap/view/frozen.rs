@@ -0,0 +1,165 @@
+use super::{ContainerView, RoaringBitmapView, StoreView};
+use crate::bitmap::serialization::{FROZEN_ARRAY, FROZEN_BITMAP, FROZEN_COOKIE, FROZEN_RUN};
+use crate::DeserializeError;
+
+impl<'a> RoaringBitmapView<'a> {
+    /// Creates a view over a bitmap serialized in the frozen format of CRoaring, as written by
+    /// [`RoaringBitmap::serialize_frozen_into`] or `roaring_bitmap_frozen_serialize`. This
+    /// method checks that all of the internal values are valid, which requires reading them
+    /// once. If the bytes come from a trusted source consider
+    /// [`RoaringBitmapView::from_frozen_bytes_unchecked`].
+    ///
+    /// The bytes do not need to be aligned and must be little-endian.
+    ///
+    /// [`RoaringBitmap::serialize_frozen_into`]: crate::RoaringBitmap::serialize_frozen_into
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{RoaringBitmap, RoaringBitmapView};
+    ///
+    /// let rb: RoaringBitmap = (1..4).chain(100_000..200_000).collect();
+    /// let mut bytes = vec![];
+    /// rb.serialize_frozen_into(&mut bytes).unwrap();
+    ///
+    /// let view = RoaringBitmapView::from_frozen_bytes(&bytes).unwrap();
+    /// assert!(view.contains(3));
+    /// assert_eq!(RoaringBitmap::from(&view), rb);
+    /// ```
+    pub fn from_frozen_bytes(bytes: &'a [u8]) -> Result<RoaringBitmapView<'a>, DeserializeError> {
+        RoaringBitmapView::parse_frozen(bytes, true)
+    }
+
+    /// Creates a view over a bitmap serialized in the frozen format of CRoaring, as written by
+    /// [`RoaringBitmap::serialize_frozen_into`] or `roaring_bitmap_frozen_serialize`. Only the
+    /// layout of the bytes is checked, the queries are memory safe but will return meaningless
+    /// results if the data is not a valid bitmap.
+    ///
+    /// [`RoaringBitmap::serialize_frozen_into`]: crate::RoaringBitmap::serialize_frozen_into
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{RoaringBitmap, RoaringBitmapView};
+    ///
+    /// let rb: RoaringBitmap = (1..4).collect();
+    /// let mut bytes = vec![];
+    /// rb.serialize_frozen_into(&mut bytes).unwrap();
+    ///
+    /// let view = RoaringBitmapView::from_frozen_bytes_unchecked(&bytes).unwrap();
+    /// assert_eq!(RoaringBitmap::from(&view), rb);
+    /// ```
+    pub fn from_frozen_bytes_unchecked(
+        bytes: &'a [u8],
+    ) -> Result<RoaringBitmapView<'a>, DeserializeError> {
+        RoaringBitmapView::parse_frozen(bytes, false)
+    }
+
+    fn parse_frozen(
+        bytes: &'a [u8],
+        check: bool,
+    ) -> Result<RoaringBitmapView<'a>, DeserializeError> {
+        let truncated = |key, offset| DeserializeError::Truncated { key, offset };
+
+        // The header is at the end, preceded by the keys, the counts and the type codes
+        let header_position = bytes.len().checked_sub(4).ok_or_else(|| truncated(None, 0))?;
+        let header = u32_at(bytes, header_position);
+        if header & 0x7FFF != FROZEN_COOKIE {
+            return Err(DeserializeError::UnknownCookie {
+                cookie: header,
+                offset: header_position as u64,
+            });
+        }
+
+        let size = (header >> 15) as usize;
+        if size > u16::MAX as usize + 1 {
+            return Err(DeserializeError::TooManyContainers {
+                count: size as u64,
+                offset: header_position as u64,
+            });
+        }
+
+        let descriptions_position =
+            header_position.checked_sub(size * 5).ok_or_else(|| truncated(None, 0))?;
+        let keys = &bytes[descriptions_position..];
+        let counts = &bytes[descriptions_position + size * 2..];
+        let typecodes = &bytes[descriptions_position + size * 4..header_position];
+        let key = |i: usize| u16_at(keys, i * 2);
+        let count = |i: usize| usize::from(u16_at(counts, i * 2));
+
+        // The containers are grouped by type, bitmaps first, then runs and arrays
+        let mut zone_sizes = [0; 3];
+        for (i, &typecode) in typecodes.iter().enumerate() {
+            match typecode {
+                FROZEN_BITMAP => zone_sizes[0] += 8 * 1024,
+                FROZEN_RUN => zone_sizes[1] += count(i) * 4,
+                FROZEN_ARRAY => zone_sizes[2] += (count(i) + 1) * 2,
+                _ => {
+                    return Err(DeserializeError::InvalidContainer {
+                        key: key(i),
+                        offset: (descriptions_position + size * 4 + i) as u64,
+                        source: "unknown container type".into(),
+                    })
+                }
+            }
+        }
+        let data_size = zone_sizes.iter().sum::<usize>();
+        if data_size > descriptions_position {
+            return Err(truncated(None, 0));
+        } else if data_size < descriptions_position {
+            return Err(DeserializeError::LengthMismatch {
+                expected: descriptions_position as u64,
+                actual: data_size as u64,
+                offset: 0,
+            });
+        }
+
+        // The positions of the next container of each type
+        let mut positions = [0, zone_sizes[0], zone_sizes[0] + zone_sizes[1]];
+        let mut containers = Vec::with_capacity(size);
+        for (i, &typecode) in typecodes.iter().enumerate() {
+            let (key, count) = (key(i), count(i));
+            let (zone, data_size) = match typecode {
+                FROZEN_BITMAP => (0, 8 * 1024),
+                FROZEN_RUN => (1, count * 4),
+                _ => (2, (count + 1) * 2),
+            };
+            let position = positions[zone];
+            let data = &bytes[position..position + data_size];
+            positions[zone] += data_size;
+
+            let (len, store) = match typecode {
+                FROZEN_BITMAP => (count as u64 + 1, StoreView::Bitmap(data)),
+                FROZEN_RUN => {
+                    if check && count == 0 {
+                        return Err(DeserializeError::InvalidContainer {
+                            key,
+                            offset: position as u64,
+                            source: "run container without runs".into(),
+                        });
+                    }
+                    let store = StoreView::Run(data);
+                    (store.count(), store)
+                }
+                _ => (count as u64 + 1, StoreView::Array(data)),
+            };
+
+            let container = ContainerView { key, len, store };
+            if check {
+                let description_offset = (descriptions_position + i * 2) as u64;
+                container.validate(containers.last(), description_offset, position as u64)?;
+            }
+            containers.push(container);
+        }
+
+        Ok(RoaringBitmapView { containers })
+    }
+}
+
+fn u16_at(bytes: &[u8], i: usize) -> u16 {
+    u16::from_le_bytes([bytes[i], bytes[i + 1]])
+}
+
+fn u32_at(bytes: &[u8], i: usize) -> u32 {
+    u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]])
+}
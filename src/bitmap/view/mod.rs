@@ -0,0 +1,574 @@
+use std::fmt;
+use std::iter::FusedIterator;
+use std::slice;
+
+use super::container::Container;
+use super::serialization::{NO_OFFSET_THRESHOLD, SERIAL_COOKIE, SERIAL_COOKIE_NO_RUNCONTAINER};
+use super::util;
+use crate::{DeserializeError, RoaringBitmap};
+
+mod frozen;
+mod ops;
+mod store;
+
+pub(super) use self::store::{partition_point, StoreView};
+
+/// A read-only bitmap borrowing [the standard Roaring on-disk format][format] in place.
+///
+/// Only the container descriptions are decoded when the view is created, the values are read
+/// directly from the borrowed bytes, which do not need to be aligned.
+///
+/// [format]: https://github.com/RoaringBitmap/RoaringFormatSpec
+///
+/// # Examples
+///
+/// ```rust
+/// use roaring::{RoaringBitmap, RoaringBitmapView};
+///
+/// let rb: RoaringBitmap = (1..4).chain(100_000..200_000).collect();
+/// let mut bytes = vec![];
+/// rb.serialize_into(&mut bytes).unwrap();
+///
+/// let view = RoaringBitmapView::from_bytes(&bytes).unwrap();
+/// assert!(view.contains(3));
+/// assert_eq!(view.len(), rb.len());
+/// assert_eq!(view.iter().collect::<RoaringBitmap>(), rb);
+/// ```
+pub struct RoaringBitmapView<'a> {
+    pub(super) containers: Vec<ContainerView<'a>>,
+}
+
+#[derive(Clone, Copy)]
+pub(super) struct ContainerView<'a> {
+    pub key: u16,
+    pub len: u64,
+    pub store: StoreView<'a>,
+}
+
+impl ContainerView<'_> {
+    /// Checks that the values are valid and match the cardinality recorded in the header, and
+    /// that the key is greater than the one of the previous container. The errors report the
+    /// offsets of the description and of the data of the container.
+    pub fn validate(
+        &self,
+        previous: Option<&ContainerView>,
+        description_offset: u64,
+        offset: u64,
+    ) -> Result<(), DeserializeError> {
+        let key = self.key;
+        if previous.map_or(false, |previous| previous.key >= key) {
+            return Err(DeserializeError::UnsortedKeys { key, offset: description_offset });
+        }
+        // The owned stores are arrays up to 4096 values and bitmaps above
+        let type_matches = match self.store {
+            StoreView::Array(..) => self.len <= 4096,
+            StoreView::Bitmap(..) => self.len > 4096,
+            StoreView::Run(..) => true,
+        };
+        if !type_matches {
+            let source = "container type contradicts its cardinality".into();
+            return Err(DeserializeError::InvalidContainer { key, offset, source });
+        }
+        self.store.validate().map_err(|e| DeserializeError::InvalidContainer {
+            key,
+            offset,
+            source: e.into(),
+        })?;
+        let actual = self.store.count();
+        if actual != self.len {
+            return Err(DeserializeError::BadCardinality {
+                key,
+                offset,
+                expected: self.len,
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn to_container(self) -> Container {
+        Container { key: self.key, store: self.store.to_store(self.len) }
+    }
+}
+
+impl<'a> RoaringBitmapView<'a> {
+    /// Creates a view over a bitmap serialized in [the standard Roaring on-disk format][format],
+    /// as written by [`RoaringBitmap::serialize_into`]. This method checks that all of the
+    /// internal values are valid, which requires reading them once. If the bytes come from a
+    /// trusted source consider [`RoaringBitmapView::from_bytes_unchecked`].
+    ///
+    /// [format]: https://github.com/RoaringBitmap/RoaringFormatSpec
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{RoaringBitmap, RoaringBitmapView};
+    ///
+    /// let rb: RoaringBitmap = (1..4).collect();
+    /// let mut bytes = vec![];
+    /// rb.serialize_into(&mut bytes).unwrap();
+    ///
+    /// let view = RoaringBitmapView::from_bytes(&bytes).unwrap();
+    /// assert_eq!(RoaringBitmap::from(&view), rb);
+    /// ```
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<RoaringBitmapView<'a>, DeserializeError> {
+        RoaringBitmapView::parse(bytes, true).map(|(view, _)| view)
+    }
+
+    /// Creates a view over a bitmap serialized in [the standard Roaring on-disk format][format],
+    /// as written by [`RoaringBitmap::serialize_into`]. Only the layout of the bytes is checked,
+    /// the queries are memory safe but will return meaningless results if the data is not a valid
+    /// bitmap.
+    ///
+    /// [format]: https://github.com/RoaringBitmap/RoaringFormatSpec
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{RoaringBitmap, RoaringBitmapView};
+    ///
+    /// let rb: RoaringBitmap = (1..4).collect();
+    /// let mut bytes = vec![];
+    /// rb.serialize_into(&mut bytes).unwrap();
+    ///
+    /// let view = RoaringBitmapView::from_bytes_unchecked(&bytes).unwrap();
+    /// assert_eq!(RoaringBitmap::from(&view), rb);
+    /// ```
+    pub fn from_bytes_unchecked(
+        bytes: &'a [u8],
+    ) -> Result<RoaringBitmapView<'a>, DeserializeError> {
+        RoaringBitmapView::parse(bytes, false).map(|(view, _)| view)
+    }
+
+    /// Parses a bitmap in the portable format and returns it with the number of bytes it spans.
+    pub(in crate::bitmap) fn parse(
+        bytes: &'a [u8],
+        check: bool,
+    ) -> Result<(RoaringBitmapView<'a>, usize), DeserializeError> {
+        let header = HeaderView::parse(bytes)?;
+
+        // Without offsets the containers are laid out one after the other
+        let mut position = header.data_start;
+        let mut end = position;
+        let mut containers = Vec::with_capacity(header.size());
+
+        for i in 0..header.size() {
+            let key = header.key(i);
+            let len = header.len(i);
+            if let Some(offset) = header.offset(i) {
+                position = offset;
+            }
+            let truncated =
+                || DeserializeError::Truncated { key: Some(key), offset: position as u64 };
+            let mut data = bytes.get(position..).ok_or_else(truncated)?;
+
+            let store = StoreView::read(&mut data, header.is_run(i), len).ok_or_else(truncated)?;
+            let container = ContainerView { key, len, store };
+            if check {
+                let description_offset = header.description_offset(i);
+                container.validate(containers.last(), description_offset, position as u64)?;
+            }
+            containers.push(container);
+
+            position = bytes.len() - data.len();
+            end = end.max(position);
+        }
+
+        Ok((RoaringBitmapView { containers }, end))
+    }
+
+    /// Returns `true` if this set contains the specified integer.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{RoaringBitmap, RoaringBitmapView};
+    ///
+    /// let rb: RoaringBitmap = (1..4).collect();
+    /// let mut bytes = vec![];
+    /// rb.serialize_into(&mut bytes).unwrap();
+    ///
+    /// let view = RoaringBitmapView::from_bytes(&bytes).unwrap();
+    /// assert_eq!(view.contains(0), false);
+    /// assert_eq!(view.contains(1), true);
+    /// assert_eq!(view.contains(100), false);
+    /// ```
+    pub fn contains(&self, value: u32) -> bool {
+        let (key, index) = util::split(value);
+        match self.containers.binary_search_by_key(&key, |c| c.key) {
+            Ok(loc) => self.containers[loc].store.contains(index),
+            Err(_) => false,
+        }
+    }
+
+    /// Returns `true` if there are no integers in this set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{RoaringBitmap, RoaringBitmapView};
+    ///
+    /// let mut bytes = vec![];
+    /// RoaringBitmap::new().serialize_into(&mut bytes).unwrap();
+    ///
+    /// let view = RoaringBitmapView::from_bytes(&bytes).unwrap();
+    /// assert_eq!(view.is_empty(), true);
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.containers.is_empty()
+    }
+
+    /// Returns the number of distinct integers in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{RoaringBitmap, RoaringBitmapView};
+    ///
+    /// let rb: RoaringBitmap = (1..4).collect();
+    /// let mut bytes = vec![];
+    /// rb.serialize_into(&mut bytes).unwrap();
+    ///
+    /// let view = RoaringBitmapView::from_bytes(&bytes).unwrap();
+    /// assert_eq!(view.len(), 3);
+    /// ```
+    pub fn len(&self) -> u64 {
+        self.containers.iter().map(|container| container.len).sum()
+    }
+
+    /// Returns the minimum value in the set (if the set is non-empty).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{RoaringBitmap, RoaringBitmapView};
+    ///
+    /// let rb: RoaringBitmap = (3..5).collect();
+    /// let mut bytes = vec![];
+    /// rb.serialize_into(&mut bytes).unwrap();
+    ///
+    /// let view = RoaringBitmapView::from_bytes(&bytes).unwrap();
+    /// assert_eq!(view.min(), Some(3));
+    /// ```
+    pub fn min(&self) -> Option<u32> {
+        self.containers
+            .first()
+            .and_then(|head| head.store.min().map(|min| util::join(head.key, min)))
+    }
+
+    /// Returns the maximum value in the set (if the set is non-empty).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{RoaringBitmap, RoaringBitmapView};
+    ///
+    /// let rb: RoaringBitmap = (3..5).collect();
+    /// let mut bytes = vec![];
+    /// rb.serialize_into(&mut bytes).unwrap();
+    ///
+    /// let view = RoaringBitmapView::from_bytes(&bytes).unwrap();
+    /// assert_eq!(view.max(), Some(4));
+    /// ```
+    pub fn max(&self) -> Option<u32> {
+        self.containers
+            .last()
+            .and_then(|tail| tail.store.max().map(|max| util::join(tail.key, max)))
+    }
+
+    /// Returns the number of integers that are <= value. rank(u32::MAX) == len()
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{RoaringBitmap, RoaringBitmapView};
+    ///
+    /// let rb: RoaringBitmap = (3..5).collect();
+    /// let mut bytes = vec![];
+    /// rb.serialize_into(&mut bytes).unwrap();
+    ///
+    /// let view = RoaringBitmapView::from_bytes(&bytes).unwrap();
+    /// assert_eq!(view.rank(0), 0);
+    /// assert_eq!(view.rank(3), 1);
+    /// assert_eq!(view.rank(10), 2);
+    /// ```
+    pub fn rank(&self, value: u32) -> u64 {
+        let (key, index) = util::split(value);
+
+        match self.containers.binary_search_by_key(&key, |c| c.key) {
+            Ok(i) => {
+                self.containers[i].store.rank(index)
+                    + self.containers[..i].iter().map(|c| c.len).sum::<u64>()
+            }
+            Err(i) => self.containers[..i].iter().map(|c| c.len).sum(),
+        }
+    }
+
+    /// Returns the `n`th integer in the set or `None` if `n >= len()`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{RoaringBitmap, RoaringBitmapView};
+    ///
+    /// let rb: RoaringBitmap = [0, 10, 100].iter().collect();
+    /// let mut bytes = vec![];
+    /// rb.serialize_into(&mut bytes).unwrap();
+    ///
+    /// let view = RoaringBitmapView::from_bytes(&bytes).unwrap();
+    /// assert_eq!(view.select(0), Some(0));
+    /// assert_eq!(view.select(1), Some(10));
+    /// assert_eq!(view.select(2), Some(100));
+    /// assert_eq!(view.select(3), None);
+    /// ```
+    pub fn select(&self, n: u32) -> Option<u32> {
+        let mut n = n as u64;
+
+        for container in &self.containers {
+            if container.len > n {
+                return container
+                    .store
+                    .select(n as u16)
+                    .map(|index| util::join(container.key, index));
+            }
+            n -= container.len;
+        }
+
+        None
+    }
+
+    /// Iterator over each value stored in the view, in ascending order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{RoaringBitmap, RoaringBitmapView};
+    ///
+    /// let rb: RoaringBitmap = (1..4).collect();
+    /// let mut bytes = vec![];
+    /// rb.serialize_into(&mut bytes).unwrap();
+    ///
+    /// let view = RoaringBitmapView::from_bytes(&bytes).unwrap();
+    /// let mut iter = view.iter();
+    ///
+    /// assert_eq!(iter.next(), Some(1));
+    /// assert_eq!(iter.next(), Some(2));
+    /// assert_eq!(iter.next(), Some(3));
+    /// assert_eq!(iter.next(), None);
+    /// ```
+    pub fn iter(&self) -> Iter<'a, '_> {
+        Iter { containers: self.containers.iter(), current: None, size_hint: self.len() }
+    }
+}
+
+/// An iterator for `RoaringBitmapView`.
+pub struct Iter<'a, 'b> {
+    containers: slice::Iter<'b, ContainerView<'a>>,
+    current: Option<(u16, store::Iter<'a>)>,
+    size_hint: u64,
+}
+
+impl Iterator for Iter<'_, '_> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        loop {
+            if let Some((key, iter)) = self.current.as_mut() {
+                if let Some(index) = iter.next() {
+                    self.size_hint = self.size_hint.saturating_sub(1);
+                    return Some(util::join(*key, index));
+                }
+            }
+            let container = self.containers.next()?;
+            self.current = Some((container.key, container.store.iter()));
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.size_hint < usize::MAX as u64 {
+            (self.size_hint as usize, Some(self.size_hint as usize))
+        } else {
+            (usize::MAX, None)
+        }
+    }
+}
+
+impl FusedIterator for Iter<'_, '_> {}
+
+impl<'a, 'b> IntoIterator for &'b RoaringBitmapView<'a> {
+    type Item = u32;
+    type IntoIter = Iter<'a, 'b>;
+
+    fn into_iter(self) -> Iter<'a, 'b> {
+        self.iter()
+    }
+}
+
+impl From<&RoaringBitmapView<'_>> for RoaringBitmap {
+    fn from(view: &RoaringBitmapView<'_>) -> RoaringBitmap {
+        RoaringBitmap { containers: view.containers.iter().map(|c| c.to_container()).collect() }
+    }
+}
+
+impl fmt::Debug for RoaringBitmapView<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.len() < 16 {
+            write!(f, "RoaringBitmapView<{:?}>", self.iter().collect::<Vec<u32>>())
+        } else {
+            write!(
+                f,
+                "RoaringBitmapView<{:?} values between {:?} and {:?}>",
+                self.len(),
+                self.min(),
+                self.max()
+            )
+        }
+    }
+}
+
+/// The header of a bitmap serialized in the portable format, borrowed from its bytes.
+#[derive(Clone, Copy)]
+pub(in crate::bitmap) struct HeaderView<'a> {
+    run_container_bitmap: Option<&'a [u8]>,
+    descriptions: &'a [u8],
+    offsets: Option<&'a [u8]>,
+    /// The position of the descriptions in the bytes.
+    descriptions_start: usize,
+    /// The position of the data of the first container in the bytes.
+    pub data_start: usize,
+}
+
+impl<'a> HeaderView<'a> {
+    /// Reads the header at the start of `bytes`, without reading the containers data.
+    pub fn parse(bytes: &'a [u8]) -> Result<HeaderView<'a>, DeserializeError> {
+        let mut header = bytes;
+        let position = |header: &[u8]| (bytes.len() - header.len()) as u64;
+        let truncated = |offset| DeserializeError::Truncated { key: None, offset };
+
+        let cookie = take(&mut header, 4).ok_or_else(|| truncated(0))?;
+        let cookie = u32::from_le_bytes([cookie[0], cookie[1], cookie[2], cookie[3]]);
+        let (size, has_offsets, run_container_bitmap) = if cookie == SERIAL_COOKIE_NO_RUNCONTAINER {
+            let size = take(&mut header, 4).ok_or_else(|| truncated(4))?;
+            let size = u32::from_le_bytes([size[0], size[1], size[2], size[3]]) as usize;
+            if size > u16::MAX as usize + 1 {
+                return Err(DeserializeError::TooManyContainers { count: size as u64, offset: 4 });
+            }
+            (size, true, None)
+        } else if (cookie as u16) == SERIAL_COOKIE {
+            let size = ((cookie >> 16) + 1) as usize;
+            let offset = position(header);
+            let bitmap = take(&mut header, (size + 7) / 8).ok_or_else(|| truncated(offset))?;
+            (size, size >= NO_OFFSET_THRESHOLD, Some(bitmap))
+        } else {
+            return Err(DeserializeError::UnknownCookie { cookie, offset: 0 });
+        };
+
+        let descriptions_start = bytes.len() - header.len();
+        let descriptions =
+            take(&mut header, size * 4).ok_or_else(|| truncated(descriptions_start as u64))?;
+        let offsets = if has_offsets {
+            let offset = position(header);
+            Some(take(&mut header, size * 4).ok_or_else(|| truncated(offset))?)
+        } else {
+            None
+        };
+        let data_start = bytes.len() - header.len();
+
+        Ok(HeaderView {
+            run_container_bitmap,
+            descriptions,
+            offsets,
+            descriptions_start,
+            data_start,
+        })
+    }
+
+    /// Returns the number of containers.
+    pub fn size(&self) -> usize {
+        self.descriptions.len() / 4
+    }
+
+    pub fn key(&self, i: usize) -> u16 {
+        u16::from_le_bytes([self.descriptions[i * 4], self.descriptions[i * 4 + 1]])
+    }
+
+    /// Returns the cardinality of the `i`th container.
+    pub fn len(&self, i: usize) -> u64 {
+        u64::from(u16::from_le_bytes([self.descriptions[i * 4 + 2], self.descriptions[i * 4 + 3]]))
+            + 1
+    }
+
+    pub fn is_run(&self, i: usize) -> bool {
+        self.run_container_bitmap.map_or(false, |bm| bm[i / 8] & (1 << (i % 8)) != 0)
+    }
+
+    /// Returns the position of the data of the `i`th container recorded in the offsets of the
+    /// header, if it has any.
+    pub fn offset(&self, i: usize) -> Option<usize> {
+        self.offsets.map(|offsets| {
+            let offset = &offsets[i * 4..i * 4 + 4];
+            u32::from_le_bytes([offset[0], offset[1], offset[2], offset[3]]) as usize
+        })
+    }
+
+    /// Returns the position of the description of the `i`th container in the bytes.
+    pub fn description_offset(&self, i: usize) -> u64 {
+        (self.descriptions_start + i * 4) as u64
+    }
+}
+
+/// Splits the first `n` bytes off `bytes`, if there are enough of them.
+pub(in crate::bitmap) fn take<'a>(bytes: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+    if bytes.len() < n {
+        return None;
+    }
+    let (head, tail) = bytes.split_at(n);
+    *bytes = tail;
+    Some(head)
+}
+
+#[cfg(test)]
+mod test {
+    use super::RoaringBitmapView;
+    use crate::RoaringBitmap;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn view_queries(
+            bitmap in RoaringBitmap::arbitrary(),
+            values in proptest::collection::vec(0u32..=262143, 64),
+        ) {
+            let mut buffer = Vec::new();
+            bitmap.serialize_into(&mut buffer).unwrap();
+            let view = RoaringBitmapView::from_bytes(&buffer).unwrap();
+
+            prop_assert_eq!(view.len(), bitmap.len());
+            prop_assert_eq!(view.min(), bitmap.min());
+            prop_assert_eq!(view.max(), bitmap.max());
+            prop_assert_eq!(RoaringBitmap::from(&view), bitmap.clone());
+            prop_assert!(view.iter().eq(bitmap.iter()));
+            for value in values {
+                prop_assert_eq!(view.contains(value), bitmap.contains(value));
+                prop_assert_eq!(view.rank(value), bitmap.rank(value));
+                prop_assert_eq!(view.select(value), bitmap.select(value));
+            }
+        }
+
+        #[test]
+        fn view_ops(
+            a in RoaringBitmap::arbitrary(),
+            b in RoaringBitmap::arbitrary(),
+        ) {
+            let mut buffer = Vec::new();
+            b.serialize_into(&mut buffer).unwrap();
+            let view = RoaringBitmapView::from_bytes(&buffer).unwrap();
+
+            prop_assert_eq!(&a | &view, &a | &b);
+            prop_assert_eq!(&a & &view, &a & &b);
+            prop_assert_eq!(&a - &view, &a - &b);
+            prop_assert_eq!(&a ^ &view, &a ^ &b);
+            prop_assert_eq!(&view - &a, &b - &a);
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use std::cmp::Ordering;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Sub, SubAssign};
+
+use retain_mut::RetainMut;
+
+use super::{ContainerView, RoaringBitmapView};
+use crate::bitmap::container::Container;
+use crate::RoaringBitmap;
+
+/// Merges the values of an owned container with the ones of a container view, keeping the values
+/// for which `op` holds given whether they belong to the left and right containers.
+fn merge(lhs: &Container, rhs: &ContainerView, op: impl Fn(bool, bool) -> bool) -> Container {
+    let mut container = Container::new(lhs.key);
+    let mut lhs = (&lhs.store).into_iter().peekable();
+    let mut rhs = rhs.store.iter().peekable();
+
+    loop {
+        let (index, in_lhs, in_rhs) = match (lhs.peek().copied(), rhs.peek().copied()) {
+            (Some(l), Some(r)) => match l.cmp(&r) {
+                Ordering::Less => (lhs.next(), true, false),
+                Ordering::Greater => (rhs.next(), false, true),
+                Ordering::Equal => {
+                    rhs.next();
+                    (lhs.next(), true, true)
+                }
+            },
+            (Some(_), None) => (lhs.next(), true, false),
+            (None, Some(_)) => (rhs.next(), false, true),
+            (None, None) => break,
+        };
+        if op(in_lhs, in_rhs) {
+            container.push_unchecked(index.unwrap());
+        }
+    }
+
+    container
+}
+
+impl BitOr<&RoaringBitmapView<'_>> for RoaringBitmap {
+    type Output = RoaringBitmap;
+
+    /// An `union` between two sets.
+    fn bitor(mut self, rhs: &RoaringBitmapView) -> RoaringBitmap {
+        BitOrAssign::bitor_assign(&mut self, rhs);
+        self
+    }
+}
+
+impl BitOr<&RoaringBitmapView<'_>> for &RoaringBitmap {
+    type Output = RoaringBitmap;
+
+    /// An `union` between two sets.
+    fn bitor(self, rhs: &RoaringBitmapView) -> RoaringBitmap {
+        BitOr::bitor(self.clone(), rhs)
+    }
+}
+
+impl BitOr<RoaringBitmap> for &RoaringBitmapView<'_> {
+    type Output = RoaringBitmap;
+
+    /// An `union` between two sets.
+    fn bitor(self, rhs: RoaringBitmap) -> RoaringBitmap {
+        BitOr::bitor(rhs, self)
+    }
+}
+
+impl BitOr<&RoaringBitmap> for &RoaringBitmapView<'_> {
+    type Output = RoaringBitmap;
+
+    /// An `union` between two sets.
+    fn bitor(self, rhs: &RoaringBitmap) -> RoaringBitmap {
+        BitOr::bitor(rhs, self)
+    }
+}
+
+impl BitOrAssign<&RoaringBitmapView<'_>> for RoaringBitmap {
+    /// An `union` between two sets.
+    fn bitor_assign(&mut self, rhs: &RoaringBitmapView) {
+        for container in &rhs.containers {
+            match self.containers.binary_search_by_key(&container.key, |c| c.key) {
+                Err(loc) => self.containers.insert(loc, container.to_container()),
+                Ok(loc) => {
+                    let lhs = &mut self.containers[loc];
+                    *lhs = merge(lhs, container, |l, r| l || r);
+                }
+            }
+        }
+    }
+}
+
+impl BitAnd<&RoaringBitmapView<'_>> for RoaringBitmap {
+    type Output = RoaringBitmap;
+
+    /// An `intersection` between two sets.
+    fn bitand(mut self, rhs: &RoaringBitmapView) -> RoaringBitmap {
+        BitAndAssign::bitand_assign(&mut self, rhs);
+        self
+    }
+}
+
+impl BitAnd<&RoaringBitmapView<'_>> for &RoaringBitmap {
+    type Output = RoaringBitmap;
+
+    /// An `intersection` between two sets.
+    fn bitand(self, rhs: &RoaringBitmapView) -> RoaringBitmap {
+        let mut containers = Vec::new();
+
+        for lhs in &self.containers {
+            if let Ok(loc) = rhs.containers.binary_search_by_key(&lhs.key, |c| c.key) {
+                let container = merge(lhs, &rhs.containers[loc], |l, r| l && r);
+                if container.len() != 0 {
+                    containers.push(container);
+                }
+            }
+        }
+
+        RoaringBitmap { containers }
+    }
+}
+
+impl BitAnd<RoaringBitmap> for &RoaringBitmapView<'_> {
+    type Output = RoaringBitmap;
+
+    /// An `intersection` between two sets.
+    fn bitand(self, rhs: RoaringBitmap) -> RoaringBitmap {
+        BitAnd::bitand(rhs, self)
+    }
+}
+
+impl BitAnd<&RoaringBitmap> for &RoaringBitmapView<'_> {
+    type Output = RoaringBitmap;
+
+    /// An `intersection` between two sets.
+    fn bitand(self, rhs: &RoaringBitmap) -> RoaringBitmap {
+        BitAnd::bitand(rhs, self)
+    }
+}
+
+impl BitAndAssign<&RoaringBitmapView<'_>> for RoaringBitmap {
+    /// An `intersection` between two sets.
+    fn bitand_assign(&mut self, rhs: &RoaringBitmapView) {
+        RetainMut::retain_mut(&mut self.containers, |cont| {
+            match rhs.containers.binary_search_by_key(&cont.key, |c| c.key) {
+                Ok(loc) => {
+                    *cont = merge(cont, &rhs.containers[loc], |l, r| l && r);
+                    cont.len() != 0
+                }
+                Err(_) => false,
+            }
+        })
+    }
+}
+
+impl Sub<&RoaringBitmapView<'_>> for RoaringBitmap {
+    type Output = RoaringBitmap;
+
+    /// A `difference` between two sets.
+    fn sub(mut self, rhs: &RoaringBitmapView) -> RoaringBitmap {
+        SubAssign::sub_assign(&mut self, rhs);
+        self
+    }
+}
+
+impl Sub<&RoaringBitmapView<'_>> for &RoaringBitmap {
+    type Output = RoaringBitmap;
+
+    /// A `difference` between two sets.
+    fn sub(self, rhs: &RoaringBitmapView) -> RoaringBitmap {
+        let mut containers = Vec::new();
+
+        for lhs in &self.containers {
+            match rhs.containers.binary_search_by_key(&lhs.key, |c| c.key) {
+                Ok(loc) => {
+                    let container = merge(lhs, &rhs.containers[loc], |l, r| l && !r);
+                    if container.len() != 0 {
+                        containers.push(container);
+                    }
+                }
+                Err(_) => containers.push(lhs.clone()),
+            }
+        }
+
+        RoaringBitmap { containers }
+    }
+}
+
+impl Sub<RoaringBitmap> for &RoaringBitmapView<'_> {
+    type Output = RoaringBitmap;
+
+    /// A `difference` between two sets.
+    fn sub(self, rhs: RoaringBitmap) -> RoaringBitmap {
+        Sub::sub(self, &rhs)
+    }
+}
+
+impl Sub<&RoaringBitmap> for &RoaringBitmapView<'_> {
+    type Output = RoaringBitmap;
+
+    /// A `difference` between two sets.
+    fn sub(self, rhs: &RoaringBitmap) -> RoaringBitmap {
+        let mut containers = Vec::new();
+
+        for lhs in &self.containers {
+            match rhs.containers.binary_search_by_key(&lhs.key, |c| c.key) {
+                Ok(loc) => {
+                    // The operands are swapped as `merge` takes the owned container first
+                    let container = merge(&rhs.containers[loc], lhs, |l, r| !l && r);
+                    if container.len() != 0 {
+                        containers.push(container);
+                    }
+                }
+                Err(_) => containers.push(lhs.to_container()),
+            }
+        }
+
+        RoaringBitmap { containers }
+    }
+}
+
+impl SubAssign<&RoaringBitmapView<'_>> for RoaringBitmap {
+    /// A `difference` between two sets.
+    fn sub_assign(&mut self, rhs: &RoaringBitmapView) {
+        RetainMut::retain_mut(&mut self.containers, |cont| {
+            match rhs.containers.binary_search_by_key(&cont.key, |c| c.key) {
+                Ok(loc) => {
+                    *cont = merge(cont, &rhs.containers[loc], |l, r| l && !r);
+                    cont.len() != 0
+                }
+                Err(_) => true,
+            }
+        })
+    }
+}
+
+impl BitXor<&RoaringBitmapView<'_>> for RoaringBitmap {
+    type Output = RoaringBitmap;
+
+    /// A `symmetric difference` between two sets.
+    fn bitxor(mut self, rhs: &RoaringBitmapView) -> RoaringBitmap {
+        BitXorAssign::bitxor_assign(&mut self, rhs);
+        self
+    }
+}
+
+impl BitXor<&RoaringBitmapView<'_>> for &RoaringBitmap {
+    type Output = RoaringBitmap;
+
+    /// A `symmetric difference` between two sets.
+    fn bitxor(self, rhs: &RoaringBitmapView) -> RoaringBitmap {
+        BitXor::bitxor(self.clone(), rhs)
+    }
+}
+
+impl BitXor<RoaringBitmap> for &RoaringBitmapView<'_> {
+    type Output = RoaringBitmap;
+
+    /// A `symmetric difference` between two sets.
+    fn bitxor(self, rhs: RoaringBitmap) -> RoaringBitmap {
+        BitXor::bitxor(rhs, self)
+    }
+}
+
+impl BitXor<&RoaringBitmap> for &RoaringBitmapView<'_> {
+    type Output = RoaringBitmap;
+
+    /// A `symmetric difference` between two sets.
+    fn bitxor(self, rhs: &RoaringBitmap) -> RoaringBitmap {
+        BitXor::bitxor(rhs, self)
+    }
+}
+
+impl BitXorAssign<&RoaringBitmapView<'_>> for RoaringBitmap {
+    /// A `symmetric difference` between two sets.
+    fn bitxor_assign(&mut self, rhs: &RoaringBitmapView) {
+        for container in &rhs.containers {
+            match self.containers.binary_search_by_key(&container.key, |c| c.key) {
+                Err(loc) => self.containers.insert(loc, container.to_container()),
+                Ok(loc) => {
+                    let lhs = merge(&self.containers[loc], container, |l, r| l != r);
+                    if lhs.len() != 0 {
+                        self.containers[loc] = lhs;
+                    } else {
+                        self.containers.remove(loc);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,286 @@
+use std::convert::TryInto;
+use std::ops::RangeInclusive;
+use std::slice::ChunksExact;
+
+use super::take;
+use crate::bitmap::store::{ArrayStore, BitmapStore, Interval, RunStore, Store};
+
+/// The serialized data of a container, borrowed as little-endian bytes.
+///
+/// The bytes are not required to be aligned, every value is decoded when it is read.
+#[derive(Clone, Copy)]
+pub enum StoreView<'a> {
+    /// Sorted `u16` values.
+    Array(&'a [u8]),
+    /// 1024 `u64` words.
+    Bitmap(&'a [u8]),
+    /// `(start, length - 1)` pairs of `u16`, without the number of runs prefix.
+    Run(&'a [u8]),
+}
+
+impl<'a> StoreView<'a> {
+    /// Splits the data of a container off the start of `data`, the number of runs of a run
+    /// container is read from its prefix. Returns `None` if `data` is too short.
+    pub fn read(data: &mut &'a [u8], is_run_container: bool, len: u64) -> Option<StoreView<'a>> {
+        if is_run_container {
+            let runs = take(data, 2)?;
+            let runs = u16::from_le_bytes([runs[0], runs[1]]);
+            Some(StoreView::Run(take(data, usize::from(runs) * 4)?))
+        } else if len <= 4096 {
+            Some(StoreView::Array(take(data, len as usize * 2)?))
+        } else {
+            Some(StoreView::Bitmap(take(data, 8 * 1024)?))
+        }
+    }
+
+    pub fn contains(&self, index: u16) -> bool {
+        match *self {
+            StoreView::Array(bytes) => {
+                let n = bytes.len() / 2;
+                let loc = partition_point(n, |i| value(bytes, i) < index);
+                loc < n && value(bytes, loc) == index
+            }
+            StoreView::Bitmap(bytes) => {
+                word(bytes, usize::from(index / 64)) & (1 << (index % 64)) != 0
+            }
+            StoreView::Run(bytes) => {
+                let n = bytes.len() / 4;
+                match partition_point(n, |i| run(bytes, i).start() <= &index) {
+                    0 => false,
+                    loc => index <= *run(bytes, loc - 1).end(),
+                }
+            }
+        }
+    }
+
+    /// Returns the number of values lower than or equal to `index`.
+    pub fn rank(&self, index: u16) -> u64 {
+        match *self {
+            StoreView::Array(bytes) => {
+                partition_point(bytes.len() / 2, |i| value(bytes, i) <= index) as u64
+            }
+            StoreView::Bitmap(bytes) => {
+                let key = usize::from(index / 64);
+                let bit = index % 64;
+                let before: u64 = (0..key).map(|i| u64::from(word(bytes, i).count_ones())).sum();
+                // Shift to the left to keep the bits up to and including `bit`
+                before + u64::from((word(bytes, key) << (63 - bit)).count_ones())
+            }
+            StoreView::Run(..) => self
+                .runs()
+                .take_while(|run| *run.start() <= index)
+                .map(|run| u64::from((*run.end()).min(index) - run.start()) + 1)
+                .sum(),
+        }
+    }
+
+    pub fn select(&self, n: u16) -> Option<u16> {
+        match *self {
+            StoreView::Array(bytes) => {
+                let n = usize::from(n);
+                if n < bytes.len() / 2 {
+                    Some(value(bytes, n))
+                } else {
+                    None
+                }
+            }
+            StoreView::Bitmap(bytes) => {
+                let mut n = u32::from(n);
+                for key in 0..1024 {
+                    let mut bits = word(bytes, key);
+                    let len = bits.count_ones();
+                    if n < len {
+                        for _ in 0..n {
+                            bits &= bits - 1;
+                        }
+                        return Some((key * 64) as u16 + bits.trailing_zeros() as u16);
+                    }
+                    n -= len;
+                }
+                None
+            }
+            StoreView::Run(..) => {
+                let mut n = u32::from(n);
+                for run in self.runs() {
+                    let len = u32::from(run.end() - run.start()) + 1;
+                    if n < len {
+                        return Some(run.start() + n as u16);
+                    }
+                    n -= len;
+                }
+                None
+            }
+        }
+    }
+
+    pub fn min(&self) -> Option<u16> {
+        self.iter().next()
+    }
+
+    pub fn max(&self) -> Option<u16> {
+        match *self {
+            StoreView::Array(bytes) => bytes.len().checked_sub(2).map(|i| value(bytes, i / 2)),
+            StoreView::Bitmap(bytes) => (0..1024).rev().find_map(|key| {
+                let bits = word(bytes, key);
+                if bits != 0 {
+                    Some((key * 64) as u16 + 63 - bits.leading_zeros() as u16)
+                } else {
+                    None
+                }
+            }),
+            StoreView::Run(bytes) => (bytes.len() / 4).checked_sub(1).map(|i| *run(bytes, i).end()),
+        }
+    }
+
+    /// Counts the values of this store, without relying on a cardinality stored elsewhere.
+    pub fn count(&self) -> u64 {
+        match *self {
+            StoreView::Array(bytes) => (bytes.len() / 2) as u64,
+            StoreView::Bitmap(bytes) => {
+                (0..1024).map(|key| u64::from(word(bytes, key).count_ones())).sum()
+            }
+            StoreView::Run(..) => {
+                self.runs().map(|run| u64::from(run.end() - run.start()) + 1).sum()
+            }
+        }
+    }
+
+    /// Checks the invariants the owned stores rely on.
+    pub fn validate(&self) -> Result<(), &'static str> {
+        match *self {
+            StoreView::Array(bytes) => {
+                let n = bytes.len() / 2;
+                if (1..n).all(|i| value(bytes, i - 1) < value(bytes, i)) {
+                    Ok(())
+                } else {
+                    Err("array container values are not sorted")
+                }
+            }
+            StoreView::Bitmap(..) => Ok(()),
+            StoreView::Run(bytes) => {
+                let n = bytes.len() / 4;
+                for i in 0..n {
+                    let (start, len) = (value(bytes, i * 2), value(bytes, i * 2 + 1));
+                    if start.checked_add(len).is_none() {
+                        return Err("run container with invalid run");
+                    }
+                    if i > 0 && u32::from(start) <= u32::from(*run(bytes, i - 1).end()) + 1 {
+                        return Err("run container runs are not sorted");
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Decodes the runs of a run store, the other stores yield nothing.
+    pub fn runs(&self) -> impl Iterator<Item = RangeInclusive<u16>> + 'a {
+        let bytes = match *self {
+            StoreView::Run(bytes) => bytes,
+            _ => &[],
+        };
+        bytes.chunks_exact(4).map(|chunk| {
+            let start = u16::from_le_bytes([chunk[0], chunk[1]]);
+            let len = u16::from_le_bytes([chunk[2], chunk[3]]);
+            start..=start.saturating_add(len)
+        })
+    }
+
+    pub fn iter(&self) -> Iter<'a> {
+        match *self {
+            StoreView::Array(bytes) => Iter::Array(bytes.chunks_exact(2)),
+            StoreView::Bitmap(bytes) => Iter::Bitmap { bytes, key: 0, value: word(bytes, 0) },
+            StoreView::Run(bytes) => Iter::Run { runs: bytes.chunks_exact(4), next: 1, end: 0 },
+        }
+    }
+
+    /// Copies the values into an owned store.
+    pub fn to_store(self, len: u64) -> Store {
+        match self {
+            StoreView::Array(..) => {
+                Store::Array(ArrayStore::from_vec_unchecked(self.iter().collect()))
+            }
+            StoreView::Bitmap(bytes) => {
+                let mut bits = Box::new([0; 1024]);
+                bits.iter_mut().enumerate().for_each(|(key, bits)| *bits = word(bytes, key));
+                Store::Bitmap(BitmapStore::from_unchecked(len, bits))
+            }
+            StoreView::Run(..) => Store::Run(RunStore::from_vec_unchecked(
+                self.runs().map(|run| Interval::new(*run.start(), *run.end())).collect(),
+            )),
+        }
+    }
+}
+
+pub enum Iter<'a> {
+    Array(ChunksExact<'a, u8>),
+    Bitmap { bytes: &'a [u8], key: usize, value: u64 },
+    Run { runs: ChunksExact<'a, u8>, next: u32, end: u32 },
+}
+
+impl Iterator for Iter<'_> {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<u16> {
+        match self {
+            Iter::Array(chunks) => {
+                chunks.next().map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+            }
+            Iter::Bitmap { bytes, key, value } => loop {
+                if *value != 0 {
+                    let index = value.trailing_zeros() as usize;
+                    *value &= *value - 1;
+                    return Some((*key * 64 + index) as u16);
+                }
+                *key += 1;
+                if *key >= 1024 {
+                    return None;
+                }
+                *value = word(bytes, *key);
+            },
+            Iter::Run { runs, next, end } => {
+                if *next > *end {
+                    let chunk = runs.next()?;
+                    let start = u16::from_le_bytes([chunk[0], chunk[1]]);
+                    let len = u16::from_le_bytes([chunk[2], chunk[3]]);
+                    *next = u32::from(start);
+                    *end = u32::from(start) + u32::from(len);
+                }
+                let index = *next;
+                *next += 1;
+                Some(index as u16)
+            }
+        }
+    }
+}
+
+/// Returns the index of the first element for which `pred` doesn't hold,
+/// `pred` must hold for a prefix of `0..n`.
+pub(in crate::bitmap) fn partition_point(n: usize, pred: impl Fn(usize) -> bool) -> usize {
+    let (mut lo, mut hi) = (0, n);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if pred(mid) {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
+
+#[inline]
+fn value(bytes: &[u8], i: usize) -> u16 {
+    u16::from_le_bytes([bytes[i * 2], bytes[i * 2 + 1]])
+}
+
+#[inline]
+fn word(bytes: &[u8], i: usize) -> u64 {
+    u64::from_le_bytes(bytes[i * 8..i * 8 + 8].try_into().unwrap())
+}
+
+#[inline]
+fn run(bytes: &[u8], i: usize) -> RangeInclusive<u16> {
+    let start = value(bytes, i * 2);
+    start..=start.saturating_add(value(bytes, i * 2 + 1))
+}
@@ -0,0 +1,19 @@
+use std::fmt;
+
+use crate::Roaring64Bitmap;
+
+impl fmt::Debug for Roaring64Bitmap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.len() < 16 {
+            write!(f, "Roaring64Bitmap<{:?}>", self.iter().collect::<Vec<u64>>())
+        } else {
+            write!(
+                f,
+                "Roaring64Bitmap<{:?} values between {:?} and {:?}>",
+                self.len(),
+                self.min().unwrap(),
+                self.max().unwrap()
+            )
+        }
+    }
+}
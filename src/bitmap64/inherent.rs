@@ -0,0 +1,298 @@
+use crate::bitmap::container::Container;
+use crate::Roaring64Bitmap;
+
+use super::util;
+
+impl Roaring64Bitmap {
+    /// Creates an empty `Roaring64Bitmap`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::Roaring64Bitmap;
+    /// let rb = Roaring64Bitmap::new();
+    /// ```
+    pub fn new() -> Roaring64Bitmap {
+        Roaring64Bitmap { containers: Vec::new() }
+    }
+
+    /// Searches for the container of the given 48 most significant bits.
+    pub(super) fn find(&self, high: u32, key: u16) -> Result<usize, usize> {
+        self.containers.binary_search_by_key(&(high, key), |(high, c)| (*high, c.key))
+    }
+
+    /// Adds a value to the set.
+    ///
+    /// Returns whether the value was absent from the set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::Roaring64Bitmap;
+    ///
+    /// let mut rb = Roaring64Bitmap::new();
+    /// assert_eq!(rb.insert(3), true);
+    /// assert_eq!(rb.insert(3), false);
+    /// assert_eq!(rb.contains(3), true);
+    /// ```
+    pub fn insert(&mut self, value: u64) -> bool {
+        let (high, key, index) = util::split(value);
+        let loc = match self.find(high, key) {
+            Ok(loc) => loc,
+            Err(loc) => {
+                self.containers.insert(loc, (high, Container::new(key)));
+                loc
+            }
+        };
+        self.containers[loc].1.insert(index)
+    }
+
+    /// Removes a value from the set.
+    ///
+    /// Returns whether the value was present in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::Roaring64Bitmap;
+    ///
+    /// let mut rb = Roaring64Bitmap::new();
+    /// rb.insert(3);
+    /// assert_eq!(rb.remove(3), true);
+    /// assert_eq!(rb.remove(3), false);
+    /// assert_eq!(rb.contains(3), false);
+    /// ```
+    pub fn remove(&mut self, value: u64) -> bool {
+        let (high, key, index) = util::split(value);
+        match self.find(high, key) {
+            Ok(loc) if self.containers[loc].1.remove(index) => {
+                if self.containers[loc].1.len() == 0 {
+                    self.containers.remove(loc);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if this set contains the specified integer.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::Roaring64Bitmap;
+    ///
+    /// let mut rb = Roaring64Bitmap::new();
+    /// rb.insert(1 << 40);
+    /// assert_eq!(rb.contains(0), false);
+    /// assert_eq!(rb.contains(1 << 40), true);
+    /// assert_eq!(rb.contains(100), false);
+    /// ```
+    pub fn contains(&self, value: u64) -> bool {
+        let (high, key, index) = util::split(value);
+        match self.find(high, key) {
+            Ok(loc) => self.containers[loc].1.contains(index),
+            Err(_) => false,
+        }
+    }
+
+    /// Clears all integers in this set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::Roaring64Bitmap;
+    ///
+    /// let mut rb = Roaring64Bitmap::new();
+    /// rb.insert(1);
+    /// assert_eq!(rb.contains(1), true);
+    /// rb.clear();
+    /// assert_eq!(rb.contains(1), false);
+    /// ```
+    pub fn clear(&mut self) {
+        self.containers.clear();
+    }
+
+    /// Returns `true` if there are no integers in this set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::Roaring64Bitmap;
+    ///
+    /// let mut rb = Roaring64Bitmap::new();
+    /// assert_eq!(rb.is_empty(), true);
+    ///
+    /// rb.insert(3);
+    /// assert_eq!(rb.is_empty(), false);
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.containers.is_empty()
+    }
+
+    /// Returns the number of distinct integers added to the set.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::Roaring64Bitmap;
+    ///
+    /// let mut rb = Roaring64Bitmap::new();
+    /// assert_eq!(rb.len(), 0);
+    ///
+    /// rb.insert(3);
+    /// assert_eq!(rb.len(), 1);
+    ///
+    /// rb.insert(3);
+    /// rb.insert(4 << 50);
+    /// assert_eq!(rb.len(), 2);
+    /// ```
+    pub fn len(&self) -> u64 {
+        self.containers.iter().map(|(_, container)| container.len()).sum()
+    }
+
+    /// Returns the minimum value in the set (if the set is non-empty).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::Roaring64Bitmap;
+    ///
+    /// let mut rb = Roaring64Bitmap::new();
+    /// assert_eq!(rb.min(), None);
+    ///
+    /// rb.insert(3);
+    /// rb.insert(4 << 50);
+    /// assert_eq!(rb.min(), Some(3));
+    /// ```
+    pub fn min(&self) -> Option<u64> {
+        self.containers
+            .first()
+            .and_then(|(high, head)| head.min().map(|min| util::join(*high, head.key, min)))
+    }
+
+    /// Returns the maximum value in the set (if the set is non-empty).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::Roaring64Bitmap;
+    ///
+    /// let mut rb = Roaring64Bitmap::new();
+    /// assert_eq!(rb.max(), None);
+    ///
+    /// rb.insert(3);
+    /// rb.insert(4 << 50);
+    /// assert_eq!(rb.max(), Some(4 << 50));
+    /// ```
+    pub fn max(&self) -> Option<u64> {
+        self.containers
+            .last()
+            .and_then(|(high, tail)| tail.max().map(|max| util::join(*high, tail.key, max)))
+    }
+
+    /// Returns the number of integers that are <= value. rank(u64::MAX) == len()
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::Roaring64Bitmap;
+    ///
+    /// let mut rb = Roaring64Bitmap::new();
+    /// assert_eq!(rb.rank(0), 0);
+    ///
+    /// rb.insert(3);
+    /// rb.insert(4 << 50);
+    /// assert_eq!(rb.rank(3), 1);
+    /// assert_eq!(rb.rank(10), 1);
+    /// assert_eq!(rb.rank(u64::MAX), 2);
+    /// ```
+    pub fn rank(&self, value: u64) -> u64 {
+        let (high, key, index) = util::split(value);
+
+        match self.find(high, key) {
+            Ok(i) => {
+                self.containers[i].1.rank(index)
+                    + self.containers[..i].iter().map(|(_, c)| c.len()).sum::<u64>()
+            }
+            Err(i) => self.containers[..i].iter().map(|(_, c)| c.len()).sum(),
+        }
+    }
+
+    /// Returns the `n`th integer in the set or `None` if `n >= len()`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::Roaring64Bitmap;
+    ///
+    /// let rb: Roaring64Bitmap = [0, 10, 100 << 40].iter().collect();
+    ///
+    /// assert_eq!(rb.select(0), Some(0));
+    /// assert_eq!(rb.select(1), Some(10));
+    /// assert_eq!(rb.select(2), Some(100 << 40));
+    /// assert_eq!(rb.select(3), None);
+    /// ```
+    pub fn select(&self, mut n: u64) -> Option<u64> {
+        for (high, container) in &self.containers {
+            let len = container.len();
+            if len > n {
+                return container
+                    .store
+                    .select(n as u16)
+                    .map(|index| util::join(*high, container.key, index));
+            }
+            n -= len;
+        }
+
+        None
+    }
+
+    /// Converts every container to its most compact representation, using run containers
+    /// where they are smaller than arrays or bitmaps.
+    ///
+    /// Returns `true` if the bitmap contains at least one run container afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::Roaring64Bitmap;
+    ///
+    /// let mut rb: Roaring64Bitmap = (0..10_000).collect();
+    /// let size = rb.serialized_size();
+    ///
+    /// assert!(rb.run_optimize());
+    /// assert!(rb.serialized_size() < size);
+    /// assert_eq!(rb, (0..10_000).collect());
+    /// ```
+    pub fn run_optimize(&mut self) -> bool {
+        let mut has_runs = false;
+        for (_, container) in &mut self.containers {
+            has_runs |= container.optimize();
+        }
+        has_runs
+    }
+
+    /// Removes run-length encoding, converting every run container to an array or a bitmap.
+    ///
+    /// Returns `true` if at least one container was converted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::Roaring64Bitmap;
+    ///
+    /// let mut rb: Roaring64Bitmap = (0..10_000).collect();
+    /// rb.run_optimize();
+    ///
+    /// assert!(rb.remove_run_compression());
+    /// assert_eq!(rb, (0..10_000).collect());
+    /// ```
+    pub fn remove_run_compression(&mut self) -> bool {
+        let mut changed = false;
+        for (_, container) in &mut self.containers {
+            changed |= container.remove_run_compression();
+        }
+        changed
+    }
+}
@@ -0,0 +1,229 @@
+use std::iter::{self, FromIterator};
+use std::{slice, vec};
+
+use super::util;
+use crate::bitmap::container::{self, Container};
+use crate::Roaring64Bitmap;
+
+struct To64Iter<'a> {
+    high: u32,
+    inner: container::Iter<'a>,
+}
+
+impl Iterator for To64Iter<'_> {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        self.inner.next().map(|n| util::join(self.high, (n >> 16) as u16, n as u16))
+    }
+}
+
+impl DoubleEndedIterator for To64Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|n| util::join(self.high, (n >> 16) as u16, n as u16))
+    }
+}
+
+fn to64iter(t: &(u32, Container)) -> To64Iter<'_> {
+    To64Iter { high: t.0, inner: (&t.1).into_iter() }
+}
+
+struct To64IntoIter {
+    high: u32,
+    inner: container::Iter<'static>,
+}
+
+impl Iterator for To64IntoIter {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        self.inner.next().map(|n| util::join(self.high, (n >> 16) as u16, n as u16))
+    }
+}
+
+impl DoubleEndedIterator for To64IntoIter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|n| util::join(self.high, (n >> 16) as u16, n as u16))
+    }
+}
+
+fn to64intoiter(t: (u32, Container)) -> To64IntoIter {
+    To64IntoIter { high: t.0, inner: t.1.into_iter() }
+}
+
+type InnerIter<'a> = iter::FlatMap<
+    slice::Iter<'a, (u32, Container)>,
+    To64Iter<'a>,
+    fn(&'a (u32, Container)) -> To64Iter<'a>,
+>;
+type InnerIntoIter = iter::FlatMap<
+    vec::IntoIter<(u32, Container)>,
+    To64IntoIter,
+    fn((u32, Container)) -> To64IntoIter,
+>;
+
+/// An iterator for `Roaring64Bitmap`.
+pub struct Iter<'a> {
+    inner: InnerIter<'a>,
+    size_hint: u64,
+}
+
+/// An iterator for `Roaring64Bitmap`.
+pub struct IntoIter {
+    inner: InnerIntoIter,
+    size_hint: u64,
+}
+
+impl Iter<'_> {
+    fn new(containers: &[(u32, Container)]) -> Iter<'_> {
+        let size_hint = containers.iter().map(|(_, c)| c.len()).sum();
+        let i = containers.iter().flat_map(to64iter as _);
+        Iter { inner: i, size_hint }
+    }
+}
+
+impl IntoIter {
+    fn new(containers: Vec<(u32, Container)>) -> IntoIter {
+        let size_hint = containers.iter().map(|(_, c)| c.len()).sum();
+        let i = containers.into_iter().flat_map(to64intoiter as _);
+        IntoIter { inner: i, size_hint }
+    }
+}
+
+impl Iterator for Iter<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        self.size_hint = self.size_hint.saturating_sub(1);
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.size_hint < usize::MAX as u64 {
+            (self.size_hint as usize, Some(self.size_hint as usize))
+        } else {
+            (usize::MAX, None)
+        }
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.size_hint = self.size_hint.saturating_sub(1);
+        self.inner.next_back()
+    }
+}
+
+#[cfg(target_pointer_width = "64")]
+impl ExactSizeIterator for Iter<'_> {
+    fn len(&self) -> usize {
+        self.size_hint as usize
+    }
+}
+
+impl Iterator for IntoIter {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        self.size_hint = self.size_hint.saturating_sub(1);
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.size_hint < usize::MAX as u64 {
+            (self.size_hint as usize, Some(self.size_hint as usize))
+        } else {
+            (usize::MAX, None)
+        }
+    }
+}
+
+impl DoubleEndedIterator for IntoIter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.size_hint = self.size_hint.saturating_sub(1);
+        self.inner.next_back()
+    }
+}
+
+#[cfg(target_pointer_width = "64")]
+impl ExactSizeIterator for IntoIter {
+    fn len(&self) -> usize {
+        self.size_hint as usize
+    }
+}
+
+impl Roaring64Bitmap {
+    /// Iterator over each value stored in the Roaring64Bitmap, guarantees values are ordered by
+    /// value.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::Roaring64Bitmap;
+    /// use std::iter::FromIterator;
+    ///
+    /// let bitmap = (1..3).collect::<Roaring64Bitmap>();
+    /// let mut iter = bitmap.iter();
+    ///
+    /// assert_eq!(iter.next(), Some(1));
+    /// assert_eq!(iter.next(), Some(2));
+    /// assert_eq!(iter.next(), None);
+    /// ```
+    pub fn iter(&self) -> Iter<'_> {
+        Iter::new(&self.containers)
+    }
+}
+
+impl<'a> IntoIterator for &'a Roaring64Bitmap {
+    type Item = u64;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl IntoIterator for Roaring64Bitmap {
+    type Item = u64;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> IntoIter {
+        IntoIter::new(self.containers)
+    }
+}
+
+impl<const N: usize> From<[u64; N]> for Roaring64Bitmap {
+    fn from(arr: [u64; N]) -> Self {
+        Roaring64Bitmap::from_iter(arr)
+    }
+}
+
+impl FromIterator<u64> for Roaring64Bitmap {
+    fn from_iter<I: IntoIterator<Item = u64>>(iterator: I) -> Roaring64Bitmap {
+        let mut rb = Roaring64Bitmap::new();
+        rb.extend(iterator);
+        rb
+    }
+}
+
+impl<'a> FromIterator<&'a u64> for Roaring64Bitmap {
+    fn from_iter<I: IntoIterator<Item = &'a u64>>(iterator: I) -> Roaring64Bitmap {
+        let mut rb = Roaring64Bitmap::new();
+        rb.extend(iterator);
+        rb
+    }
+}
+
+impl Extend<u64> for Roaring64Bitmap {
+    fn extend<I: IntoIterator<Item = u64>>(&mut self, iterator: I) {
+        for value in iterator {
+            self.insert(value);
+        }
+    }
+}
+
+impl<'a> Extend<&'a u64> for Roaring64Bitmap {
+    fn extend<I: IntoIterator<Item = &'a u64>>(&mut self, iterator: I) {
+        for value in iterator {
+            self.insert(*value);
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use crate::bitmap::container::Container;
+
+mod fmt;
+mod util;
+
+// Order of these modules matters as it determines the `impl` blocks order in
+// the docs
+mod inherent;
+mod iter;
+mod ops;
+mod serialization;
+
+pub use self::iter::{IntoIter, Iter};
+
+/// A compressed bitmap with u64 values, compatible with the `roaring64_bitmap_t` of CRoaring
+/// and the `Roaring64Bitmap` of Java.
+///
+/// The containers are keyed on the 48 most significant bits of their values, unlike
+/// [`RoaringTreemap`](crate::RoaringTreemap) which allocates a whole `RoaringBitmap` for each
+/// distinct value of the 32 most significant bits. This makes it much more compact when the
+/// values are sparse.
+///
+/// # Examples
+///
+/// ```rust
+/// use roaring::Roaring64Bitmap;
+///
+/// let mut rb = Roaring64Bitmap::new();
+///
+/// // insert sparse values
+/// rb.insert(2);
+/// rb.insert(3 << 40);
+/// rb.insert(5 << 50);
+/// rb.insert(7 << 60);
+/// println!("total bits set to true: {}", rb.len());
+/// ```
+#[derive(Clone, Default, PartialEq)]
+pub struct Roaring64Bitmap {
+    /// The containers paired with the 32 most significant bits of their values, the container
+    /// keys are the next 16 bits. They are sorted by these 48 bits.
+    containers: Vec<(u32, Container)>,
+}
@@ -0,0 +1,298 @@
+use std::mem;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Sub, SubAssign};
+
+use retain_mut::RetainMut;
+
+use crate::Roaring64Bitmap;
+
+impl BitOr<Roaring64Bitmap> for Roaring64Bitmap {
+    type Output = Roaring64Bitmap;
+
+    /// An `union` between two sets.
+    fn bitor(mut self, rhs: Roaring64Bitmap) -> Roaring64Bitmap {
+        BitOrAssign::bitor_assign(&mut self, rhs);
+        self
+    }
+}
+
+impl BitOr<&Roaring64Bitmap> for Roaring64Bitmap {
+    type Output = Roaring64Bitmap;
+
+    /// An `union` between two sets.
+    fn bitor(mut self, rhs: &Roaring64Bitmap) -> Roaring64Bitmap {
+        BitOrAssign::bitor_assign(&mut self, rhs);
+        self
+    }
+}
+
+impl BitOr<Roaring64Bitmap> for &Roaring64Bitmap {
+    type Output = Roaring64Bitmap;
+
+    /// An `union` between two sets.
+    fn bitor(self, rhs: Roaring64Bitmap) -> Roaring64Bitmap {
+        BitOr::bitor(rhs, self)
+    }
+}
+
+impl BitOr<&Roaring64Bitmap> for &Roaring64Bitmap {
+    type Output = Roaring64Bitmap;
+
+    /// An `union` between two sets.
+    fn bitor(self, rhs: &Roaring64Bitmap) -> Roaring64Bitmap {
+        if self.len() < rhs.len() {
+            BitOr::bitor(rhs.clone(), self)
+        } else {
+            BitOr::bitor(self.clone(), rhs)
+        }
+    }
+}
+
+impl BitOrAssign<Roaring64Bitmap> for Roaring64Bitmap {
+    /// An `union` between two sets.
+    fn bitor_assign(&mut self, mut rhs: Roaring64Bitmap) {
+        // We make sure that we apply the union operation on the biggest map.
+        if self.len() < rhs.len() {
+            mem::swap(self, &mut rhs);
+        }
+
+        for (high, container) in rhs.containers {
+            match self.find(high, container.key) {
+                Err(loc) => self.containers.insert(loc, (high, container)),
+                Ok(loc) => BitOrAssign::bitor_assign(&mut self.containers[loc].1, container),
+            }
+        }
+    }
+}
+
+impl BitOrAssign<&Roaring64Bitmap> for Roaring64Bitmap {
+    /// An `union` between two sets.
+    fn bitor_assign(&mut self, rhs: &Roaring64Bitmap) {
+        for (high, container) in &rhs.containers {
+            match self.find(*high, container.key) {
+                Err(loc) => self.containers.insert(loc, (*high, container.clone())),
+                Ok(loc) => BitOrAssign::bitor_assign(&mut self.containers[loc].1, container),
+            }
+        }
+    }
+}
+
+impl BitAnd<Roaring64Bitmap> for Roaring64Bitmap {
+    type Output = Roaring64Bitmap;
+
+    /// An `intersection` between two sets.
+    fn bitand(mut self, rhs: Roaring64Bitmap) -> Roaring64Bitmap {
+        BitAndAssign::bitand_assign(&mut self, &rhs);
+        self
+    }
+}
+
+impl BitAnd<&Roaring64Bitmap> for Roaring64Bitmap {
+    type Output = Roaring64Bitmap;
+
+    /// An `intersection` between two sets.
+    fn bitand(mut self, rhs: &Roaring64Bitmap) -> Roaring64Bitmap {
+        BitAndAssign::bitand_assign(&mut self, rhs);
+        self
+    }
+}
+
+impl BitAnd<Roaring64Bitmap> for &Roaring64Bitmap {
+    type Output = Roaring64Bitmap;
+
+    /// An `intersection` between two sets.
+    fn bitand(self, rhs: Roaring64Bitmap) -> Roaring64Bitmap {
+        BitAnd::bitand(rhs, self)
+    }
+}
+
+impl BitAnd<&Roaring64Bitmap> for &Roaring64Bitmap {
+    type Output = Roaring64Bitmap;
+
+    /// An `intersection` between two sets.
+    fn bitand(self, rhs: &Roaring64Bitmap) -> Roaring64Bitmap {
+        let mut containers = Vec::new();
+
+        for (high, lhs) in &self.containers {
+            if let Ok(loc) = rhs.find(*high, lhs.key) {
+                let container = BitAnd::bitand(lhs, &rhs.containers[loc].1);
+                if container.len() != 0 {
+                    containers.push((*high, container));
+                }
+            }
+        }
+
+        Roaring64Bitmap { containers }
+    }
+}
+
+impl BitAndAssign<Roaring64Bitmap> for Roaring64Bitmap {
+    /// An `intersection` between two sets.
+    fn bitand_assign(&mut self, rhs: Roaring64Bitmap) {
+        BitAndAssign::bitand_assign(self, &rhs)
+    }
+}
+
+impl BitAndAssign<&Roaring64Bitmap> for Roaring64Bitmap {
+    /// An `intersection` between two sets.
+    fn bitand_assign(&mut self, rhs: &Roaring64Bitmap) {
+        RetainMut::retain_mut(&mut self.containers, |(high, cont)| {
+            match rhs.find(*high, cont.key) {
+                Ok(loc) => {
+                    BitAndAssign::bitand_assign(cont, &rhs.containers[loc].1);
+                    cont.len() != 0
+                }
+                Err(_) => false,
+            }
+        })
+    }
+}
+
+impl Sub<Roaring64Bitmap> for Roaring64Bitmap {
+    type Output = Roaring64Bitmap;
+
+    /// A `difference` between two sets.
+    fn sub(mut self, rhs: Roaring64Bitmap) -> Roaring64Bitmap {
+        SubAssign::sub_assign(&mut self, &rhs);
+        self
+    }
+}
+
+impl Sub<&Roaring64Bitmap> for Roaring64Bitmap {
+    type Output = Roaring64Bitmap;
+
+    /// A `difference` between two sets.
+    fn sub(mut self, rhs: &Roaring64Bitmap) -> Roaring64Bitmap {
+        SubAssign::sub_assign(&mut self, rhs);
+        self
+    }
+}
+
+impl Sub<Roaring64Bitmap> for &Roaring64Bitmap {
+    type Output = Roaring64Bitmap;
+
+    /// A `difference` between two sets.
+    fn sub(self, rhs: Roaring64Bitmap) -> Roaring64Bitmap {
+        Sub::sub(self, &rhs)
+    }
+}
+
+impl Sub<&Roaring64Bitmap> for &Roaring64Bitmap {
+    type Output = Roaring64Bitmap;
+
+    /// A `difference` between two sets.
+    fn sub(self, rhs: &Roaring64Bitmap) -> Roaring64Bitmap {
+        let mut containers = Vec::new();
+
+        for (high, lhs) in &self.containers {
+            match rhs.find(*high, lhs.key) {
+                Ok(loc) => {
+                    let container = Sub::sub(lhs, &rhs.containers[loc].1);
+                    if container.len() != 0 {
+                        containers.push((*high, container));
+                    }
+                }
+                Err(_) => containers.push((*high, lhs.clone())),
+            }
+        }
+
+        Roaring64Bitmap { containers }
+    }
+}
+
+impl SubAssign<Roaring64Bitmap> for Roaring64Bitmap {
+    /// A `difference` between two sets.
+    fn sub_assign(&mut self, rhs: Roaring64Bitmap) {
+        SubAssign::sub_assign(self, &rhs)
+    }
+}
+
+impl SubAssign<&Roaring64Bitmap> for Roaring64Bitmap {
+    /// A `difference` between two sets.
+    fn sub_assign(&mut self, rhs: &Roaring64Bitmap) {
+        RetainMut::retain_mut(&mut self.containers, |(high, cont)| {
+            match rhs.find(*high, cont.key) {
+                Ok(loc) => {
+                    SubAssign::sub_assign(cont, &rhs.containers[loc].1);
+                    cont.len() != 0
+                }
+                Err(_) => true,
+            }
+        })
+    }
+}
+
+impl BitXor<Roaring64Bitmap> for Roaring64Bitmap {
+    type Output = Roaring64Bitmap;
+
+    /// A `symmetric difference` between two sets.
+    fn bitxor(mut self, rhs: Roaring64Bitmap) -> Roaring64Bitmap {
+        BitXorAssign::bitxor_assign(&mut self, rhs);
+        self
+    }
+}
+
+impl BitXor<&Roaring64Bitmap> for Roaring64Bitmap {
+    type Output = Roaring64Bitmap;
+
+    /// A `symmetric difference` between two sets.
+    fn bitxor(mut self, rhs: &Roaring64Bitmap) -> Roaring64Bitmap {
+        BitXorAssign::bitxor_assign(&mut self, rhs);
+        self
+    }
+}
+
+impl BitXor<Roaring64Bitmap> for &Roaring64Bitmap {
+    type Output = Roaring64Bitmap;
+
+    /// A `symmetric difference` between two sets.
+    fn bitxor(self, rhs: Roaring64Bitmap) -> Roaring64Bitmap {
+        BitXor::bitxor(rhs, self)
+    }
+}
+
+impl BitXor<&Roaring64Bitmap> for &Roaring64Bitmap {
+    type Output = Roaring64Bitmap;
+
+    /// A `symmetric difference` between two sets.
+    fn bitxor(self, rhs: &Roaring64Bitmap) -> Roaring64Bitmap {
+        if self.len() < rhs.len() {
+            BitXor::bitxor(rhs.clone(), self)
+        } else {
+            BitXor::bitxor(self.clone(), rhs)
+        }
+    }
+}
+
+impl BitXorAssign<Roaring64Bitmap> for Roaring64Bitmap {
+    /// A `symmetric difference` between two sets.
+    fn bitxor_assign(&mut self, rhs: Roaring64Bitmap) {
+        for (high, container) in rhs.containers {
+            match self.find(high, container.key) {
+                Err(loc) => self.containers.insert(loc, (high, container)),
+                Ok(loc) => {
+                    BitXorAssign::bitxor_assign(&mut self.containers[loc].1, container);
+                    if self.containers[loc].1.len() == 0 {
+                        self.containers.remove(loc);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl BitXorAssign<&Roaring64Bitmap> for Roaring64Bitmap {
+    /// A `symmetric difference` between two sets.
+    fn bitxor_assign(&mut self, rhs: &Roaring64Bitmap) {
+        for (high, container) in &rhs.containers {
+            match self.find(*high, container.key) {
+                Err(loc) => self.containers.insert(loc, (*high, container.clone())),
+                Ok(loc) => {
+                    BitXorAssign::bitxor_assign(&mut self.containers[loc].1, container);
+                    if self.containers[loc].1.len() == 0 {
+                        self.containers.remove(loc);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use std::io;
+use std::iter;
+use std::mem::size_of;
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::bitmap::container::Container;
+use crate::bitmap::serialization::{
+    header_size, serialize_header_into, serialize_store_into, serialized_store_size,
+    CountingReader, Descriptor,
+};
+use crate::{DeserializeError, Roaring64Bitmap, RoaringBitmap};
+
+impl Roaring64Bitmap {
+    /// Return the size in bytes of the serialized output.
+    /// This is compatible with the official C/C++, Java and Go implementations.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::Roaring64Bitmap;
+    ///
+    /// let rb1: Roaring64Bitmap = (1..4).collect();
+    /// let mut bytes = Vec::with_capacity(rb1.serialized_size());
+    /// rb1.serialize_into(&mut bytes).unwrap();
+    /// let rb2 = Roaring64Bitmap::deserialize_from(&bytes[..]).unwrap();
+    ///
+    /// assert_eq!(rb1, rb2);
+    /// ```
+    pub fn serialized_size(&self) -> usize {
+        self.groups().fold(size_of::<u64>(), |acc, (_, containers)| {
+            let descriptors = containers.iter().map(|(_, c)| Descriptor::of(c));
+            let container_sizes: usize =
+                containers.iter().map(|(_, c)| serialized_store_size(&c.store)).sum();
+            acc + size_of::<u32>() + header_size(descriptors) + container_sizes
+        })
+    }
+
+    /// Serialize this bitmap into the portable format of 64-bit bitmaps, as written by
+    /// `roaring64_bitmap_portable_serialize` in CRoaring and `Roaring64Bitmap::serializePortable`
+    /// in Java.
+    ///
+    /// The output is the number of distinct values of the 32 most significant bits, followed by
+    /// each of these values and the bitmap of the 32 least significant bits in
+    /// [the standard Roaring on-disk format][format]. It is the format written by
+    /// [`RoaringTreemap::serialize_into`](crate::RoaringTreemap::serialize_into).
+    ///
+    /// [format]: https://github.com/RoaringBitmap/RoaringFormatSpec
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::Roaring64Bitmap;
+    ///
+    /// let rb1: Roaring64Bitmap = (1..4).collect();
+    /// let mut bytes = vec![];
+    /// rb1.serialize_into(&mut bytes).unwrap();
+    /// let rb2 = Roaring64Bitmap::deserialize_from(&bytes[..]).unwrap();
+    ///
+    /// assert_eq!(rb1, rb2);
+    /// ```
+    pub fn serialize_into<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_u64::<LittleEndian>(self.groups().count() as u64)?;
+
+        for (high, containers) in self.groups() {
+            writer.write_u32::<LittleEndian>(high)?;
+            serialize_header_into(containers.iter().map(|(_, c)| Descriptor::of(c)), &mut writer)?;
+            for (_, container) in containers {
+                serialize_store_into(&container.store, &mut writer)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Deserialize a bitmap into memory from the portable format of 64-bit bitmaps.
+    ///
+    /// This is compatible with the official C/C++, Java and Go implementations.
+    /// This method checks that all of the internal values are valid.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::Roaring64Bitmap;
+    ///
+    /// let rb1: Roaring64Bitmap = (1..4).collect();
+    /// let mut bytes = vec![];
+    /// rb1.serialize_into(&mut bytes).unwrap();
+    /// let rb2 = Roaring64Bitmap::deserialize_from(&bytes[..]).unwrap();
+    ///
+    /// assert_eq!(rb1, rb2);
+    /// ```
+    pub fn deserialize_from<R: io::Read>(reader: R) -> Result<Self, DeserializeError> {
+        Roaring64Bitmap::deserialize_from_impl(reader, |r| RoaringBitmap::deserialize_checked(r))
+    }
+
+    /// Deserialize a bitmap into memory from the portable format of 64-bit bitmaps.
+    ///
+    /// This is compatible with the official C/C++, Java and Go implementations.
+    /// This method is memory safe but will not check if the data is a valid bitmap.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::Roaring64Bitmap;
+    ///
+    /// let rb1: Roaring64Bitmap = (1..4).collect();
+    /// let mut bytes = vec![];
+    /// rb1.serialize_into(&mut bytes).unwrap();
+    /// let rb2 = Roaring64Bitmap::deserialize_unchecked_from(&bytes[..]).unwrap();
+    ///
+    /// assert_eq!(rb1, rb2);
+    /// ```
+    pub fn deserialize_unchecked_from<R: io::Read>(reader: R) -> Result<Self, DeserializeError> {
+        Roaring64Bitmap::deserialize_from_impl(reader, |r| RoaringBitmap::deserialize_unchecked(r))
+    }
+
+    fn deserialize_from_impl<R, F>(
+        reader: R,
+        mut deserialize_bitmap: F,
+    ) -> Result<Self, DeserializeError>
+    where
+        R: io::Read,
+        F: FnMut(&mut CountingReader<R>) -> Result<RoaringBitmap, DeserializeError>,
+    {
+        let mut reader = CountingReader { inner: reader, offset: 0 };
+        let eof = |offset| move |e| DeserializeError::from_io(e, None, offset);
+        let size = reader.read_u64::<LittleEndian>().map_err(eof(0))?;
+
+        let mut s = Self::new();
+        let mut previous = None;
+
+        for _ in 0..size {
+            let offset = reader.offset;
+            let high = reader.read_u32::<LittleEndian>().map_err(eof(offset))?;
+            if previous.map_or(false, |previous| previous >= high) {
+                return Err(DeserializeError::UnsortedWideKeys { key: u64::from(high), offset });
+            }
+            previous = Some(high);
+
+            // The errors of the bitmap are located from its start
+            let start = reader.offset;
+            let bitmap = deserialize_bitmap(&mut reader).map_err(|e| e.shifted(start))?;
+            s.containers.extend(bitmap.containers.into_iter().map(|c| (high, c)));
+        }
+
+        Ok(s)
+    }
+
+    /// Iterates over the containers grouped by the 32 most significant bits of their values.
+    fn groups(&self) -> impl Iterator<Item = (u32, &[(u32, Container)])> {
+        let mut containers = &self.containers[..];
+        iter::from_fn(move || {
+            let high = containers.first()?.0;
+            let len = containers.iter().take_while(|(h, _)| *h == high).count();
+            let (group, rest) = containers.split_at(len);
+            containers = rest;
+            Some((high, group))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Roaring64Bitmap, RoaringTreemap};
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn test_serialization(
+            treemap in RoaringTreemap::arbitrary(),
+        ) {
+            let bitmap: Roaring64Bitmap = treemap.iter().collect();
+            let mut buffer = Vec::new();
+            bitmap.serialize_into(&mut buffer).unwrap();
+            prop_assert_eq!(buffer.len(), bitmap.serialized_size());
+            prop_assert_eq!(&bitmap, &Roaring64Bitmap::deserialize_from(buffer.as_slice()).unwrap());
+
+            // The format is the one of the treemaps
+            let treemap: RoaringTreemap = treemap.iter().collect();
+            let mut expected = Vec::new();
+            treemap.serialize_into(&mut expected).unwrap();
+            prop_assert_eq!(buffer, expected);
+        }
+    }
+}
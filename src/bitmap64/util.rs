@@ -0,0 +1,34 @@
+/// Splits a value into the 32 most significant bits, the container key and the container index.
+#[inline]
+pub fn split(value: u64) -> (u32, u16, u16) {
+    ((value >> 32) as u32, (value >> 16) as u16, value as u16)
+}
+
+#[inline]
+pub fn join(high: u32, key: u16, index: u16) -> u64 {
+    (u64::from(high) << 32) | (u64::from(key) << 16) | u64::from(index)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{join, split};
+
+    #[test]
+    fn test_split_u64() {
+        assert_eq!((0x0000_0000, 0x0000, 0x0000), split(0x0000_0000_0000_0000u64));
+        assert_eq!((0x0000_0000, 0x0000, 0x0001), split(0x0000_0000_0000_0001u64));
+        assert_eq!((0x0000_0000, 0xFFFF, 0xFFFE), split(0x0000_0000_FFFF_FFFEu64));
+        assert_eq!((0x0000_0001, 0x0000, 0x0000), split(0x0000_0001_0000_0000u64));
+        assert_eq!((0x1234_5678, 0x9ABC, 0xDEF0), split(0x1234_5678_9ABC_DEF0u64));
+        assert_eq!((0xFFFF_FFFF, 0xFFFF, 0xFFFF), split(0xFFFF_FFFF_FFFF_FFFFu64));
+    }
+
+    #[test]
+    fn test_join_u64() {
+        assert_eq!(0x0000_0000_0000_0000u64, join(0x0000_0000, 0x0000, 0x0000));
+        assert_eq!(0x0000_0000_FFFF_FFFEu64, join(0x0000_0000, 0xFFFF, 0xFFFE));
+        assert_eq!(0x0000_0001_0000_0000u64, join(0x0000_0001, 0x0000, 0x0000));
+        assert_eq!(0x1234_5678_9ABC_DEF0u64, join(0x1234_5678, 0x9ABC, 0xDEF0));
+        assert_eq!(0xFFFF_FFFF_FFFF_FFFFu64, join(0xFFFF_FFFF, 0xFFFF, 0xFFFF));
+    }
+}
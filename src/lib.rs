@@ -17,6 +17,7 @@ extern crate byteorder;
 
 use std::error::Error;
 use std::fmt;
+use std::io;
 
 /// A compressed bitmap using the [Roaring bitmap compression scheme](https://roaringbitmap.org/).
 pub mod bitmap;
@@ -24,7 +25,17 @@ pub mod bitmap;
 /// A compressed bitmap with u64 values.  Implemented as a `BTreeMap` of `RoaringBitmap`s.
 pub mod treemap;
 
-pub use bitmap::RoaringBitmap;
+/// A compressed bitmap with u64 values.  Implemented as a sorted `Vec` of containers keyed on
+/// the 48 most significant bits.
+pub mod bitmap64;
+
+pub use bitmap::{
+    BitmapArchive, BitmapArchiveWriter, RoaringBitmap, RoaringBitmapView, SerializedBitmap,
+    SerializedBitmapInfo, SerializedBitmapWriter, SerializedIter,
+};
+#[cfg(feature = "mmap")]
+pub use bitmap::{MmapBitmap, MmapBitmapMut};
+pub use bitmap64::Roaring64Bitmap;
 pub use treemap::RoaringTreemap;
 
 /// An error type that is returned when an iterator isn't sorted.
@@ -48,6 +59,478 @@ impl fmt::Display for NonSortedIntegers {
 
 impl Error for NonSortedIntegers {}
 
+/// An error type that is returned when a serialized bitmap can't be deserialized.
+///
+/// Every variant records the byte offset, from the start of the serialized bitmap, of the
+/// part that couldn't be read, and the key of the container being read if any.
+///
+/// The deserialization methods return it directly, except [`RoaringBitmap::deserialize_from`],
+/// [`RoaringBitmap::deserialize_unchecked_from`] and their [`RoaringTreemap`] counterparts,
+/// which return an [`io::Error`] wrapping it.
+///
+/// # Examples
+///
+/// ```rust
+/// use roaring::{DeserializeError, RoaringBitmap};
+///
+/// let bytes = [1, 2, 3, 4];
+/// let error = RoaringBitmap::deserialize_from(&bytes[..]).unwrap_err();
+/// match error.get_ref().and_then(|e| e.downcast_ref()) {
+///     Some(DeserializeError::UnknownCookie { cookie: 0x0403_0201, offset: 0 }) => (),
+///     _ => panic!("the cookie is not rejected"),
+/// }
+/// ```
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum DeserializeError {
+    /// The cookie that starts the serialized bitmap isn't one of a known format, or another
+    /// magic value isn't a known one: the tag byte of the CRoaring native format, the boolean
+    /// of the Java `Roaring64NavigableMap` format or the magic bytes and the version of a
+    /// [`BitmapArchive`].
+    UnknownCookie {
+        /// The cookie, or the tag, that was read.
+        cookie: u32,
+        /// The offset of the cookie.
+        offset: u64,
+    },
+    /// The header declares more containers than a bitmap can hold, or a negative number of
+    /// bitmaps.
+    TooManyContainers {
+        /// The number of containers declared.
+        count: u64,
+        /// The offset of the number of containers.
+        offset: u64,
+    },
+    /// A container key isn't strictly greater than the previous one.
+    UnsortedKeys {
+        /// The key of the container.
+        key: u16,
+        /// The offset of the description of the container.
+        offset: u64,
+    },
+    /// A key wider than 16 bits, such as the key of a bitmap of a treemap, isn't strictly
+    /// greater than the previous one.
+    UnsortedWideKeys {
+        /// The key that was read.
+        key: u64,
+        /// The offset of the key.
+        offset: u64,
+    },
+    /// A bitmap of a treemap holds no value.
+    EmptyBitmap {
+        /// The key of the bitmap, the 32 most significant bits of its values.
+        key: u32,
+        /// The offset of the bitmap.
+        offset: u64,
+    },
+    /// A container doesn't hold the number of values declared by its description.
+    BadCardinality {
+        /// The key of the container.
+        key: u16,
+        /// The offset of the data of the container.
+        offset: u64,
+        /// The number of values declared by the description of the container.
+        expected: u64,
+        /// The number of values of the container.
+        actual: u64,
+    },
+    /// A container holds invalid values, such as unsorted values or overlapping runs.
+    InvalidContainer {
+        /// The key of the container.
+        key: u16,
+        /// The offset of the data of the container.
+        offset: u64,
+        /// What is wrong with the values.
+        source: Box<dyn Error + Send + Sync>,
+    },
+    /// The input ended before the end of the serialized bitmap.
+    Truncated {
+        /// The key of the container being read, if the header has been read.
+        key: Option<u16>,
+        /// The offset of the part that couldn't be read entirely.
+        offset: u64,
+    },
+    /// Reading the input would exceed one of the limits of the [`DeserializeOptions`].
+    LimitExceeded {
+        /// The limit that would be exceeded.
+        limit: Limit,
+        /// The offset of the part that would exceed the limit.
+        offset: u64,
+    },
+    /// The checksum of a bitmap written with a checksum doesn't match its bytes.
+    ChecksumMismatch {
+        /// The checksum that was read.
+        expected: u32,
+        /// The checksum of the bytes that were read.
+        actual: u32,
+        /// The offset of the checksum.
+        offset: u64,
+    },
+    /// The serialized bitmap doesn't span the length declared for it, such as the length that
+    /// precedes a bitmap written with a checksum or the data size of a frozen bitmap.
+    LengthMismatch {
+        /// The length that was declared.
+        expected: u64,
+        /// The length of the serialized bitmap.
+        actual: u64,
+        /// The offset of the length.
+        offset: u64,
+    },
+    /// The index of a [`BitmapArchive`] is invalid, such as a bitmap out of the bounds of the
+    /// archive or keys out of order.
+    InvalidIndex {
+        /// The offset of the invalid part of the index.
+        offset: u64,
+        /// What is wrong with the index.
+        source: Box<dyn Error + Send + Sync>,
+    },
+    /// An op of the ops log that follows a bitmap in the Pilosa format isn't a supported one.
+    UnknownOp {
+        /// The type of the op that was read.
+        op: u8,
+        /// The offset of the op.
+        offset: u64,
+    },
+    /// Reading the input failed.
+    Io {
+        /// The key of the container being read, if the header has been read.
+        key: Option<u16>,
+        /// The offset of the part that couldn't be read.
+        offset: u64,
+        /// The error returned by the reader.
+        source: io::Error,
+    },
+}
+
+impl DeserializeError {
+    /// Returns the key of the container that couldn't be read, if any.
+    pub fn key(&self) -> Option<u16> {
+        match *self {
+            DeserializeError::UnknownCookie { .. } => None,
+            DeserializeError::TooManyContainers { .. } => None,
+            DeserializeError::UnsortedKeys { key, .. } => Some(key),
+            DeserializeError::UnsortedWideKeys { .. } => None,
+            DeserializeError::EmptyBitmap { .. } => None,
+            DeserializeError::BadCardinality { key, .. } => Some(key),
+            DeserializeError::InvalidContainer { key, .. } => Some(key),
+            DeserializeError::Truncated { key, .. } => key,
+            DeserializeError::LimitExceeded { .. } => None,
+            DeserializeError::ChecksumMismatch { .. } => None,
+            DeserializeError::LengthMismatch { .. } => None,
+            DeserializeError::InvalidIndex { .. } => None,
+            DeserializeError::UnknownOp { .. } => None,
+            DeserializeError::Io { key, .. } => key,
+        }
+    }
+
+    /// Returns the offset, from the start of the serialized bitmap, of the part that couldn't
+    /// be read.
+    pub fn offset(&self) -> u64 {
+        match *self {
+            DeserializeError::UnknownCookie { offset, .. } => offset,
+            DeserializeError::TooManyContainers { offset, .. } => offset,
+            DeserializeError::UnsortedKeys { offset, .. } => offset,
+            DeserializeError::UnsortedWideKeys { offset, .. } => offset,
+            DeserializeError::EmptyBitmap { offset, .. } => offset,
+            DeserializeError::BadCardinality { offset, .. } => offset,
+            DeserializeError::InvalidContainer { offset, .. } => offset,
+            DeserializeError::Truncated { offset, .. } => offset,
+            DeserializeError::LimitExceeded { offset, .. } => offset,
+            DeserializeError::ChecksumMismatch { offset, .. } => offset,
+            DeserializeError::LengthMismatch { offset, .. } => offset,
+            DeserializeError::InvalidIndex { offset, .. } => offset,
+            DeserializeError::UnknownOp { offset, .. } => offset,
+            DeserializeError::Io { offset, .. } => offset,
+        }
+    }
+
+    /// Classifies the error of a read, a premature end of the input is a truncated bitmap.
+    pub(crate) fn from_io(error: io::Error, key: Option<u16>, offset: u64) -> DeserializeError {
+        if error.kind() == io::ErrorKind::UnexpectedEof {
+            DeserializeError::Truncated { key, offset }
+        } else {
+            DeserializeError::Io { key, offset, source: error }
+        }
+    }
+
+    /// Moves the offset of the error by `shift`, for a bitmap read after `shift` bytes.
+    pub(crate) fn shifted(mut self, shift: u64) -> DeserializeError {
+        match &mut self {
+            DeserializeError::UnknownCookie { offset, .. }
+            | DeserializeError::TooManyContainers { offset, .. }
+            | DeserializeError::UnsortedKeys { offset, .. }
+            | DeserializeError::UnsortedWideKeys { offset, .. }
+            | DeserializeError::EmptyBitmap { offset, .. }
+            | DeserializeError::BadCardinality { offset, .. }
+            | DeserializeError::InvalidContainer { offset, .. }
+            | DeserializeError::Truncated { offset, .. }
+            | DeserializeError::LimitExceeded { offset, .. }
+            | DeserializeError::ChecksumMismatch { offset, .. }
+            | DeserializeError::LengthMismatch { offset, .. }
+            | DeserializeError::InvalidIndex { offset, .. }
+            | DeserializeError::UnknownOp { offset, .. }
+            | DeserializeError::Io { offset, .. } => *offset += shift,
+        }
+        self
+    }
+}
+
+impl fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeserializeError::UnknownCookie { cookie, .. } => {
+                write!(f, "unknown cookie value {}", cookie)?
+            }
+            DeserializeError::TooManyContainers { count, .. } => {
+                write!(f, "{} containers is greater than supported", count)?
+            }
+            DeserializeError::UnsortedKeys { .. } => {
+                f.write_str("container key is not greater than the previous one")?
+            }
+            DeserializeError::UnsortedWideKeys { key, .. } => {
+                write!(f, "key {} is not greater than the previous one", key)?
+            }
+            DeserializeError::EmptyBitmap { key, .. } => write!(f, "bitmap {} is empty", key)?,
+            DeserializeError::BadCardinality { expected, actual, .. } => {
+                write!(f, "expected cardinality was {} but was {}", expected, actual)?
+            }
+            DeserializeError::InvalidContainer { source, .. } => {
+                write!(f, "invalid container: {}", source)?
+            }
+            DeserializeError::Truncated { .. } => f.write_str("unexpected end of input")?,
+            DeserializeError::LimitExceeded { limit, .. } => {
+                write!(f, "the {} limit is exceeded", limit)?
+            }
+            DeserializeError::ChecksumMismatch { expected, actual, .. } => {
+                write!(f, "expected checksum was {:#010x} but was {:#010x}", expected, actual)?
+            }
+            DeserializeError::LengthMismatch { expected, actual, .. } => {
+                write!(f, "expected length was {} but was {}", expected, actual)?
+            }
+            DeserializeError::InvalidIndex { source, .. } => {
+                write!(f, "invalid archive index: {}", source)?
+            }
+            DeserializeError::UnknownOp { op, .. } => write!(f, "unsupported op type {}", op)?,
+            DeserializeError::Io { source, .. } => write!(f, "failed to read: {}", source)?,
+        }
+        match self.key() {
+            Some(key) => write!(f, " (container {} at byte {})", key, self.offset()),
+            None => write!(f, " (at byte {})", self.offset()),
+        }
+    }
+}
+
+impl Error for DeserializeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DeserializeError::InvalidContainer { source, .. } => Some(&**source),
+            DeserializeError::InvalidIndex { source, .. } => Some(&**source),
+            DeserializeError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<DeserializeError> for io::Error {
+    fn from(error: DeserializeError) -> io::Error {
+        let kind = match &error {
+            DeserializeError::UnknownCookie { .. } => io::ErrorKind::Other,
+            DeserializeError::TooManyContainers { .. } => io::ErrorKind::Other,
+            DeserializeError::LimitExceeded { .. } => io::ErrorKind::Other,
+            DeserializeError::Truncated { .. } => io::ErrorKind::UnexpectedEof,
+            DeserializeError::Io { source, .. } => source.kind(),
+            _ => io::ErrorKind::InvalidData,
+        };
+        io::Error::new(kind, error)
+    }
+}
+
+/// An error type that is returned when a bitmap doesn't uphold the invariants of its internal
+/// representation, see [`RoaringBitmap::validate`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum InvariantError {
+    /// A container key isn't strictly greater than the previous one.
+    UnsortedKeys {
+        /// The position of the container.
+        index: usize,
+        /// The key of the container.
+        key: u16,
+    },
+    /// A container holds no value.
+    EmptyContainer {
+        /// The position of the container.
+        index: usize,
+        /// The key of the container.
+        key: u16,
+    },
+    /// A container holds invalid values, such as unsorted values, overlapping runs or a wrong
+    /// recorded cardinality.
+    InvalidContainer {
+        /// The position of the container.
+        index: usize,
+        /// The key of the container.
+        key: u16,
+        /// What is wrong with the values.
+        source: Box<dyn Error + Send + Sync>,
+    },
+    /// An array container holds more than 4096 values or a bitmap container holds at most 4096.
+    WrongContainerType {
+        /// The position of the container.
+        index: usize,
+        /// The key of the container.
+        key: u16,
+        /// The number of values of the container.
+        len: u64,
+    },
+}
+
+impl InvariantError {
+    /// Returns the key of the container that breaks an invariant.
+    pub fn key(&self) -> u16 {
+        match *self {
+            InvariantError::UnsortedKeys { key, .. } => key,
+            InvariantError::EmptyContainer { key, .. } => key,
+            InvariantError::InvalidContainer { key, .. } => key,
+            InvariantError::WrongContainerType { key, .. } => key,
+        }
+    }
+}
+
+impl fmt::Display for InvariantError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let index = match self {
+            InvariantError::UnsortedKeys { index, .. } => {
+                f.write_str("container key is not greater than the previous one")?;
+                index
+            }
+            InvariantError::EmptyContainer { index, .. } => {
+                f.write_str("empty container")?;
+                index
+            }
+            InvariantError::InvalidContainer { index, source, .. } => {
+                write!(f, "invalid container: {}", source)?;
+                index
+            }
+            InvariantError::WrongContainerType { index, len, .. } => {
+                write!(f, "wrong container type for {} values", len)?;
+                index
+            }
+        };
+        write!(f, " (container {} at index {})", self.key(), index)
+    }
+}
+
+impl Error for InvariantError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            InvariantError::InvalidContainer { source, .. } => Some(&**source),
+            _ => None,
+        }
+    }
+}
+
+/// A limit of the [`DeserializeOptions`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Limit {
+    /// The number of containers, see [`DeserializeOptions::max_containers`].
+    Containers,
+    /// The number of bytes read, see [`DeserializeOptions::max_bytes`].
+    Bytes,
+    /// The number of values, see [`DeserializeOptions::max_cardinality`].
+    Cardinality,
+    /// The number of bitmaps of a treemap, see [`DeserializeOptions::max_treemap_entries`].
+    TreemapEntries,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Limit::Containers => "container count",
+            Limit::Bytes => "byte count",
+            Limit::Cardinality => "cardinality",
+            Limit::TreemapEntries => "treemap entry count",
+        })
+    }
+}
+
+/// Limits on the resources used to deserialize a bitmap from an untrusted source.
+///
+/// The limits are checked against the sizes declared by the input before anything is allocated
+/// or read, so that a crafted header can't make the deserialization allocate more than allowed.
+/// When deserializing a [`RoaringTreemap`] the limits apply to all of its bitmaps together.
+/// No limit is set by default.
+///
+/// # Examples
+///
+/// ```rust
+/// use roaring::{DeserializeError, DeserializeOptions, Limit, RoaringBitmap};
+///
+/// let rb: RoaringBitmap = (1..4).chain(100_000..200_000).collect();
+/// let mut bytes = vec![];
+/// rb.serialize_into(&mut bytes).unwrap();
+///
+/// let options = DeserializeOptions::new().max_containers(4).max_bytes(64 * 1024);
+/// assert_eq!(RoaringBitmap::deserialize_from_with_options(&bytes[..], &options).unwrap(), rb);
+///
+/// let options = options.max_cardinality(1000);
+/// match RoaringBitmap::deserialize_from_with_options(&bytes[..], &options) {
+///     Err(DeserializeError::LimitExceeded { limit: Limit::Cardinality, .. }) => (),
+///     _ => panic!("the limit is not exceeded"),
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeserializeOptions {
+    max_containers: u64,
+    max_bytes: u64,
+    max_cardinality: u64,
+    max_treemap_entries: u64,
+}
+
+impl DeserializeOptions {
+    /// Creates options without any limit.
+    pub fn new() -> DeserializeOptions {
+        DeserializeOptions {
+            max_containers: u64::MAX,
+            max_bytes: u64::MAX,
+            max_cardinality: u64::MAX,
+            max_treemap_entries: u64::MAX,
+        }
+    }
+
+    /// Limits the number of containers, a container holds the values sharing their 16 most
+    /// significant bits and takes up to 8 KiB.
+    pub fn max_containers(mut self, max: u64) -> DeserializeOptions {
+        self.max_containers = max;
+        self
+    }
+
+    /// Limits the number of bytes read from the input.
+    pub fn max_bytes(mut self, max: u64) -> DeserializeOptions {
+        self.max_bytes = max;
+        self
+    }
+
+    /// Limits the number of values of the deserialized bitmap.
+    pub fn max_cardinality(mut self, max: u64) -> DeserializeOptions {
+        self.max_cardinality = max;
+        self
+    }
+
+    /// Limits the number of bitmaps of a deserialized [`RoaringTreemap`].
+    pub fn max_treemap_entries(mut self, max: u64) -> DeserializeOptions {
+        self.max_treemap_entries = max;
+        self
+    }
+}
+
+impl Default for DeserializeOptions {
+    fn default() -> DeserializeOptions {
+        DeserializeOptions::new()
+    }
+}
+
 /// A [`Iterator::collect`] blanket implementation that provides extra methods for [`RoaringBitmap`]
 /// and [`RoaringTreemap`].
 ///
@@ -0,0 +1,134 @@
+use std::io;
+
+use futures_util::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+
+use super::RoaringTreemap;
+use crate::bitmap::async_io::{deserialize_checked_async, last_bytes, read_bytes};
+use crate::bitmap::serialization::Budget;
+use crate::{DeserializeError, DeserializeOptions, Limit};
+
+impl RoaringTreemap {
+    /// Serialize this treemap like [RoaringTreemap::serialize_into], writing it to an
+    /// asynchronous writer one bitmap at a time.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringTreemap;
+    ///
+    /// futures_executor::block_on(async {
+    ///     let rb1: RoaringTreemap = (1..4).chain(u64::MAX - 4..u64::MAX).collect();
+    ///     let mut bytes = vec![];
+    ///     rb1.serialize_into_async(&mut bytes).await.unwrap();
+    ///     let rb2 = RoaringTreemap::deserialize_from_async(&bytes[..]).await.unwrap();
+    ///
+    ///     assert_eq!(rb1, rb2);
+    /// });
+    /// ```
+    pub async fn serialize_into_async<W: AsyncWrite + Unpin>(
+        &self,
+        mut writer: W,
+    ) -> io::Result<()> {
+        writer.write_all(&(self.map.len() as u64).to_le_bytes()).await?;
+
+        for (key, bitmap) in &self.map {
+            writer.write_all(&key.to_le_bytes()).await?;
+            bitmap.serialize_into_async(&mut writer).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Deserialize a treemap with the checks of [RoaringTreemap::deserialize_from], reading it
+    /// from an asynchronous reader.
+    ///
+    /// Only the bytes of the treemap are read, the reader is left at the end of it. The bitmaps
+    /// are read one container at a time, like [RoaringBitmap::deserialize_from_async].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringTreemap;
+    ///
+    /// futures_executor::block_on(async {
+    ///     let rb1: RoaringTreemap = (1..4).chain(u64::MAX - 4..u64::MAX).collect();
+    ///     let mut bytes = vec![];
+    ///     rb1.serialize_into(&mut bytes).unwrap();
+    ///     let rb2 = RoaringTreemap::deserialize_from_async(&bytes[..]).await.unwrap();
+    ///
+    ///     assert_eq!(rb1, rb2);
+    /// });
+    /// ```
+    pub async fn deserialize_from_async<R: AsyncRead + Unpin>(
+        reader: R,
+    ) -> Result<Self, DeserializeError> {
+        RoaringTreemap::deserialize_from_async_with_options(reader, &DeserializeOptions::new())
+            .await
+    }
+
+    /// Deserialize a treemap like [RoaringTreemap::deserialize_from_async], without exceeding
+    /// the limits of `options`.
+    ///
+    /// The limits apply to all the bitmaps of the treemap together, like with
+    /// [RoaringTreemap::deserialize_from_with_options], and are checked before reading each
+    /// part of the input into memory.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{DeserializeError, DeserializeOptions, Limit, RoaringTreemap};
+    ///
+    /// futures_executor::block_on(async {
+    ///     let rb1: RoaringTreemap = (1..4).chain(1 << 32..(1 << 32) + 4).collect();
+    ///     let mut bytes = vec![];
+    ///     rb1.serialize_into(&mut bytes).unwrap();
+    ///
+    ///     let options = DeserializeOptions::new().max_treemap_entries(2);
+    ///     let rb2 = RoaringTreemap::deserialize_from_async_with_options(&bytes[..], &options);
+    ///     assert_eq!(rb2.await.unwrap(), rb1);
+    ///
+    ///     let options = DeserializeOptions::new().max_treemap_entries(1);
+    ///     match RoaringTreemap::deserialize_from_async_with_options(&bytes[..], &options).await {
+    ///         Err(DeserializeError::LimitExceeded { limit: Limit::TreemapEntries, .. }) => (),
+    ///         _ => panic!("the limit is not exceeded"),
+    ///     }
+    /// });
+    /// ```
+    pub async fn deserialize_from_async_with_options<R: AsyncRead + Unpin>(
+        mut reader: R,
+        options: &DeserializeOptions,
+    ) -> Result<Self, DeserializeError> {
+        let budget = &mut Budget::new(options);
+        let mut bytes = Vec::new();
+        budget.spend(Limit::Bytes, 8, 0)?;
+        read_bytes(&mut reader, &mut bytes, 8, None, 0).await?;
+        let size = u64::from_le_bytes(last_bytes(&bytes));
+        budget.spend(Limit::TreemapEntries, size, 0)?;
+        let mut offset = 8;
+
+        let mut s = Self::new();
+
+        for _ in 0..size {
+            bytes.clear();
+            budget.spend(Limit::Bytes, 4, offset)?;
+            read_bytes(&mut reader, &mut bytes, 4, None, offset).await?;
+            let key = u32::from_le_bytes(last_bytes(&bytes));
+            if s.map.keys().next_back().map_or(false, |&last| last >= key) {
+                return Err(DeserializeError::UnsortedWideKeys { key: u64::from(key), offset });
+            }
+
+            let start = offset + 4;
+            let (bitmap, len) = deserialize_checked_async(&mut reader, budget)
+                .await
+                .map_err(|e| e.shifted(start))?;
+            if bitmap.is_empty() {
+                return Err(DeserializeError::EmptyBitmap { key, offset: start });
+            }
+            offset = start + len;
+
+            s.map.insert(key, bitmap);
+        }
+
+        Ok(s)
+    }
+}
@@ -413,6 +413,54 @@ impl RoaringTreemap {
 
         None
     }
+
+    /// Converts every container of every bitmap to its most compact representation,
+    /// see [`RoaringBitmap::run_optimize`].
+    ///
+    /// Returns `true` if the treemap contains at least one run container afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringTreemap;
+    ///
+    /// let mut rb: RoaringTreemap = (0..10_000).chain(1 << 40..(1 << 40) + 10).collect();
+    /// let size = rb.serialized_size();
+    ///
+    /// assert!(rb.run_optimize());
+    /// assert!(rb.serialized_size() < size);
+    /// ```
+    pub fn run_optimize(&mut self) -> bool {
+        let mut has_runs = false;
+        for bitmap in self.map.values_mut() {
+            has_runs |= bitmap.run_optimize();
+        }
+        has_runs
+    }
+
+    /// Converts every run container of every bitmap back to an array or a bitmap container,
+    /// see [`RoaringBitmap::remove_run_compression`].
+    ///
+    /// Returns `true` if at least one container was converted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringTreemap;
+    ///
+    /// let mut rb: RoaringTreemap = (0..10_000).collect();
+    ///
+    /// assert!(rb.run_optimize());
+    /// assert!(rb.remove_run_compression());
+    /// assert!(!rb.remove_run_compression());
+    /// ```
+    pub fn remove_run_compression(&mut self) -> bool {
+        let mut removed = false;
+        for bitmap in self.map.values_mut() {
+            removed |= bitmap.remove_run_compression();
+        }
+        removed
+    }
 }
 
 impl Default for RoaringTreemap {
@@ -8,10 +8,13 @@ mod util;
 // Order of these modules matters as it determines the `impl` blocks order in
 // the docs
 mod arbitrary;
+#[cfg(feature = "futures")]
+mod async_io;
 mod cmp;
 mod inherent;
 mod iter;
 mod ops;
+mod pilosa;
 #[cfg(feature = "serde")]
 mod serde;
 mod serialization;
@@ -0,0 +1,549 @@
+use std::convert::TryFrom;
+use std::io::{self, Read};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use super::RoaringTreemap;
+use crate::bitmap::container::Container;
+use crate::bitmap::serialization::{
+    serialize_store_into, serialized_store_size, CountingReader, StoreError,
+};
+use crate::bitmap::store::{ArrayStore, BitmapStore, Interval, RunStore, Store};
+use crate::DeserializeError;
+
+const PILOSA_COOKIE: u32 = 12348;
+const PILOSA_STORAGE_VERSION: u32 = 0;
+/// The size of the cookie and the number of containers.
+const HEADER_BASE_SIZE: usize = 8;
+/// The size of the key, type, cardinality and offset of a container.
+const CONTAINER_HEADER_SIZE: usize = 16;
+
+const CONTAINER_ARRAY: u16 = 1;
+const CONTAINER_BITMAP: u16 = 2;
+const CONTAINER_RUN: u16 = 3;
+
+const OP_ADD: u8 = 0;
+const OP_REMOVE: u8 = 1;
+const OP_ADD_BATCH: u8 = 2;
+const OP_REMOVE_BATCH: u8 = 3;
+
+impl RoaringTreemap {
+    /// Return the size in bytes of the output of [`RoaringTreemap::serialize_pilosa_into`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringTreemap;
+    ///
+    /// let rb: RoaringTreemap = (1..4).collect();
+    /// let mut bytes = Vec::with_capacity(rb.pilosa_serialized_size());
+    /// rb.serialize_pilosa_into(&mut bytes).unwrap();
+    ///
+    /// assert_eq!(bytes.len(), rb.pilosa_serialized_size());
+    /// ```
+    pub fn pilosa_serialized_size(&self) -> usize {
+        self.containers().fold(HEADER_BASE_SIZE, |acc, (_, container)| {
+            acc + CONTAINER_HEADER_SIZE + serialized_store_size(&container.store)
+        })
+    }
+
+    /// Serialize this bitmap into the roaring format of Pilosa and FeatureBase, the one of
+    /// their fragment files, without an ops log.
+    ///
+    /// The output starts with the cookie 12348, holding the storage version 0 and no flags, and
+    /// the number of containers as a little-endian `u32`. The description of each container
+    /// follows, as its 48 bits key in a `u64`, its type and its cardinality minus one, then the
+    /// offset of each container and their data. The runs of the run containers are written as
+    /// their first and last values.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringTreemap;
+    ///
+    /// let rb1: RoaringTreemap = (1..4).chain(u64::MAX - 2..=u64::MAX).collect();
+    /// let mut bytes = vec![];
+    /// rb1.serialize_pilosa_into(&mut bytes).unwrap();
+    /// let rb2 = RoaringTreemap::deserialize_pilosa_from(&bytes[..]).unwrap();
+    ///
+    /// assert_eq!(rb1, rb2);
+    /// ```
+    pub fn serialize_pilosa_into<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        let size = self.containers().count();
+        let size = u32::try_from(size).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidInput, "too many containers for the format")
+        })?;
+        writer.write_u32::<LittleEndian>(PILOSA_COOKIE | (PILOSA_STORAGE_VERSION << 16))?;
+        writer.write_u32::<LittleEndian>(size)?;
+
+        for (key, container) in self.containers() {
+            let kind = match &container.store {
+                Store::Array(..) => CONTAINER_ARRAY,
+                Store::Bitmap(..) => CONTAINER_BITMAP,
+                Store::Run(..) => CONTAINER_RUN,
+            };
+            writer.write_u64::<LittleEndian>(key)?;
+            writer.write_u16::<LittleEndian>(kind)?;
+            writer.write_u16::<LittleEndian>((container.len() - 1) as u16)?;
+        }
+
+        let mut offset = (HEADER_BASE_SIZE + CONTAINER_HEADER_SIZE * size as usize) as u64;
+        for (_, container) in self.containers() {
+            let offset32 = u32::try_from(offset).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidInput, "container offset exceeds 4 GiB")
+            })?;
+            writer.write_u32::<LittleEndian>(offset32)?;
+            offset += serialized_store_size(&container.store) as u64;
+        }
+
+        for (_, container) in self.containers() {
+            match &container.store {
+                Store::Run(runs) => {
+                    writer.write_u16::<LittleEndian>(runs.run_amount() as u16)?;
+                    for run in runs.as_slice() {
+                        writer.write_u16::<LittleEndian>(run.start)?;
+                        writer.write_u16::<LittleEndian>(run.end)?;
+                    }
+                }
+                store => serialize_store_into(store, &mut writer)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Deserialize a bitmap from the roaring format of Pilosa and FeatureBase, like a fragment
+    /// file, and replay the ops log that follows the containers.
+    ///
+    /// The reader is read until its end. The values added and removed by the ops log are
+    /// applied in order, the ops adding or removing a single value or a batch of values are
+    /// supported and their checksums verified. The containers must be laid out in order, one
+    /// after the other, as Pilosa writes them.
+    ///
+    /// This method checks that all of the internal values are valid. The keys reported by the
+    /// errors are the 16 least significant bits of the Pilosa keys, and an op whose checksum
+    /// doesn't match is reported as a [`DeserializeError::ChecksumMismatch`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringTreemap;
+    ///
+    /// let rb1: RoaringTreemap = (1..4).collect();
+    /// let mut bytes = vec![];
+    /// rb1.serialize_pilosa_into(&mut bytes).unwrap();
+    ///
+    /// // An op adding the value 10, with the FNV-1a checksum of its type and value
+    /// bytes.push(0);
+    /// bytes.extend_from_slice(&10u64.to_le_bytes());
+    /// bytes.extend_from_slice(&0x2317_cab5u32.to_le_bytes());
+    ///
+    /// let rb2 = RoaringTreemap::deserialize_pilosa_from(&bytes[..]).unwrap();
+    /// assert_eq!(rb2, (1..4).chain(Some(10)).collect());
+    /// ```
+    pub fn deserialize_pilosa_from<R: io::Read>(reader: R) -> Result<Self, DeserializeError> {
+        let mut reader = CountingReader { inner: reader, offset: 0 };
+        let eof = |offset| move |e| DeserializeError::from_io(e, None, offset);
+
+        // The high byte holds flags which don't change the layout
+        let cookie = reader.read_u32::<LittleEndian>().map_err(eof(0))?;
+        if cookie & 0xffff != PILOSA_COOKIE || (cookie >> 16) & 0xff != PILOSA_STORAGE_VERSION {
+            return Err(DeserializeError::UnknownCookie { cookie, offset: 0 });
+        }
+        let size = reader.read_u32::<LittleEndian>().map_err(eof(4))? as usize;
+
+        // The size isn't trusted yet, the descriptions only grow with the bytes actually read
+        let mut descriptions: Vec<Description> = Vec::new();
+        for _ in 0..size {
+            let offset = reader.offset;
+            let key = reader.read_u64::<LittleEndian>().map_err(eof(offset))?;
+            let kind = reader.read_u16::<LittleEndian>().map_err(eof(offset))?;
+            let len = u64::from(reader.read_u16::<LittleEndian>().map_err(eof(offset))?) + 1;
+            if key >> 48 != 0 {
+                let source = "container key too large".into();
+                return Err(DeserializeError::InvalidContainer { key: key as u16, offset, source });
+            }
+            if descriptions.last().map_or(false, |previous| previous.key >= key) {
+                return Err(DeserializeError::UnsortedWideKeys { key, offset });
+            }
+            descriptions.push(Description { key, kind, len, offset: 0 });
+        }
+        for description in &mut descriptions {
+            let offset = reader.offset;
+            description.offset = u64::from(reader.read_u32::<LittleEndian>().map_err(eof(offset))?);
+        }
+
+        let mut treemap = RoaringTreemap::new();
+        for Description { key, kind, len, offset } in descriptions {
+            if offset != reader.offset {
+                return Err(DeserializeError::InvalidContainer {
+                    key: key as u16,
+                    offset: reader.offset,
+                    source: "container offset doesn't follow the previous container".into(),
+                });
+            }
+            let store = read_pilosa_store(&mut reader, key as u16, kind, len)?;
+            let mut container = Container { key: key as u16, store };
+            container.ensure_correct_store();
+            let hi = (key >> 16) as u32;
+            treemap.map.entry(hi).or_default().containers.push(container);
+        }
+
+        replay_ops(&mut reader, &mut treemap)?;
+        Ok(treemap)
+    }
+
+    /// Iterates over the containers with their Pilosa key, the 48 most significant bits of
+    /// their values.
+    fn containers(&self) -> impl Iterator<Item = (u64, &Container)> {
+        self.map.iter().flat_map(|(&hi, bitmap)| {
+            bitmap.containers.iter().map(move |c| ((u64::from(hi) << 16) | u64::from(c.key), c))
+        })
+    }
+}
+
+/// What the header of the Pilosa format records about a container.
+struct Description {
+    key: u64,
+    kind: u16,
+    len: u64,
+    offset: u64,
+}
+
+/// Reads the data of a container of type `kind` holding `len` values, checking it.
+fn read_pilosa_store<R: io::Read>(
+    reader: &mut CountingReader<R>,
+    key: u16,
+    kind: u16,
+    len: u64,
+) -> Result<Store, DeserializeError> {
+    let offset = reader.offset;
+    let eof = |e| DeserializeError::from_io(e, Some(key), offset);
+    match kind {
+        CONTAINER_ARRAY => {
+            let mut values = Vec::new();
+            for _ in 0..len {
+                values.push(reader.read_u16::<LittleEndian>().map_err(eof)?);
+            }
+            Ok(Store::Array(ArrayStore::try_from(values).map_err(invalid(key, offset))?))
+        }
+        CONTAINER_BITMAP => {
+            let mut bits = Box::new([0; 1024]);
+            reader.read_u64_into::<LittleEndian>(&mut bits[..]).map_err(eof)?;
+            Ok(Store::Bitmap(BitmapStore::try_from(len, bits).map_err(invalid(key, offset))?))
+        }
+        CONTAINER_RUN => {
+            let runs = reader.read_u16::<LittleEndian>().map_err(eof)?;
+            let mut intervals = Vec::with_capacity(runs as usize);
+            for _ in 0..runs {
+                let start = reader.read_u16::<LittleEndian>().map_err(eof)?;
+                let last = reader.read_u16::<LittleEndian>().map_err(eof)?;
+                if start > last {
+                    let source = "run container with invalid run".into();
+                    return Err(DeserializeError::InvalidContainer { key, offset, source });
+                }
+                intervals.push(Interval::new(start, last));
+            }
+            Ok(Store::Run(RunStore::try_from_len(len, intervals).map_err(invalid(key, offset))?))
+        }
+        _ => {
+            let source = "unknown container type".into();
+            Err(DeserializeError::InvalidContainer { key, offset, source })
+        }
+    }
+}
+
+/// Turns the error of a checked store constructor into the error of the container.
+fn invalid<E: StoreError>(key: u16, offset: u64) -> impl FnOnce(E) -> DeserializeError {
+    move |e| match e.cardinality() {
+        Some((expected, actual)) => {
+            DeserializeError::BadCardinality { key, offset, expected, actual }
+        }
+        None => DeserializeError::InvalidContainer { key, offset, source: Box::new(e) },
+    }
+}
+
+/// Applies the ops of the log that follows the containers, until the end of the reader.
+fn replay_ops<R: io::Read>(
+    reader: &mut CountingReader<R>,
+    treemap: &mut RoaringTreemap,
+) -> Result<(), DeserializeError> {
+    loop {
+        let offset = reader.offset;
+        let eof = |e| DeserializeError::from_io(e, None, offset);
+        let mut kind = [0];
+        let read = loop {
+            match reader.read(&mut kind) {
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                result => break result.map_err(eof)?,
+            }
+        };
+        if read == 0 {
+            return Ok(());
+        }
+        let value = reader.read_u64::<LittleEndian>().map_err(eof)?;
+        let checksum_offset = reader.offset;
+        let checksum = reader.read_u32::<LittleEndian>().map_err(eof)?;
+        let mismatch = |actual| DeserializeError::ChecksumMismatch {
+            expected: checksum,
+            actual,
+            offset: checksum_offset,
+        };
+
+        let mut hasher = Fnv32a::new();
+        hasher.update(&kind);
+        hasher.update(&value.to_le_bytes());
+        match kind[0] {
+            OP_ADD | OP_REMOVE => {
+                if hasher.finish() != checksum {
+                    return Err(mismatch(hasher.finish()));
+                }
+                if kind[0] == OP_ADD {
+                    treemap.insert(value);
+                } else {
+                    treemap.remove(value);
+                }
+            }
+            OP_ADD_BATCH | OP_REMOVE_BATCH => {
+                // The values are only applied once the checksum covering all of them is verified
+                let mut values = Vec::new();
+                for _ in 0..value {
+                    let value = reader.read_u64::<LittleEndian>().map_err(eof)?;
+                    hasher.update(&value.to_le_bytes());
+                    values.push(value);
+                }
+                if hasher.finish() != checksum {
+                    return Err(mismatch(hasher.finish()));
+                }
+                if kind[0] == OP_ADD_BATCH {
+                    for value in values {
+                        treemap.insert(value);
+                    }
+                } else {
+                    for value in values {
+                        treemap.remove(value);
+                    }
+                }
+            }
+            op => return Err(DeserializeError::UnknownOp { op, offset }),
+        }
+    }
+}
+
+/// The 32 bits FNV-1a hash, with which Pilosa checksums the ops.
+struct Fnv32a(u32);
+
+impl Fnv32a {
+    fn new() -> Fnv32a {
+        Fnv32a(0x811c_9dc5)
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u32::from(byte)).wrapping_mul(0x0100_0193);
+        }
+    }
+
+    fn finish(&self) -> u32 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Fnv32a;
+    use crate::{DeserializeError, RoaringTreemap};
+    use proptest::prelude::*;
+
+    /// Returns an op of the ops log, `value` is ignored by the batch ops.
+    fn op(kind: u8, value: u64, values: &[u64]) -> Vec<u8> {
+        let value = if kind < 2 { value } else { values.len() as u64 };
+        let mut bytes = vec![kind];
+        bytes.extend_from_slice(&value.to_le_bytes());
+        let mut hasher = Fnv32a::new();
+        hasher.update(&bytes);
+        let values: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        hasher.update(&values);
+        bytes.extend_from_slice(&hasher.finish().to_le_bytes());
+        bytes.extend(values);
+        bytes
+    }
+
+    proptest! {
+        #[test]
+        fn test_pilosa_serialization(
+            mut treemap in RoaringTreemap::arbitrary(),
+            optimize in any::<bool>(),
+        ) {
+            if optimize {
+                treemap.run_optimize();
+            }
+            let mut buffer = Vec::new();
+            treemap.serialize_pilosa_into(&mut buffer).unwrap();
+            prop_assert_eq!(buffer.len(), treemap.pilosa_serialized_size());
+            prop_assert_eq!(treemap, RoaringTreemap::deserialize_pilosa_from(buffer.as_slice()).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_pilosa_layout() {
+        let treemap: RoaringTreemap = [1, 2, (7 << 16) + 5].iter().copied().collect();
+        let mut bytes = Vec::new();
+        treemap.serialize_pilosa_into(&mut bytes).unwrap();
+
+        #[rustfmt::skip]
+        let expected = [
+            0x3c, 0x30, 0, 0, 2, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 1, 0,
+            7, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0,
+            40, 0, 0, 0, 44, 0, 0, 0,
+            1, 0, 2, 0,
+            5, 0,
+        ];
+        assert_eq!(bytes, expected);
+
+        // A run container of the values 10 to 20 and a bitmap container of few values, with
+        // flags in the high byte of the cookie
+        #[rustfmt::skip]
+        let bytes = [
+            0x3c, 0x30, 0, 1, 2, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 10, 0,
+            0, 0, 0, 0, 1, 0, 0, 0, 2, 0, 1, 0,
+            40, 0, 0, 0, 46, 0, 0, 0,
+            1, 0, 10, 0, 20, 0,
+        ];
+        let mut bytes = bytes.to_vec();
+        let mut bits = [0u64; 1024];
+        bits[0] = 0b11;
+        bytes.extend(bits.iter().flat_map(|word| word.to_le_bytes()));
+
+        let treemap = RoaringTreemap::deserialize_pilosa_from(&bytes[..]).unwrap();
+        let expected: RoaringTreemap = (10..=20).chain([1 << 48, (1 << 48) + 1]).collect();
+        assert_eq!(treemap, expected);
+        assert!(treemap.map.values().all(|bitmap| bitmap.validate().is_ok()));
+    }
+
+    #[test]
+    fn test_pilosa_ops_log() {
+        let treemap: RoaringTreemap = (0..10).collect();
+        let mut bytes = Vec::new();
+        treemap.serialize_pilosa_into(&mut bytes).unwrap();
+
+        bytes.extend(op(0, u64::MAX, &[]));
+        bytes.extend(op(1, 3, &[]));
+        bytes.extend(op(2, 0, &[100, 1 << 40]));
+        bytes.extend(op(3, 0, &[0, 1, 100]));
+        bytes.extend(op(0, 100, &[]));
+        let expected: RoaringTreemap =
+            [2, 4, 5, 6, 7, 8, 9, 100, 1 << 40, u64::MAX].iter().copied().collect();
+        assert_eq!(RoaringTreemap::deserialize_pilosa_from(&bytes[..]).unwrap(), expected);
+
+        let end = bytes.len() as u64;
+        let mut corrupted = bytes.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        match RoaringTreemap::deserialize_pilosa_from(&corrupted[..]) {
+            Err(DeserializeError::ChecksumMismatch { offset, .. }) => assert_eq!(offset, end - 4),
+            result => panic!("unexpected result {:?}", result),
+        }
+
+        let truncated = &bytes[..bytes.len() - 1];
+        match RoaringTreemap::deserialize_pilosa_from(truncated) {
+            Err(DeserializeError::Truncated { key: None, offset }) => assert_eq!(offset, end - 13),
+            result => panic!("unexpected result {:?}", result),
+        }
+
+        let mut unsupported = bytes.clone();
+        unsupported.extend(op(4, 0, &[]));
+        match RoaringTreemap::deserialize_pilosa_from(&unsupported[..]) {
+            Err(DeserializeError::UnknownOp { op: 4, offset }) => assert_eq!(offset, end),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_pilosa_interrupted() {
+        /// A reader interrupted before every read.
+        struct Interrupting<'a> {
+            bytes: &'a [u8],
+            interrupted: bool,
+        }
+
+        impl std::io::Read for Interrupting<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                self.interrupted = !self.interrupted;
+                if self.interrupted {
+                    return Err(std::io::ErrorKind::Interrupted.into());
+                }
+                self.bytes.read(buf)
+            }
+        }
+
+        let treemap: RoaringTreemap = (0..10).collect();
+        let mut bytes = Vec::new();
+        treemap.serialize_pilosa_into(&mut bytes).unwrap();
+        bytes.extend(op(0, 100, &[]));
+        bytes.extend(op(2, 0, &[200, 300]));
+
+        let reader = Interrupting { bytes: &bytes, interrupted: false };
+        let expected: RoaringTreemap = (0..10).chain([100, 200, 300]).collect();
+        assert_eq!(RoaringTreemap::deserialize_pilosa_from(reader).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_pilosa_invalid() {
+        let treemap: RoaringTreemap = (0..10).chain(1 << 20..(1 << 20) + 10).collect();
+        let mut bytes = Vec::new();
+        treemap.serialize_pilosa_into(&mut bytes).unwrap();
+
+        let error = |bytes: &[u8]| RoaringTreemap::deserialize_pilosa_from(bytes).unwrap_err();
+
+        let mut cookie = bytes.clone();
+        cookie[0] = 0x3b;
+        match error(&cookie) {
+            DeserializeError::UnknownCookie { cookie: 0x303b, offset: 0 } => (),
+            error => panic!("unexpected error {:?}", error),
+        }
+
+        let mut version = bytes.clone();
+        version[2] = 1;
+        match error(&version) {
+            DeserializeError::UnknownCookie { cookie: 0x1_303c, offset: 0 } => (),
+            error => panic!("unexpected error {:?}", error),
+        }
+
+        // The second key equals the first one
+        let mut unsorted = bytes.clone();
+        unsorted[20] = 0;
+        match error(&unsorted) {
+            DeserializeError::UnsortedWideKeys { key: 0, offset: 20 } => (),
+            error => panic!("unexpected error {:?}", error),
+        }
+
+        let mut offset = bytes.clone();
+        offset[36] += 1;
+        match error(&offset) {
+            DeserializeError::InvalidContainer { key: 16, offset: 60, .. } => (),
+            error => panic!("unexpected error {:?}", error),
+        }
+
+        let mut kind = bytes.clone();
+        kind[16] = 4;
+        match error(&kind) {
+            DeserializeError::InvalidContainer { key: 0, offset: 40, .. } => (),
+            error => panic!("unexpected error {:?}", error),
+        }
+
+        match error(&bytes[..bytes.len() - 1]) {
+            DeserializeError::Truncated { key: Some(16), offset: 60 } => (),
+            error => panic!("unexpected error {:?}", error),
+        }
+
+        // The array container values are not sorted
+        let mut values = bytes;
+        values.swap(48, 50);
+        match error(&values) {
+            DeserializeError::InvalidContainer { key: 0, offset: 40, .. } => (),
+            error => panic!("unexpected error {:?}", error),
+        }
+    }
+}
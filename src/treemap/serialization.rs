@@ -1,6 +1,9 @@
 use super::RoaringTreemap;
-use crate::RoaringBitmap;
-use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "checksum")]
+use crate::bitmap::checksum::{check_length, read_with_checksum, write_with_checksum};
+use crate::bitmap::serialization::{Budget, CountingReader};
+use crate::{DeserializeError, DeserializeOptions, Limit, RoaringBitmap};
+use byteorder::{BigEndian, LittleEndian, ReadBytesExt, WriteBytesExt};
 use std::{io, mem::size_of};
 
 impl RoaringTreemap {
@@ -69,8 +72,42 @@ impl RoaringTreemap {
     /// assert_eq!(rb1, rb2);
     /// ```
     pub fn deserialize_from<R: io::Read>(reader: R) -> io::Result<Self> {
-        RoaringTreemap::deserialize_from_impl(reader, |reader| {
-            RoaringBitmap::deserialize_from(reader)
+        Ok(RoaringTreemap::deserialize_from_with_options(reader, &DeserializeOptions::new())?)
+    }
+
+    /// Deserialize a bitmap into memory like [RoaringTreemap::deserialize_from], without
+    /// exceeding the limits of `options`.
+    ///
+    /// The limits apply to all the bitmaps of the treemap together. When one of them would be
+    /// exceeded, a [`DeserializeError::LimitExceeded`] is returned. The offsets of the errors
+    /// are counted from the start of the treemap.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{DeserializeError, DeserializeOptions, Limit, RoaringTreemap};
+    ///
+    /// let rb1: RoaringTreemap = (1..4).chain(1 << 32..(1 << 32) + 4).collect();
+    /// let mut bytes = vec![];
+    /// rb1.serialize_into(&mut bytes).unwrap();
+    ///
+    /// let options = DeserializeOptions::new().max_treemap_entries(2);
+    /// let rb2 = RoaringTreemap::deserialize_from_with_options(&bytes[..], &options).unwrap();
+    /// assert_eq!(rb1, rb2);
+    ///
+    /// let options = DeserializeOptions::new().max_treemap_entries(1);
+    /// match RoaringTreemap::deserialize_from_with_options(&bytes[..], &options) {
+    ///     Err(DeserializeError::LimitExceeded { limit: Limit::TreemapEntries, .. }) => (),
+    ///     _ => panic!("the limit is not exceeded"),
+    /// }
+    /// ```
+    pub fn deserialize_from_with_options<R: io::Read>(
+        reader: R,
+        options: &DeserializeOptions,
+    ) -> Result<Self, DeserializeError> {
+        let budget = &mut Budget::new(options);
+        RoaringTreemap::deserialize_from_impl(reader, true, budget, |r, b| {
+            RoaringBitmap::deserialize_with_budget(r, b)
         })
     }
 
@@ -92,23 +129,255 @@ impl RoaringTreemap {
     /// assert_eq!(rb1, rb2);
     /// ```
     pub fn deserialize_unchecked_from<R: io::Read>(reader: R) -> io::Result<Self> {
-        RoaringTreemap::deserialize_from_impl(reader, |reader| {
-            RoaringBitmap::deserialize_unchecked_from(reader)
+        let budget = &mut Budget::unlimited();
+        let treemap = RoaringTreemap::deserialize_from_impl(reader, false, budget, |r, _| {
+            RoaringBitmap::deserialize_unchecked(r)
+        })?;
+        Ok(treemap)
+    }
+
+    /// Serialize this bitmap like [RoaringTreemap::serialize_into], framed by its length and a
+    /// checksum so that [RoaringTreemap::deserialize_with_checksum_from] detects corrupted
+    /// bytes.
+    ///
+    /// The framing is the one of [RoaringBitmap::serialize_with_checksum_into].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringTreemap;
+    ///
+    /// let rb1: RoaringTreemap = (1..4).chain(1 << 32..(1 << 32) + 4).collect();
+    /// let mut bytes = vec![];
+    /// rb1.serialize_with_checksum_into(&mut bytes).unwrap();
+    /// let rb2 = RoaringTreemap::deserialize_with_checksum_from(&bytes[..]).unwrap();
+    ///
+    /// assert_eq!(rb1, rb2);
+    /// ```
+    #[cfg(feature = "checksum")]
+    pub fn serialize_with_checksum_into<W: io::Write>(&self, writer: W) -> io::Result<()> {
+        write_with_checksum(writer, self.serialized_size() as u64, |w| self.serialize_into(w))
+    }
+
+    /// Deserialize a bitmap written by [RoaringTreemap::serialize_with_checksum_into].
+    ///
+    /// The whole bitmap is read and its checksum verified before it is deserialized with the
+    /// checks of [RoaringTreemap::deserialize_from], corrupted bytes are reported as a
+    /// [`DeserializeError::ChecksumMismatch`]. The offsets of the errors that follow the
+    /// verification are from the start of the treemap, after its length.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::{DeserializeError, RoaringTreemap};
+    ///
+    /// let rb: RoaringTreemap = (1..4).collect();
+    /// let mut bytes = vec![];
+    /// rb.serialize_with_checksum_into(&mut bytes).unwrap();
+    ///
+    /// bytes[16] ^= 1;
+    /// match RoaringTreemap::deserialize_with_checksum_from(&bytes[..]) {
+    ///     Err(DeserializeError::ChecksumMismatch { .. }) => (),
+    ///     _ => panic!("the corruption is not detected"),
+    /// }
+    /// ```
+    #[cfg(feature = "checksum")]
+    pub fn deserialize_with_checksum_from<R: io::Read>(
+        reader: R,
+    ) -> Result<Self, DeserializeError> {
+        let bytes = read_with_checksum(reader)?;
+        let mut rest = &bytes[..];
+        let options = DeserializeOptions::new();
+        let treemap = RoaringTreemap::deserialize_from_with_options(&mut rest, &options)?;
+        check_length(&bytes, bytes.len() - rest.len())?;
+        Ok(treemap)
+    }
+
+    /// Return the size in bytes of the output of [`RoaringTreemap::serialize_java_into`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringTreemap;
+    ///
+    /// let rb: RoaringTreemap = (1..4).collect();
+    /// let mut bytes = Vec::with_capacity(rb.java_serialized_size());
+    /// rb.serialize_java_into(&mut bytes).unwrap();
+    ///
+    /// assert_eq!(bytes.len(), rb.java_serialized_size());
+    /// ```
+    pub fn java_serialized_size(&self) -> usize {
+        self.map.values().fold(size_of::<u8>() + size_of::<u32>(), |acc, bitmap| {
+            acc + size_of::<u32>() + bitmap.serialized_size()
         })
     }
 
-    fn deserialize_from_impl<R, F>(mut reader: R, mut deserialize_bitmap: F) -> io::Result<Self>
+    /// Serialize this bitmap into the legacy format of the Java `Roaring64NavigableMap`, which
+    /// is the one written by its `serialize` method by default.
+    ///
+    /// The output is a boolean telling whether the keys are ordered as signed integers, always
+    /// `false` here, and the number of bitmaps as a big-endian `i32`. Each bitmap follows, as
+    /// its big-endian `i32` key and its value in the standard Roaring on-disk format.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringTreemap;
+    ///
+    /// let rb1: RoaringTreemap = (1..4).collect();
+    /// let mut bytes = vec![];
+    /// rb1.serialize_java_into(&mut bytes).unwrap();
+    /// let rb2 = RoaringTreemap::deserialize_java_from(&bytes[..]).unwrap();
+    ///
+    /// assert_eq!(rb1, rb2);
+    /// ```
+    pub fn serialize_java_into<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        // The keys of the map are sorted as unsigned integers
+        writer.write_u8(0)?;
+        writer.write_u32::<BigEndian>(self.map.len() as u32)?;
+
+        for (key, bitmap) in &self.map {
+            writer.write_u32::<BigEndian>(*key)?;
+            bitmap.serialize_into(&mut writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Deserialize a bitmap into memory from the legacy format of the Java
+    /// `Roaring64NavigableMap`, whether its keys are ordered as signed integers or not.
+    ///
+    /// This method checks that all of the internal values are valid, that the keys are
+    /// strictly increasing in the order given by the header and that no bitmap is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringTreemap;
+    ///
+    /// let rb1: RoaringTreemap = (1..4).collect();
+    /// let mut bytes = vec![];
+    /// rb1.serialize_java_into(&mut bytes).unwrap();
+    /// let rb2 = RoaringTreemap::deserialize_java_from(&bytes[..]).unwrap();
+    ///
+    /// assert_eq!(rb1, rb2);
+    /// ```
+    pub fn deserialize_java_from<R: io::Read>(reader: R) -> Result<Self, DeserializeError> {
+        RoaringTreemap::deserialize_java_from_impl(reader, true, |r| {
+            RoaringBitmap::deserialize_checked(r)
+        })
+    }
+
+    /// Deserialize a bitmap into memory from the legacy format of the Java
+    /// `Roaring64NavigableMap`, whether its keys are ordered as signed integers or not.
+    ///
+    /// This method is memory safe but will not check if the data is a valid bitmap.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use roaring::RoaringTreemap;
+    ///
+    /// let rb1: RoaringTreemap = (1..4).collect();
+    /// let mut bytes = vec![];
+    /// rb1.serialize_java_into(&mut bytes).unwrap();
+    /// let rb2 = RoaringTreemap::deserialize_java_unchecked_from(&bytes[..]).unwrap();
+    ///
+    /// assert_eq!(rb1, rb2);
+    /// ```
+    pub fn deserialize_java_unchecked_from<R: io::Read>(
+        reader: R,
+    ) -> Result<Self, DeserializeError> {
+        RoaringTreemap::deserialize_java_from_impl(reader, false, |r| {
+            RoaringBitmap::deserialize_unchecked(r)
+        })
+    }
+
+    fn deserialize_java_from_impl<R, F>(
+        reader: R,
+        check_keys: bool,
+        mut deserialize_bitmap: F,
+    ) -> Result<Self, DeserializeError>
     where
         R: io::Read,
-        F: FnMut(&mut R) -> io::Result<RoaringBitmap>,
+        F: FnMut(&mut CountingReader<R>) -> Result<RoaringBitmap, DeserializeError>,
     {
-        let size = reader.read_u64::<LittleEndian>()?;
+        let mut reader = CountingReader { inner: reader, offset: 0 };
+        let eof = |offset| move |e| DeserializeError::from_io(e, None, offset);
+
+        // The keys are the high 32 bits of the values, ordered as signed integers or not
+        let signed_longs = reader.read_u8().map_err(eof(0))?;
+        if signed_longs > 1 {
+            return Err(DeserializeError::UnknownCookie { cookie: signed_longs.into(), offset: 0 });
+        }
+        let order = |key: u32| if signed_longs == 1 { key ^ (1 << 31) } else { key };
+
+        let size = reader.read_i32::<BigEndian>().map_err(eof(1))?;
+        if size < 0 {
+            return Err(DeserializeError::TooManyContainers {
+                count: size as u32 as u64,
+                offset: 1,
+            });
+        }
+
+        let mut s = Self::new();
+        let mut previous = None;
+
+        for _ in 0..size {
+            let offset = reader.offset;
+            let key = reader.read_u32::<BigEndian>().map_err(eof(offset))?;
+            if check_keys && previous.map_or(false, |previous| previous >= order(key)) {
+                return Err(DeserializeError::UnsortedWideKeys { key: u64::from(key), offset });
+            }
+            previous = Some(order(key));
+
+            let start = reader.offset;
+            let bitmap = deserialize_bitmap(&mut reader).map_err(|e| e.shifted(start))?;
+            if check_keys && bitmap.is_empty() {
+                return Err(DeserializeError::EmptyBitmap { key, offset: start });
+            }
+
+            // An empty bitmap would break the invariants of the treemap
+            if !bitmap.is_empty() {
+                s.map.insert(key, bitmap);
+            }
+        }
+
+        Ok(s)
+    }
+
+    fn deserialize_from_impl<R, F>(
+        reader: R,
+        check_keys: bool,
+        budget: &mut Budget,
+        mut deserialize_bitmap: F,
+    ) -> Result<Self, DeserializeError>
+    where
+        R: io::Read,
+        F: FnMut(&mut CountingReader<R>, &mut Budget) -> Result<RoaringBitmap, DeserializeError>,
+    {
+        let mut reader = CountingReader { inner: reader, offset: 0 };
+        let eof = |offset| move |e| DeserializeError::from_io(e, None, offset);
+
+        budget.spend(Limit::Bytes, 8, 0)?;
+        let size = reader.read_u64::<LittleEndian>().map_err(eof(0))?;
+        budget.spend(Limit::TreemapEntries, size, 0)?;
 
         let mut s = Self::new();
 
         for _ in 0..size {
-            let key = reader.read_u32::<LittleEndian>()?;
-            let bitmap = deserialize_bitmap(&mut reader)?;
+            let offset = reader.offset;
+            budget.spend(Limit::Bytes, 4, offset)?;
+            let key = reader.read_u32::<LittleEndian>().map_err(eof(offset))?;
+            if check_keys && s.map.keys().next_back().map_or(false, |&last| last >= key) {
+                return Err(DeserializeError::UnsortedWideKeys { key: u64::from(key), offset });
+            }
+
+            let start = reader.offset;
+            let bitmap = deserialize_bitmap(&mut reader, budget).map_err(|e| e.shifted(start))?;
+            if check_keys && bitmap.is_empty() {
+                return Err(DeserializeError::EmptyBitmap { key, offset: start });
+            }
 
             s.map.insert(key, bitmap);
         }
@@ -131,5 +400,26 @@ mod test {
             treemap.serialize_into(&mut buffer).unwrap();
             prop_assert_eq!(treemap, RoaringTreemap::deserialize_from(buffer.as_slice()).unwrap());
         }
+
+        #[cfg(feature = "checksum")]
+        #[test]
+        fn test_checksum_serialization(
+            treemap in RoaringTreemap::arbitrary(),
+        ) {
+            let mut buffer = Vec::new();
+            treemap.serialize_with_checksum_into(&mut buffer).unwrap();
+            prop_assert_eq!(buffer.len(), treemap.serialized_size() + 12);
+            prop_assert_eq!(treemap, RoaringTreemap::deserialize_with_checksum_from(buffer.as_slice()).unwrap());
+        }
+
+        #[test]
+        fn test_java_serialization(
+            treemap in RoaringTreemap::arbitrary(),
+        ) {
+            let mut buffer = Vec::new();
+            treemap.serialize_java_into(&mut buffer).unwrap();
+            prop_assert_eq!(buffer.len(), treemap.java_serialized_size());
+            prop_assert_eq!(treemap, RoaringTreemap::deserialize_java_from(buffer.as_slice()).unwrap());
+        }
     }
 }
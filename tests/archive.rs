@@ -0,0 +1,101 @@
+extern crate roaring;
+
+use roaring::{BitmapArchive, BitmapArchiveWriter, DeserializeError, RoaringBitmap};
+use std::io;
+
+/// A writer failing once `capacity` bytes are written.
+struct Limited {
+    bytes: Vec<u8>,
+    capacity: usize,
+}
+
+impl io::Write for Limited {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.capacity - self.bytes.len());
+        self.bytes.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn bitmaps() -> Vec<(String, RoaringBitmap)> {
+    (0..20u32)
+        .map(|i| {
+            let mut bitmap: RoaringBitmap =
+                (i * 1000..i * 100_000).step_by(i as usize + 1).collect();
+            if i % 2 == 0 {
+                bitmap.run_optimize();
+            }
+            (format!("bitmap-{}", i), bitmap)
+        })
+        .collect()
+}
+
+#[test]
+fn archive() {
+    let bitmaps = bitmaps();
+    let mut writer = BitmapArchiveWriter::new(Vec::new()).unwrap();
+    for (key, bitmap) in bitmaps.iter().rev() {
+        writer.append(key, bitmap).unwrap();
+    }
+    assert_eq!(writer.len(), bitmaps.len());
+    let bytes = writer.finish().unwrap();
+
+    let archive = BitmapArchive::from_bytes(&bytes).unwrap();
+    assert_eq!(archive.len(), bitmaps.len());
+    let mut keys: Vec<_> = bitmaps.iter().map(|(key, _)| key.as_bytes()).collect();
+    keys.sort_unstable();
+    assert!(archive.keys().eq(keys));
+
+    for (key, bitmap) in &bitmaps {
+        assert!(archive.contains_key(key));
+        assert_eq!(archive.get_bytes(key).unwrap(), &bitmap.serialize_to_vec()[..]);
+        assert_eq!(archive.load(key).unwrap().as_ref(), Some(bitmap));
+        let view = archive.view(key).unwrap().unwrap();
+        assert_eq!(&RoaringBitmap::from(&view), bitmap);
+    }
+    assert!(!archive.contains_key("missing"));
+    assert_eq!(archive.load("missing").unwrap(), None);
+    assert!(archive.view("missing").unwrap().is_none());
+}
+
+#[test]
+fn archive_errors() {
+    let mut writer = BitmapArchiveWriter::new(Vec::new()).unwrap();
+    writer.append("a", &RoaringBitmap::from([1, 2, 3])).unwrap();
+    let bytes = writer.finish().unwrap();
+
+    match BitmapArchive::from_bytes(&bytes[..bytes.len() - 1]) {
+        Err(DeserializeError::Truncated { key: None, .. }) => (),
+        result => panic!("unexpected result {:?}", result.map(|archive| archive.len())),
+    }
+    let mut version = bytes.clone();
+    version[4] = 2;
+    match BitmapArchive::from_bytes(&version) {
+        Err(DeserializeError::UnknownCookie { cookie: 2, offset: 4 }) => (),
+        result => panic!("unexpected result {:?}", result.map(|archive| archive.len())),
+    }
+
+    // The bitmap is located in the archive, after its header
+    let mut corrupted = bytes;
+    corrupted[9] = 0;
+    let archive = BitmapArchive::from_bytes(&corrupted).unwrap();
+    match archive.load("a") {
+        Err(DeserializeError::UnknownCookie { offset: 8, .. }) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn archive_failed_append() {
+    // The bitmap is cut short by the writer, it can't be part of the archive
+    let limited = Limited { bytes: Vec::new(), capacity: 32 };
+    let mut writer = BitmapArchiveWriter::new(limited).unwrap();
+    writer.append("a", &RoaringBitmap::from([1, 2, 3])).unwrap();
+    assert!(writer.append("b", &(0..1000).collect()).is_err());
+    assert!(writer.append("c", &RoaringBitmap::new()).is_err());
+    assert!(writer.finish().is_err());
+}
@@ -0,0 +1,207 @@
+#![cfg(feature = "futures")]
+extern crate roaring;
+
+use futures_executor::block_on;
+use futures_util::io::AsyncRead;
+use roaring::{DeserializeError, DeserializeOptions, Limit, RoaringBitmap, RoaringTreemap};
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+static BITMAP_WITHOUT_RUNS: &[u8] = include_bytes!("bitmapwithoutruns.bin");
+static BITMAP_WITH_RUNS: &[u8] = include_bytes!("bitmapwithruns.bin");
+
+/// A reader returning at most one byte per read, and pending every other poll.
+struct Trickle<'a> {
+    bytes: &'a [u8],
+    ready: bool,
+}
+
+impl AsyncRead for Trickle<'_> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.ready = !self.ready;
+        if !self.ready {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        match (self.bytes.split_first(), buf.first_mut()) {
+            (Some((&byte, rest)), Some(first)) => {
+                *first = byte;
+                self.bytes = rest;
+                Poll::Ready(Ok(1))
+            }
+            _ => Poll::Ready(Ok(0)),
+        }
+    }
+}
+
+fn trickle(bytes: &[u8]) -> Trickle<'_> {
+    Trickle { bytes, ready: false }
+}
+
+#[test]
+fn test_bitmap() {
+    for bytes in [BITMAP_WITHOUT_RUNS, BITMAP_WITH_RUNS] {
+        let expected = RoaringBitmap::deserialize_from(bytes).unwrap();
+        let mut reader = trickle(bytes);
+        let bitmap = block_on(RoaringBitmap::deserialize_from_async(&mut reader)).unwrap();
+        assert_eq!(bitmap, expected);
+        assert!(reader.bytes.is_empty());
+
+        let mut written = vec![];
+        block_on(bitmap.serialize_into_async(&mut written)).unwrap();
+        assert_eq!(written, bitmap.serialize_to_vec());
+    }
+}
+
+#[test]
+fn test_bitmap_back_to_back() {
+    let first: RoaringBitmap = (0..100).chain(1_000_000..1_100_000).collect();
+    let mut second: RoaringBitmap = (200..300).collect();
+    second.run_optimize();
+
+    let mut bytes = vec![];
+    block_on(async {
+        first.serialize_into_async(&mut bytes).await.unwrap();
+        second.serialize_into_async(&mut bytes).await.unwrap();
+    });
+
+    let mut reader = trickle(&bytes);
+    block_on(async {
+        assert_eq!(RoaringBitmap::deserialize_from_async(&mut reader).await.unwrap(), first);
+        assert_eq!(RoaringBitmap::deserialize_from_async(&mut reader).await.unwrap(), second);
+    });
+    assert!(reader.bytes.is_empty());
+}
+
+#[test]
+fn test_bitmap_errors() {
+    let bytes = BITMAP_WITH_RUNS;
+
+    // The containers are read one at a time, with the checks of the synchronous reader
+    for len in (0..bytes.len()).step_by(97) {
+        let error = RoaringBitmap::deserialize_from(&bytes[..len]).unwrap_err();
+        let error: DeserializeError = *error.into_inner().unwrap().downcast().unwrap();
+        match block_on(RoaringBitmap::deserialize_from_async(trickle(&bytes[..len]))) {
+            Err(DeserializeError::Truncated { key, offset }) => {
+                assert_eq!(key, error.key());
+                assert_eq!(offset, error.offset());
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    match block_on(RoaringBitmap::deserialize_from_async(&bytes[..bytes.len() - 1])) {
+        Err(DeserializeError::Truncated { key: Some(_), .. }) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+
+    match block_on(RoaringBitmap::deserialize_from_async(&[0, 0, 0, 0][..])) {
+        Err(DeserializeError::UnknownCookie { cookie: 0, offset: 0 }) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+
+    // Two containers with the keys 1 and 0
+    let unsorted = [0x3b, 0x30, 1, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 5, 0, 5, 0];
+    match block_on(RoaringBitmap::deserialize_from_async(&unsorted[..])) {
+        Err(DeserializeError::UnsortedKeys { key: 0, .. }) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn test_treemap() {
+    let treemap: RoaringTreemap =
+        (0..100).chain(1 << 40..(1 << 40) + 10_000).chain(u64::MAX - 10..u64::MAX).collect();
+
+    let mut bytes = vec![];
+    block_on(treemap.serialize_into_async(&mut bytes)).unwrap();
+    let mut expected = vec![];
+    treemap.serialize_into(&mut expected).unwrap();
+    assert_eq!(bytes, expected);
+
+    let mut reader = trickle(&bytes);
+    assert_eq!(block_on(RoaringTreemap::deserialize_from_async(&mut reader)).unwrap(), treemap);
+    assert!(reader.bytes.is_empty());
+
+    // The errors are those of the synchronous reader, located from the start of the treemap
+    let truncated = &bytes[..bytes.len() - 1];
+    let error = RoaringTreemap::deserialize_from(truncated).unwrap_err();
+    let error: DeserializeError = *error.into_inner().unwrap().downcast().unwrap();
+    match block_on(RoaringTreemap::deserialize_from_async(truncated)) {
+        Err(DeserializeError::Truncated { key: Some(key), offset }) => {
+            assert_eq!(Some(key), error.key());
+            assert_eq!(offset, error.offset());
+        }
+        result => panic!("unexpected result {:?}", result),
+    }
+
+    // The same key twice
+    let bitmap = [0x3a, 0x30, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 16, 0, 0, 0, 1, 0];
+    let unsorted = [&[2, 0, 0, 0, 0, 0, 0, 0, 7, 0, 0, 0][..], &bitmap, &[7, 0, 0, 0], &bitmap];
+    match block_on(RoaringTreemap::deserialize_from_async(&unsorted.concat()[..])) {
+        Err(DeserializeError::UnsortedWideKeys { key: 7, offset: 30 }) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
+/// Returns the limit and the offset of a [`DeserializeError::LimitExceeded`], `None` on success.
+fn limit_exceeded<T>(result: Result<T, DeserializeError>) -> Option<(Limit, u64)> {
+    match result {
+        Ok(_) => None,
+        Err(DeserializeError::LimitExceeded { limit, offset }) => Some((limit, offset)),
+        Err(error) => panic!("unexpected error {:?}", error),
+    }
+}
+
+#[test]
+fn test_bitmap_limits() {
+    // The limits are exceeded at the same places as when reading synchronously
+    for bytes in [BITMAP_WITHOUT_RUNS, BITMAP_WITH_RUNS] {
+        let bitmap = RoaringBitmap::deserialize_from(bytes).unwrap();
+        let mut options: Vec<_> = (0..=bytes.len() as u64)
+            .step_by(61)
+            .map(|max| DeserializeOptions::new().max_bytes(max))
+            .collect();
+        options.push(DeserializeOptions::new().max_bytes(bytes.len() as u64));
+        options.push(DeserializeOptions::new().max_containers(2));
+        options.push(DeserializeOptions::new().max_cardinality(bitmap.len() - 1));
+
+        for options in &options {
+            let sync = RoaringBitmap::deserialize_from_with_options(bytes, options);
+            let future =
+                RoaringBitmap::deserialize_from_async_with_options(trickle(bytes), options);
+            assert_eq!(limit_exceeded(block_on(future)), limit_exceeded(sync));
+        }
+    }
+}
+
+#[test]
+fn test_treemap_limits() {
+    let treemap = (0..10u64).map(|i| i << 32).chain(0..100_000).collect::<RoaringTreemap>();
+    let mut bytes = vec![];
+    treemap.serialize_into(&mut bytes).unwrap();
+
+    let options = [
+        DeserializeOptions::new().max_treemap_entries(10).max_bytes(bytes.len() as u64),
+        DeserializeOptions::new().max_treemap_entries(9),
+        DeserializeOptions::new().max_bytes(bytes.len() as u64 - 1),
+        DeserializeOptions::new().max_cardinality(treemap.len() - 1),
+        DeserializeOptions::new().max_containers(10),
+    ];
+    for options in &options {
+        let sync = RoaringTreemap::deserialize_from_with_options(&bytes[..], options);
+        let future = RoaringTreemap::deserialize_from_async_with_options(trickle(&bytes), options);
+        assert_eq!(limit_exceeded(block_on(future)), limit_exceeded(sync));
+    }
+
+    // A huge number of bitmaps is rejected before reading them
+    let huge = [0xff; 8];
+    let options = DeserializeOptions::new().max_treemap_entries(1 << 20);
+    let future = RoaringTreemap::deserialize_from_async_with_options(&huge[..], &options);
+    assert_eq!(limit_exceeded(block_on(future)), Some((Limit::TreemapEntries, 0)));
+}
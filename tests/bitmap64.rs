@@ -0,0 +1,109 @@
+extern crate roaring;
+
+use roaring::{DeserializeError, Roaring64Bitmap, RoaringTreemap};
+
+fn sparse() -> Vec<u64> {
+    (0..1000).map(|i| i * 0x0001_0003_0007_000B).chain([0, 1, 2, u64::MAX]).collect()
+}
+
+#[test]
+fn insert_remove() {
+    let mut rb = Roaring64Bitmap::new();
+    for value in sparse() {
+        rb.insert(value);
+    }
+    assert_eq!(rb.len(), 1003);
+    assert!(rb.contains(u64::MAX));
+    assert!(rb.contains(0x0001_0003_0007_000B * 999));
+    assert!(!rb.contains(0x0001_0003_0007_000B * 999 + 1));
+    assert_eq!(rb.min(), Some(0));
+    assert_eq!(rb.max(), Some(u64::MAX));
+
+    assert!(rb.remove(u64::MAX));
+    assert!(!rb.remove(u64::MAX));
+    assert_eq!(rb.max(), Some(0x0001_0003_0007_000B * 999));
+}
+
+#[test]
+fn iter() {
+    let values = {
+        let mut values = sparse();
+        values.extend((1 << 40)..(1 << 40) + 100_000);
+        values.sort_unstable();
+        values.dedup();
+        values
+    };
+    let rb = values.iter().collect::<Roaring64Bitmap>();
+    assert_eq!(rb.len(), values.len() as u64);
+    assert!(rb.iter().eq(values.iter().copied()));
+    assert!(rb.iter().rev().eq(values.iter().rev().copied()));
+    assert!(rb.clone().into_iter().eq(values.iter().copied()));
+    assert_eq!(rb.iter().len(), values.len());
+
+    for (i, &value) in values.iter().enumerate().step_by(97) {
+        assert_eq!(rb.select(i as u64), Some(value));
+        assert_eq!(rb.rank(value), i as u64 + 1);
+    }
+    assert_eq!(rb.select(values.len() as u64), None);
+}
+
+#[test]
+fn ops() {
+    let lhs = sparse().into_iter().chain(0..100_000).collect::<Roaring64Bitmap>();
+    let rhs = sparse().into_iter().step_by(2).chain(50_000..150_000).collect::<Roaring64Bitmap>();
+    let lhs_treemap = lhs.iter().collect::<RoaringTreemap>();
+    let rhs_treemap = rhs.iter().collect::<RoaringTreemap>();
+
+    let check = |bitmap: Roaring64Bitmap, treemap: RoaringTreemap| {
+        assert!(bitmap.iter().eq(treemap.iter()));
+    };
+    check(&lhs | &rhs, &lhs_treemap | &rhs_treemap);
+    check(&lhs & &rhs, &lhs_treemap & &rhs_treemap);
+    check(&lhs - &rhs, &lhs_treemap - &rhs_treemap);
+    check(&lhs ^ &rhs, &lhs_treemap ^ &rhs_treemap);
+    check(lhs.clone() | rhs.clone(), &lhs_treemap | &rhs_treemap);
+    check(lhs.clone() & rhs.clone(), &lhs_treemap & &rhs_treemap);
+    check(lhs.clone() - rhs.clone(), &lhs_treemap - &rhs_treemap);
+    check(lhs.clone() ^ rhs.clone(), &lhs_treemap ^ &rhs_treemap);
+    check(&lhs ^ &lhs, RoaringTreemap::new());
+}
+
+#[test]
+fn serialization() {
+    let mut rb = sparse().into_iter().collect::<Roaring64Bitmap>();
+    rb.extend((1 << 40)..(1 << 40) + 100_000);
+    rb.run_optimize();
+
+    let mut bytes = vec![];
+    rb.serialize_into(&mut bytes).unwrap();
+    assert_eq!(bytes.len(), rb.serialized_size());
+    assert_eq!(Roaring64Bitmap::deserialize_from(&bytes[..]).unwrap(), rb);
+    assert_eq!(Roaring64Bitmap::deserialize_unchecked_from(&bytes[..]).unwrap(), rb);
+
+    // The bitmaps written by the treemaps use the same format
+    let treemap = RoaringTreemap::deserialize_from(&bytes[..]).unwrap();
+    assert!(treemap.iter().eq(rb.iter()));
+    let mut treemap_bytes = vec![];
+    treemap.serialize_into(&mut treemap_bytes).unwrap();
+    assert_eq!(Roaring64Bitmap::deserialize_from(&treemap_bytes[..]).unwrap(), rb);
+
+    assert!(Roaring64Bitmap::deserialize_from(&bytes[..bytes.len() - 1]).is_err());
+}
+
+#[test]
+fn unsorted_serialization() {
+    // Two bitmaps with the same 32 most significant bits
+    let bitmap = [0x3a, 0x30, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 16, 0, 0, 0, 1, 0];
+    let bytes =
+        [&[2, 0, 0, 0, 0, 0, 0, 0, 7, 0, 0, 0][..], &bitmap, &[7, 0, 0, 0], &bitmap].concat();
+    match Roaring64Bitmap::deserialize_from(&bytes[..]) {
+        Err(DeserializeError::UnsortedWideKeys { key: 7, offset: 30 }) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+
+    // The errors of a bitmap are located from the start of the input
+    match Roaring64Bitmap::deserialize_from(&bytes[..29]) {
+        Err(DeserializeError::Truncated { key: Some(0), offset: 28 }) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
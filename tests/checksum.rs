@@ -0,0 +1,80 @@
+#![cfg(feature = "checksum")]
+extern crate roaring;
+
+use roaring::{DeserializeError, RoaringBitmap, RoaringTreemap};
+
+// Test data from https://github.com/RoaringBitmap/RoaringFormatSpec/tree/master/testdata
+static BITMAP_WITH_RUNS: &[u8] = include_bytes!("bitmapwithruns.bin");
+
+#[test]
+fn checksum() {
+    let bitmaps = [
+        RoaringBitmap::new(),
+        RoaringBitmap::deserialize_from(BITMAP_WITH_RUNS).unwrap(),
+        (0..100_000).step_by(3).collect(),
+    ];
+    for bitmap in &bitmaps {
+        let mut bytes = Vec::new();
+        bitmap.serialize_with_checksum_into(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 8 + bitmap.serialized_size() + 4);
+        assert_eq!(&bytes[8..bytes.len() - 4], &bitmap.serialize_to_vec()[..]);
+
+        // The reader is left after the checksum
+        bytes.extend_from_slice(b"trailer");
+        let mut reader = &bytes[..];
+        assert_eq!(&RoaringBitmap::deserialize_with_checksum_from(&mut reader).unwrap(), bitmap);
+        assert_eq!(reader, b"trailer");
+    }
+
+    let treemap: RoaringTreemap = (0..10).chain((1 << 40)..(1 << 40) + 10_000).collect();
+    let mut bytes = Vec::new();
+    treemap.serialize_with_checksum_into(&mut bytes).unwrap();
+    assert_eq!(RoaringTreemap::deserialize_with_checksum_from(&bytes[..]).unwrap(), treemap);
+}
+
+#[test]
+fn checksum_errors() {
+    let bitmap: RoaringBitmap = (0..100_000).step_by(3).collect();
+    let mut bytes = Vec::new();
+    bitmap.serialize_with_checksum_into(&mut bytes).unwrap();
+    let checksum_offset = bytes.len() as u64 - 4;
+
+    // Every corrupted byte is detected
+    for i in (0..bytes.len()).step_by(97) {
+        let mut corrupted = bytes.clone();
+        corrupted[i] ^= 0x10;
+        match RoaringBitmap::deserialize_with_checksum_from(&corrupted[..]) {
+            Err(DeserializeError::ChecksumMismatch { offset, .. }) if i >= 8 => {
+                assert_eq!(offset, checksum_offset)
+            }
+            // With a corrupted length, the bitmap is truncated or the wrong bytes are hashed
+            Err(DeserializeError::ChecksumMismatch { .. })
+            | Err(DeserializeError::Truncated { key: None, .. })
+                if i < 8 => {}
+            result => panic!("unexpected result at {}: {:?}", i, result),
+        }
+    }
+
+    match RoaringBitmap::deserialize_with_checksum_from(&bytes[..bytes.len() - 1]) {
+        Err(DeserializeError::Truncated { key: None, offset }) => {
+            assert_eq!(offset, checksum_offset)
+        }
+        result => panic!("unexpected result {:?}", result),
+    }
+    match RoaringBitmap::deserialize_with_checksum_from(&bytes[..100]) {
+        Err(DeserializeError::Truncated { key: None, offset }) => {
+            assert_eq!(offset, checksum_offset)
+        }
+        result => panic!("unexpected result {:?}", result),
+    }
+
+    let treemap: RoaringTreemap = (0..10).chain((1 << 40)..(1 << 40) + 10_000).collect();
+    let mut bytes = Vec::new();
+    treemap.serialize_with_checksum_into(&mut bytes).unwrap();
+    let last = bytes.len() - 5;
+    bytes[last] ^= 1;
+    match RoaringTreemap::deserialize_with_checksum_from(&bytes[..]) {
+        Err(DeserializeError::ChecksumMismatch { .. }) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
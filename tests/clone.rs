@@ -38,3 +38,20 @@ fn bitmaps() {
 
     assert_eq!(clone, original);
 }
+
+#[test]
+fn clone_from() {
+    let bitmaps = [
+        (0..2000).chain(1_000_000..1_006_000).collect::<RoaringBitmap>(),
+        (500..10_000).chain(2_000_000..2_001_000).collect::<RoaringBitmap>(),
+        (1_000_000..1_000_010).chain(3_000_000..3_000_100).collect::<RoaringBitmap>(),
+        RoaringBitmap::new(),
+        (0..65536 * 3).step_by(7).collect::<RoaringBitmap>(),
+    ];
+
+    let mut clone = RoaringBitmap::new();
+    for original in bitmaps.iter().chain(bitmaps.iter().rev()) {
+        clone.clone_from(original);
+        assert_eq!(&clone, original);
+    }
+}
@@ -1,9 +1,12 @@
 extern crate roaring;
 
-use roaring::RoaringBitmap;
+use roaring::{DeserializeError, DeserializeOptions, Limit, RoaringBitmap};
+use std::collections::BTreeSet;
+use std::io;
 
 // Test data from https://github.com/RoaringBitmap/RoaringFormatSpec/tree/master/testdata
 static BITMAP_WITHOUT_RUNS: &[u8] = include_bytes!("bitmapwithoutruns.bin");
+static BITMAP_WITH_RUNS: &[u8] = include_bytes!("bitmapwithruns.bin");
 
 fn test_data_bitmap() -> RoaringBitmap {
     (0..100)
@@ -13,6 +16,13 @@ fn test_data_bitmap() -> RoaringBitmap {
         .collect::<RoaringBitmap>()
 }
 
+fn test_data_bitmap_with_runs() -> RoaringBitmap {
+    // Run containers are only created by `run_optimize`
+    let mut bitmap = test_data_bitmap();
+    bitmap.run_optimize();
+    bitmap
+}
+
 fn serialize_and_deserialize(bitmap: &RoaringBitmap) -> RoaringBitmap {
     let mut buffer = vec![];
     bitmap.serialize_into(&mut buffer).unwrap();
@@ -20,6 +30,12 @@ fn serialize_and_deserialize(bitmap: &RoaringBitmap) -> RoaringBitmap {
     RoaringBitmap::deserialize_from(&buffer[..]).unwrap()
 }
 
+/// Returns the error wrapped by the one of `RoaringBitmap::deserialize_from`.
+fn deserialize_error(bytes: &[u8]) -> DeserializeError {
+    let error = RoaringBitmap::deserialize_from(bytes).unwrap_err();
+    *error.into_inner().unwrap().downcast().unwrap()
+}
+
 #[test]
 fn test_deserialize_from_provided_data() {
     assert_eq!(RoaringBitmap::deserialize_from(BITMAP_WITHOUT_RUNS).unwrap(), test_data_bitmap());
@@ -33,6 +49,34 @@ fn test_serialize_into_provided_data() {
     assert!(BITMAP_WITHOUT_RUNS == &buffer[..]);
 }
 
+#[test]
+fn test_deserialize_with_runs_from_provided_data() {
+    assert_eq!(RoaringBitmap::deserialize_from(BITMAP_WITH_RUNS).unwrap(), test_data_bitmap());
+}
+
+#[test]
+fn test_serialize_with_runs_into_provided_data() {
+    let bitmap = test_data_bitmap_with_runs();
+    let mut buffer = vec![];
+    bitmap.serialize_into(&mut buffer).unwrap();
+    assert_eq!(buffer.len(), bitmap.serialized_size());
+    assert!(BITMAP_WITH_RUNS == &buffer[..]);
+}
+
+#[test]
+fn test_run_optimize_into_provided_data() {
+    let mut bitmap = test_data_bitmap();
+    assert!(bitmap.run_optimize());
+    let mut buffer = vec![];
+    bitmap.serialize_into(&mut buffer).unwrap();
+    assert!(BITMAP_WITH_RUNS == &buffer[..]);
+
+    assert!(bitmap.remove_run_compression());
+    let mut buffer = vec![];
+    bitmap.serialize_into(&mut buffer).unwrap();
+    assert!(BITMAP_WITHOUT_RUNS == &buffer[..]);
+}
+
 #[test]
 fn test_empty() {
     let original = RoaringBitmap::new();
@@ -112,6 +156,176 @@ fn test_mixed() {
     assert_eq!(original, new);
 }
 
+#[test]
+fn test_runs() {
+    let mut original = RoaringBitmap::new();
+    original.insert_range(1000..3000);
+    original.insert_range(70_000..77_000);
+    assert!(original.run_optimize());
+    let new = serialize_and_deserialize(&original);
+    assert_eq!(original, new);
+}
+
+#[test]
+fn test_ranges_without_runs() {
+    // Ranges are stored without run containers until `run_optimize` is called
+    let mut bitmap = RoaringBitmap::new();
+    bitmap.insert_range(0..10);
+    bitmap.insert_range(1 << 16..2 << 16);
+    let bytes = bitmap.serialize_to_vec();
+    assert_eq!(bytes[..4], 12346u32.to_le_bytes());
+    assert_eq!(bytes.len(), 8 + 2 * 8 + 10 * 2 + 8192);
+
+    assert!(bitmap.run_optimize());
+    let bytes = bitmap.serialize_to_vec();
+    assert_eq!(bytes[..2], 12347u16.to_le_bytes());
+    assert_eq!(RoaringBitmap::deserialize_from(&bytes[..]).unwrap(), bitmap);
+}
+
+#[test]
+fn test_runs_with_offsets() {
+    // From 4 containers onwards the run format includes the offset header
+    let mut original = RoaringBitmap::new();
+    for i in 0..5 {
+        original.insert_range(i << 16..(i << 16) + 10);
+    }
+    original.insert(6 << 16);
+    assert!(original.run_optimize());
+    let new = serialize_and_deserialize(&original);
+    assert_eq!(original, new);
+}
+
+#[test]
+fn test_runs_mixed() {
+    let mut original = (0..6000).map(|i| i * 2).chain(200_000..201_000).collect::<RoaringBitmap>();
+    original.insert_range(1 << 20..(1 << 20) + 100_000);
+    assert!(original.run_optimize());
+    let new = serialize_and_deserialize(&original);
+    assert_eq!(original, new);
+}
+
+#[test]
+fn test_invalid_runs() {
+    // cookie with 1 container, run bitset, key 0 with cardinality 1, 1 run
+    let header = [0x3b, 0x30, 0, 0, 1, 0, 0, 0, 0, 1, 0];
+    let valid = [&header[..], &[0, 0, 0, 0][..]].concat();
+    assert!(RoaringBitmap::deserialize_from(&valid[..]).is_ok());
+
+    let overflowing = [&header[..], &[0xff, 0xff, 1, 0][..]].concat();
+    assert!(RoaringBitmap::deserialize_from(&overflowing[..]).is_err());
+    assert!(RoaringBitmap::deserialize_unchecked_from(&overflowing[..]).is_err());
+
+    let wrong_cardinality = [&header[..], &[0, 0, 1, 0][..]].concat();
+    assert!(RoaringBitmap::deserialize_from(&wrong_cardinality[..]).is_err());
+}
+
+#[test]
+fn test_deserialize_errors() {
+    let unknown_cookie = [1, 2, 3, 4];
+    match deserialize_error(&unknown_cookie) {
+        DeserializeError::UnknownCookie { cookie: 0x0403_0201, offset: 0 } => (),
+        error => panic!("unexpected error {:?}", error),
+    }
+
+    let too_many_containers = [0x3a, 0x30, 0, 0, 1, 0, 1, 0];
+    match deserialize_error(&too_many_containers) {
+        DeserializeError::TooManyContainers { count: 65537, offset: 4 } => (),
+        error => panic!("unexpected error {:?}", error),
+    }
+
+    // cookie, 1 container, key 0 with cardinality 2, offsets, values 2 and 1
+    let unsorted = [0x3a, 0x30, 0, 0, 1, 0, 0, 0, 0, 0, 1, 0, 16, 0, 0, 0, 2, 0, 1, 0];
+    let error = deserialize_error(&unsorted);
+    assert!(matches!(error, DeserializeError::InvalidContainer { key: 0, offset: 16, .. }));
+    let error = RoaringBitmap::deserialize_from(&unsorted[..]).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+    match deserialize_error(&unsorted[..unsorted.len() - 1]) {
+        DeserializeError::Truncated { key: Some(0), offset: 16 } => (),
+        error => panic!("unexpected error {:?}", error),
+    }
+    match deserialize_error(&unsorted[..10]) {
+        DeserializeError::Truncated { key: None, offset: 8 } => (),
+        error => panic!("unexpected error {:?}", error),
+    }
+
+    // cookie with 1 container, run bitset, key 0 with cardinality 1, 1 run of 2 values
+    let wrong_cardinality = [0x3b, 0x30, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 1, 0];
+    let error = deserialize_error(&wrong_cardinality);
+    assert!(matches!(
+        error,
+        DeserializeError::BadCardinality { key: 0, offset: 9, expected: 1, actual: 2 }
+    ));
+    assert_eq!(error.key(), Some(0));
+    assert_eq!(error.offset(), 9);
+    assert_eq!(error.to_string(), "expected cardinality was 1 but was 2 (container 0 at byte 9)");
+}
+
+#[test]
+fn test_invalid_keys() {
+    // cookie with 2 containers, run bitset, keys 1 and 0 with cardinality 1, values 5
+    let unsorted = [0x3b, 0x30, 1, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 5, 0, 5, 0];
+    match deserialize_error(&unsorted) {
+        DeserializeError::UnsortedKeys { key: 0, offset: 9 } => (),
+        error => panic!("unexpected error {:?}", error),
+    }
+    assert!(RoaringBitmap::deserialize_unchecked_from(&unsorted[..]).is_ok());
+
+    let duplicate = [0x3b, 0x30, 1, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 5, 0, 5, 0];
+    match deserialize_error(&duplicate) {
+        DeserializeError::UnsortedKeys { key: 1, offset: 9 } => (),
+        error => panic!("unexpected error {:?}", error),
+    }
+
+    // A run container without any run
+    let empty = [0x3b, 0x30, 0, 0, 1, 0, 0, 0, 0, 0, 0];
+    match deserialize_error(&empty) {
+        DeserializeError::BadCardinality { key: 0, offset: 9, expected: 1, actual: 0 } => (),
+        error => panic!("unexpected error {:?}", error),
+    }
+}
+
+#[test]
+fn test_native() {
+    // Small sets are written as a tag followed by the values
+    let original = (1..4).collect::<RoaringBitmap>();
+    let mut buffer = vec![];
+    original.serialize_native_into(&mut buffer).unwrap();
+    assert_eq!(buffer, [1, 3, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0]);
+    assert_eq!(RoaringBitmap::deserialize_native_from(&buffer[..]).unwrap(), original);
+
+    // Larger sets are written as a tag followed by the portable format
+    let original = test_data_bitmap_with_runs();
+    let mut buffer = vec![];
+    original.serialize_native_into(&mut buffer).unwrap();
+    assert_eq!(buffer.len(), original.native_serialized_size());
+    assert_eq!(buffer[0], 2);
+    assert_eq!(&buffer[1..], BITMAP_WITH_RUNS);
+    assert_eq!(RoaringBitmap::deserialize_native_from(&buffer[..]).unwrap(), original);
+    assert_eq!(RoaringBitmap::deserialize_native_unchecked_from(&buffer[..]).unwrap(), original);
+
+    // The values of the array variant don't have to be sorted
+    let unsorted = [1, 2, 0, 0, 0, 3, 0, 0, 0, 1, 0, 0, 0];
+    assert_eq!(
+        RoaringBitmap::deserialize_native_from(&unsorted[..]).unwrap(),
+        [1, 3].iter().collect()
+    );
+
+    match RoaringBitmap::deserialize_native_from(BITMAP_WITH_RUNS) {
+        Err(DeserializeError::UnknownCookie { cookie: 0x3b, offset: 0 }) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+    // The offsets of the portable format are from the start of the tag
+    match RoaringBitmap::deserialize_native_from(&buffer[..buffer.len() - 1]) {
+        Err(DeserializeError::Truncated { key: Some(_), offset }) => assert!(offset > 1),
+        result => panic!("unexpected result {:?}", result),
+    }
+    match RoaringBitmap::deserialize_native_from(&buffer[..3]) {
+        Err(DeserializeError::Truncated { key: None, offset: 1 }) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
 #[test]
 fn test_strange() {
     const ARRAY: &[u32] = &[
@@ -530,3 +744,109 @@ fn test_strange() {
     let new = serialize_and_deserialize(&original);
     assert_eq!(original, new);
 }
+
+#[test]
+fn test_limits() {
+    let original = test_data_bitmap_with_runs();
+    let bytes = BITMAP_WITH_RUNS;
+    let containers = original.iter().map(|value| value >> 16).collect::<BTreeSet<_>>().len() as u64;
+    let options = DeserializeOptions::new()
+        .max_containers(containers)
+        .max_bytes(bytes.len() as u64)
+        .max_cardinality(original.len());
+    assert_eq!(RoaringBitmap::deserialize_from_with_options(bytes, &options).unwrap(), original);
+
+    let limited = [
+        (options.max_containers(containers - 1), Limit::Containers),
+        (options.max_bytes(bytes.len() as u64 - 1), Limit::Bytes),
+        (options.max_cardinality(original.len() - 1), Limit::Cardinality),
+    ];
+    for (options, expected) in limited {
+        match RoaringBitmap::deserialize_from_with_options(bytes, &options) {
+            Err(DeserializeError::LimitExceeded { limit, .. }) => assert_eq!(limit, expected),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    // The header declares 65536 containers, the limit is exceeded before reading them
+    let header = [0x3a, 0x30, 0, 0, 0, 0, 1, 0];
+    let options = DeserializeOptions::new().max_containers(1024);
+    match RoaringBitmap::deserialize_from_with_options(&header[..], &options) {
+        Err(DeserializeError::LimitExceeded { limit: Limit::Containers, offset: 4 }) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+    let options = DeserializeOptions::new().max_bytes(1024);
+    match RoaringBitmap::deserialize_from_with_options(&header[..], &options) {
+        Err(DeserializeError::LimitExceeded { limit: Limit::Bytes, offset: 8 }) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn test_slices() {
+    let bitmaps = [RoaringBitmap::new(), test_data_bitmap(), test_data_bitmap_with_runs()];
+
+    let mut packed = vec![];
+    for bitmap in &bitmaps {
+        let bytes = bitmap.serialize_to_vec();
+        assert_eq!(bytes.len(), bitmap.serialized_size());
+        packed.extend(bytes);
+    }
+    assert_eq!(&packed[packed.len() - BITMAP_WITH_RUNS.len()..], BITMAP_WITH_RUNS);
+
+    let mut rest = &packed[..];
+    for bitmap in &bitmaps {
+        let (deserialized, read) = RoaringBitmap::deserialize_from_slice(rest).unwrap();
+        assert_eq!(&deserialized, bitmap);
+        assert_eq!(read, bitmap.serialized_size());
+        rest = &rest[read..];
+    }
+    assert!(rest.is_empty());
+
+    let bitmap = test_data_bitmap();
+    let size = bitmap.serialized_size();
+    let mut buffer = vec![0xff; size + 10];
+    assert_eq!(bitmap.serialize_into_slice(&mut buffer).unwrap(), size);
+    assert_eq!(&buffer[..size], BITMAP_WITHOUT_RUNS);
+    assert_eq!(buffer[size..], [0xff; 10]);
+
+    let mut buffer = vec![0xff; size - 1];
+    let error = bitmap.serialize_into_slice(&mut buffer).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::WriteZero);
+    assert!(buffer.iter().all(|&b| b == 0xff));
+
+    match RoaringBitmap::deserialize_from_slice(&BITMAP_WITHOUT_RUNS[..size - 1]) {
+        Err(DeserializeError::Truncated { .. }) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn test_deserialize_into() {
+    let mut with_runs = (0..100).chain(200_000..201_000).collect::<RoaringBitmap>();
+    with_runs.insert_range(500_000..600_000);
+    with_runs.run_optimize();
+    let bitmaps = [
+        test_data_bitmap(),
+        test_data_bitmap_with_runs(),
+        (0..2000).chain(100_000..106_000).collect::<RoaringBitmap>(),
+        RoaringBitmap::new(),
+        with_runs,
+    ];
+
+    let mut scratch = RoaringBitmap::new();
+    for bitmap in bitmaps.iter().chain(bitmaps.iter().rev()) {
+        let bytes = bitmap.serialize_to_vec();
+        scratch.deserialize_into(&bytes[..]).unwrap();
+        assert_eq!(&scratch, bitmap);
+    }
+
+    // The bitmap is emptied when the input is invalid
+    let bytes = test_data_bitmap().serialize_to_vec();
+    assert!(scratch.deserialize_into(&bytes[..bytes.len() - 1]).is_err());
+    assert!(scratch.is_empty());
+    let unsorted = [0x3b, 0x30, 1, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 5, 0, 5, 0];
+    scratch.deserialize_into(&bytes[..]).unwrap();
+    assert!(scratch.deserialize_into(&unsorted[..]).is_err());
+    assert!(scratch.is_empty());
+}
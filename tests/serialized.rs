@@ -0,0 +1,236 @@
+extern crate roaring;
+
+use roaring::bitmap::ContainerKind;
+use roaring::{
+    DeserializeError, RoaringBitmap, SerializedBitmap, SerializedBitmapInfo,
+    SerializedBitmapWriter, SerializedIter,
+};
+use std::io::{Cursor, Seek, SeekFrom, Write};
+
+// Test data from https://github.com/RoaringBitmap/RoaringFormatSpec/tree/master/testdata
+static BITMAP_WITHOUT_RUNS: &[u8] = include_bytes!("bitmapwithoutruns.bin");
+static BITMAP_WITH_RUNS: &[u8] = include_bytes!("bitmapwithruns.bin");
+
+/// A bitmap with an array, a bitmap and a run container.
+fn mixed_bitmap() -> RoaringBitmap {
+    let mut bitmap: RoaringBitmap =
+        (0..10).step_by(2).chain((65_536..75_536).step_by(2)).chain(196_608..200_000).collect();
+    bitmap.run_optimize();
+    bitmap
+}
+
+#[test]
+fn info() {
+    for bytes in [BITMAP_WITHOUT_RUNS, BITMAP_WITH_RUNS] {
+        let bitmap = RoaringBitmap::deserialize_from(bytes).unwrap();
+        let info = SerializedBitmapInfo::parse(bytes).unwrap();
+        assert_eq!(info.len(), bitmap.len());
+        assert_eq!(info.min(), bitmap.min());
+        assert_eq!(info.max(), bitmap.max());
+        assert_eq!(info.serialized_size(), bytes.len());
+        assert!(info.containers().iter().all(|container| !container.is_empty()));
+    }
+
+    // The size of the bitmap tells where the data that follows it starts
+    let bitmap = mixed_bitmap();
+    let mut bytes = bitmap.serialize_to_vec();
+    let size = bytes.len();
+    bytes.extend_from_slice(b"trailer");
+    let info = SerializedBitmapInfo::parse(&bytes).unwrap();
+    assert_eq!(info.serialized_size(), size);
+    assert_eq!(info.keys().collect::<Vec<_>>(), [0, 1, 3]);
+    let kinds: Vec<_> = info.containers().iter().map(|container| container.kind()).collect();
+    assert_eq!(kinds, [ContainerKind::Array, ContainerKind::Bitmap, ContainerKind::Run]);
+    assert!(info.may_intersect(196_608..196_609));
+    assert!(!info.may_intersect(131_072..196_608));
+}
+
+#[test]
+fn info_errors() {
+    let bytes = mixed_bitmap().serialize_to_vec();
+    match SerializedBitmapInfo::parse(&bytes[..bytes.len() - 1]) {
+        Err(DeserializeError::Truncated { key: Some(3), .. }) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+    match SerializedBitmapInfo::parse(&[1, 2, 3, 4]) {
+        Err(DeserializeError::UnknownCookie { cookie: 0x0403_0201, offset: 0 }) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn serialized_bitmap() {
+    // The test data have offsets, the mixed bitmap has runs and no offsets
+    let mixed = mixed_bitmap().serialize_to_vec();
+    for bytes in [BITMAP_WITHOUT_RUNS, BITMAP_WITH_RUNS, &mixed[..]] {
+        let bitmap = RoaringBitmap::deserialize_from(bytes).unwrap();
+        let serialized = SerializedBitmap::from_bytes(bytes).unwrap();
+        assert_eq!(serialized.len(), bitmap.len());
+        assert!(!serialized.is_empty());
+
+        let max = bitmap.max().unwrap();
+        for value in (0..=max + 1).step_by(997).chain(bitmap.iter().step_by(101)) {
+            assert_eq!(serialized.contains(value).unwrap(), bitmap.contains(value));
+            assert_eq!(serialized.rank(value).unwrap(), bitmap.rank(value));
+            let start = value / 2;
+            let expected = bitmap.rank(value) - start.checked_sub(1).map_or(0, |v| bitmap.rank(v));
+            assert_eq!(serialized.range_cardinality(start..=value).unwrap(), expected);
+        }
+        assert_eq!(serialized.range_cardinality(..).unwrap(), bitmap.len());
+    }
+}
+
+#[test]
+fn serialized_bitmap_errors() {
+    // Only the header is read to create it, the containers are read by the queries
+    let bytes = mixed_bitmap().serialize_to_vec();
+    let serialized = SerializedBitmap::from_bytes(&bytes[..bytes.len() - 1]).unwrap();
+    assert!(serialized.contains(2).unwrap());
+    match serialized.contains(196_608) {
+        Err(DeserializeError::Truncated { key: Some(3), .. }) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+    assert!(serialized.rank(196_608).is_err());
+
+    match SerializedBitmap::from_bytes(&[0x3a, 0x30, 0, 0, 1, 0]) {
+        Err(DeserializeError::Truncated { key: None, offset: 4 }) => (),
+        result => panic!("unexpected result {:?}", result.map(|serialized| serialized.len())),
+    }
+}
+
+#[test]
+fn serialized_writer() {
+    // Enough containers for their data to be moved in several chunks
+    let bitmap: RoaringBitmap = (0..10).chain((1 << 20..1 << 21).step_by(3)).collect();
+
+    // Written after some other data
+    let mut cursor = Cursor::new(Vec::new());
+    cursor.write_all(b"header").unwrap();
+    let mut writer = SerializedBitmapWriter::new(cursor).unwrap();
+    for value in &bitmap {
+        assert!(writer.push(value).unwrap());
+    }
+    assert!(!writer.push(5).unwrap());
+    assert_eq!(writer.len(), bitmap.len());
+    let mut cursor = writer.finish().unwrap();
+    cursor.write_all(b"trailer").unwrap();
+
+    let bytes = cursor.into_inner();
+    let expected = [&b"header"[..], &bitmap.serialize_to_vec(), b"trailer"].concat();
+    assert_eq!(bytes, expected);
+}
+
+#[test]
+fn serialized_writer_empty() {
+    let mut cursor = Cursor::new(vec![0xff; 4]);
+    cursor.seek(SeekFrom::Start(2)).unwrap();
+    let writer = SerializedBitmapWriter::new(cursor).unwrap();
+    assert!(writer.is_empty());
+    let bytes = writer.finish().unwrap().into_inner();
+
+    let empty = RoaringBitmap::new().serialize_to_vec();
+    assert_eq!(&bytes[..2], [0xff; 2]);
+    assert_eq!(&bytes[2..], &empty[..]);
+}
+
+#[test]
+fn serialized_iter() {
+    let mixed = mixed_bitmap().serialize_to_vec();
+    for bytes in [BITMAP_WITHOUT_RUNS, BITMAP_WITH_RUNS, &mixed[..]] {
+        let bitmap = RoaringBitmap::deserialize_from(bytes).unwrap();
+        let iter = SerializedIter::new(bytes).unwrap();
+        assert_eq!(iter.bitmap_len(), bitmap.len());
+        let values: Result<Vec<u32>, _> = iter.collect();
+        assert_eq!(values.unwrap(), bitmap.iter().collect::<Vec<_>>());
+
+        // The containers partition the bitmap
+        let mut iter = SerializedIter::new(bytes).unwrap();
+        let mut union = RoaringBitmap::new();
+        while let Some((key, container)) = iter.next_container().transpose().unwrap() {
+            assert_eq!(container.min().unwrap() >> 16, u32::from(key));
+            assert_eq!(container.max().unwrap() >> 16, u32::from(key));
+            union |= container;
+        }
+        assert_eq!(union, bitmap);
+    }
+
+    // The reader is left at the end of the bitmap
+    let mut bytes = mixed.clone();
+    bytes.extend_from_slice(b"trailer");
+    let mut reader = &bytes[..];
+    assert_eq!(SerializedIter::new(&mut reader).unwrap().count(), 5 + 5000 + 3392);
+    assert_eq!(reader, b"trailer");
+}
+
+#[test]
+fn serialized_iter_errors() {
+    // The values are yielded up to the truncated container
+    let bytes = mixed_bitmap().serialize_to_vec();
+    let mut iter = SerializedIter::new(&bytes[..bytes.len() - 1]).unwrap();
+    assert_eq!(iter.by_ref().take(5005).filter(Result::is_ok).count(), 5005);
+    match iter.next() {
+        Some(Err(DeserializeError::Truncated { key: Some(3), .. })) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+    assert!(iter.next().is_none());
+
+    // Two containers with the keys 1 and 0
+    let unsorted = [0x3b, 0x30, 1, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 5, 0, 5, 0];
+    let mut iter = SerializedIter::new(&unsorted[..]).unwrap();
+    assert_eq!(iter.next().unwrap().unwrap(), 65_541);
+    match iter.next() {
+        Some(Err(DeserializeError::UnsortedKeys { key: 0, offset: 9 })) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn serialized_merge() {
+    let bitmaps = [
+        RoaringBitmap::deserialize_from(BITMAP_WITHOUT_RUNS).unwrap(),
+        RoaringBitmap::deserialize_from(BITMAP_WITH_RUNS).unwrap(),
+        mixed_bitmap(),
+        (0..1000).chain(196_000..197_000).collect(),
+    ];
+    let serialized: Vec<Vec<u8>> = bitmaps.iter().map(RoaringBitmap::serialize_to_vec).collect();
+    let readers = || serialized.iter().map(|bytes| &bytes[..]);
+
+    let union = bitmaps.iter().fold(RoaringBitmap::new(), |union, bitmap| union | bitmap);
+    let output = RoaringBitmap::serialize_union_into(readers(), Cursor::new(Vec::new())).unwrap();
+    assert_eq!(RoaringBitmap::deserialize_from(&output.into_inner()[..]).unwrap(), union);
+
+    let (first, rest) = bitmaps.split_first().unwrap();
+    let intersection =
+        rest.iter().fold(first.clone(), |intersection, bitmap| intersection & bitmap);
+    assert!(!intersection.is_empty());
+    let writer = Cursor::new(Vec::new());
+    let output = RoaringBitmap::serialize_intersection_into(readers(), writer).unwrap();
+    assert_eq!(RoaringBitmap::deserialize_from(&output.into_inner()[..]).unwrap(), intersection);
+
+    // Without inputs
+    let empty = RoaringBitmap::new().serialize_to_vec();
+    let output = RoaringBitmap::serialize_union_into(None::<&[u8]>, Cursor::new(Vec::new()));
+    assert_eq!(output.unwrap().into_inner(), empty);
+    let writer = Cursor::new(Vec::new());
+    let output = RoaringBitmap::serialize_intersection_into(None::<&[u8]>, writer);
+    assert_eq!(output.unwrap().into_inner(), empty);
+}
+
+#[test]
+fn serialized_merge_errors() {
+    let bytes = mixed_bitmap().serialize_to_vec();
+    let readers = [&bytes[..], &bytes[..bytes.len() - 1]];
+    let error = RoaringBitmap::serialize_union_into(readers, Cursor::new(Vec::new())).unwrap_err();
+    match error.get_ref().and_then(|e| e.downcast_ref()) {
+        Some(DeserializeError::Truncated { key: Some(3), .. }) => (),
+        _ => panic!("unexpected error {:?}", error),
+    }
+
+    let readers = [&bytes[..], &[1, 2, 3, 4][..]];
+    let writer = Cursor::new(Vec::new());
+    let error = RoaringBitmap::serialize_intersection_into(readers, writer).unwrap_err();
+    match error.get_ref().and_then(|e| e.downcast_ref()) {
+        Some(DeserializeError::UnknownCookie { offset: 0, .. }) => (),
+        _ => panic!("unexpected error {:?}", error),
+    }
+}
@@ -1,4 +1,4 @@
-use roaring::RoaringTreemap;
+use roaring::{DeserializeError, DeserializeOptions, Limit, RoaringTreemap};
 use std::iter::FromIterator;
 
 fn serialize_deserialize<Dataset, I>(dataset: Dataset)
@@ -42,3 +42,264 @@ fn basic_3() {
             .chain(u32max..(u32max + 2 * (1 << 16))),
     )
 }
+
+#[test]
+fn run_optimized() {
+    let u32max = u32::MAX as u64;
+    let mut rb = RoaringTreemap::from_iter(
+        (0..100_000).chain(u32max..(u32max + 2 * (1 << 16))).chain((1 << 40)..(1 << 40) + 10),
+    );
+    let size = rb.serialized_size();
+    assert!(rb.run_optimize());
+    assert!(rb.serialized_size() < size);
+
+    let mut buffer = vec![];
+    rb.serialize_into(&mut buffer).unwrap();
+    assert_eq!(buffer.len(), rb.serialized_size());
+    assert_eq!(rb, RoaringTreemap::deserialize_from(&buffer[..]).unwrap());
+
+    assert!(rb.remove_run_compression());
+    assert_eq!(rb.serialized_size(), size);
+}
+
+#[test]
+fn java_legacy() {
+    // A Java `Roaring64NavigableMap` with signed longs of {1, -4294967291}, the negative key first
+    let bitmap = |value| [0x3a, 0x30, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 16, 0, 0, 0, value, 0];
+    let bytes =
+        [&[1, 0, 0, 0, 2, 0xff, 0xff, 0xff, 0xff][..], &bitmap(5), &[0; 4], &bitmap(1)].concat();
+    let expected = RoaringTreemap::from_iter([1, 0xFFFF_FFFF_0000_0005]);
+    assert_eq!(RoaringTreemap::deserialize_java_from(&bytes[..]).unwrap(), expected);
+
+    // Written with unsigned keys
+    let mut buffer = vec![];
+    expected.serialize_java_into(&mut buffer).unwrap();
+    assert_eq!(buffer.len(), expected.java_serialized_size());
+    let unsigned = [&[0, 0, 0, 0, 2, 0, 0, 0, 0][..], &bitmap(1), &[0xff; 4], &bitmap(5)].concat();
+    assert_eq!(buffer, unsigned);
+    assert_eq!(RoaringTreemap::deserialize_java_unchecked_from(&buffer[..]).unwrap(), expected);
+
+    match RoaringTreemap::deserialize_java_from(&buffer[..buffer.len() - 1]) {
+        Err(DeserializeError::Truncated { key: Some(0), offset: 47 }) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+    let mut native = vec![];
+    expected.serialize_into(&mut native).unwrap();
+    match RoaringTreemap::deserialize_java_from(&native[..]) {
+        Err(DeserializeError::UnknownCookie { cookie: 2, offset: 0 }) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+
+    // The order of the keys must follow the boolean
+    let mut swapped = bytes.clone();
+    swapped[0] = 0;
+    match RoaringTreemap::deserialize_java_from(&swapped[..]) {
+        Err(DeserializeError::UnsortedWideKeys { key: 0, offset: 27 }) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+    buffer[0] = 1;
+    match RoaringTreemap::deserialize_java_from(&buffer[..]) {
+        Err(DeserializeError::UnsortedWideKeys { key: 0xFFFF_FFFF, offset: 27 }) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+    assert_eq!(RoaringTreemap::deserialize_java_unchecked_from(&buffer[..]).unwrap(), expected);
+}
+
+#[test]
+fn java_legacy_invalid_bitmaps() {
+    let bitmap = |value| [0x3a, 0x30, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 16, 0, 0, 0, value, 0];
+
+    // The same key twice
+    let bytes = [&[0, 0, 0, 0, 2, 0, 0, 0, 7][..], &bitmap(1), &[0, 0, 0, 7], &bitmap(2)].concat();
+    match RoaringTreemap::deserialize_java_from(&bytes[..]) {
+        Err(DeserializeError::UnsortedWideKeys { key: 7, offset: 27 }) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+
+    // A bitmap without containers
+    let bytes = [0, 0, 0, 0, 1, 0, 0, 0, 7, 0x3a, 0x30, 0, 0, 0, 0, 0, 0];
+    match RoaringTreemap::deserialize_java_from(&bytes[..]) {
+        Err(DeserializeError::EmptyBitmap { key: 7, offset: 9 }) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+    assert!(RoaringTreemap::deserialize_java_unchecked_from(&bytes[..]).unwrap().is_empty());
+
+    // The containers of the bitmaps are checked, here their keys 1 and 0
+    let unsorted = [0x3b, 0x30, 1, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 5, 0, 5, 0];
+    let bytes = [&[0, 0, 0, 0, 1, 0, 0, 0, 7][..], &unsorted].concat();
+    match RoaringTreemap::deserialize_java_from(&bytes[..]) {
+        Err(DeserializeError::UnsortedKeys { key: 0, offset: 18 }) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+
+    // A negative number of bitmaps
+    let bytes = [0, 0xff, 0xff, 0xff, 0xff];
+    match RoaringTreemap::deserialize_java_from(&bytes[..]) {
+        Err(DeserializeError::TooManyContainers { count: 0xFFFF_FFFF, offset: 1 }) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn invalid_keys() {
+    let bitmap = |value| [0x3a, 0x30, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 16, 0, 0, 0, value, 0];
+    let bytes = |keys: [u8; 2]| {
+        [
+            &[2, 0, 0, 0, 0, 0, 0, 0, keys[0], 0, 0, 0][..],
+            &bitmap(1),
+            &[keys[1], 0, 0, 0],
+            &bitmap(2),
+        ]
+        .concat()
+    };
+    let error = |bytes: &[u8]| -> DeserializeError {
+        let error = RoaringTreemap::deserialize_from(bytes).unwrap_err();
+        *error.into_inner().unwrap().downcast().unwrap()
+    };
+    assert!(RoaringTreemap::deserialize_from(&bytes([0, 1])[..]).is_ok());
+    match error(&bytes([1, 0])) {
+        DeserializeError::UnsortedWideKeys { key: 0, offset: 30 } => (),
+        error => panic!("unexpected error {:?}", error),
+    }
+    match error(&bytes([1, 1])) {
+        DeserializeError::UnsortedWideKeys { key: 1, offset: 30 } => (),
+        error => panic!("unexpected error {:?}", error),
+    }
+    assert!(RoaringTreemap::deserialize_unchecked_from(&bytes([1, 0])[..]).is_ok());
+
+    // A bitmap without any container
+    let empty =
+        [&[1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0][..], &[0x3a, 0x30, 0, 0, 0, 0, 0, 0]].concat();
+    match error(&empty) {
+        DeserializeError::EmptyBitmap { key: 0, offset: 12 } => (),
+        error => panic!("unexpected error {:?}", error),
+    }
+
+    // The errors of a bitmap are located from the start of the treemap
+    match error(&bytes([0, 1])[..51]) {
+        DeserializeError::Truncated { key: Some(0), offset: 50 } => (),
+        error => panic!("unexpected error {:?}", error),
+    }
+}
+
+#[test]
+fn limits() {
+    let treemap = (0..10u64).map(|i| i << 32).chain(0..100_000).collect::<RoaringTreemap>();
+    let mut bytes = vec![];
+    treemap.serialize_into(&mut bytes).unwrap();
+
+    let options = DeserializeOptions::new()
+        .max_treemap_entries(10)
+        .max_bytes(bytes.len() as u64)
+        .max_cardinality(treemap.len());
+    assert_eq!(
+        RoaringTreemap::deserialize_from_with_options(&bytes[..], &options).unwrap(),
+        treemap
+    );
+
+    // The limits apply to all the bitmaps together
+    let limited = [
+        (options.max_treemap_entries(9), Limit::TreemapEntries),
+        (options.max_bytes(bytes.len() as u64 - 1), Limit::Bytes),
+        (options.max_cardinality(treemap.len() - 1), Limit::Cardinality),
+        (options.max_containers(10), Limit::Containers),
+    ];
+    for (options, expected) in limited {
+        match RoaringTreemap::deserialize_from_with_options(&bytes[..], &options) {
+            Err(DeserializeError::LimitExceeded { limit, .. }) => assert_eq!(limit, expected),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    // A huge number of bitmaps is rejected before reading them
+    let huge = [0xff; 8];
+    let options = DeserializeOptions::new().max_treemap_entries(1 << 20);
+    assert!(RoaringTreemap::deserialize_from_with_options(&huge[..], &options).is_err());
+}
+
+/// Appends an op of the Pilosa ops log, with the FNV-1a checksum of its bytes.
+fn push_pilosa_op(bytes: &mut Vec<u8>, kind: u8, value: u64, values: &[u64]) {
+    let start = bytes.len();
+    bytes.push(kind);
+    bytes.extend_from_slice(&value.to_le_bytes());
+    let mut op: Vec<u8> = bytes[start..].to_vec();
+    for value in values {
+        op.extend_from_slice(&value.to_le_bytes());
+    }
+    let checksum = op
+        .iter()
+        .fold(0x811c_9dc5u32, |hash, &byte| (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193));
+    bytes.extend_from_slice(&checksum.to_le_bytes());
+    for value in values {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+#[test]
+fn pilosa() {
+    let mut treemap: RoaringTreemap =
+        (0..10).chain((1 << 20..1 << 21).step_by(3)).chain((1 << 40)..(1 << 40) + 10_000).collect();
+    treemap.run_optimize();
+    let mut bytes = vec![];
+    treemap.serialize_pilosa_into(&mut bytes).unwrap();
+    assert_eq!(bytes.len(), treemap.pilosa_serialized_size());
+    assert_eq!(RoaringTreemap::deserialize_pilosa_from(&bytes[..]).unwrap(), treemap);
+
+    // The ops are applied in order
+    push_pilosa_op(&mut bytes, 0, 10, &[]);
+    push_pilosa_op(&mut bytes, 1, 5, &[]);
+    push_pilosa_op(&mut bytes, 2, 2, &[u64::MAX, 12]);
+    push_pilosa_op(&mut bytes, 3, 3, &[12, 1 << 40, 0]);
+    let mut expected = treemap.clone();
+    expected.extend([10, u64::MAX]);
+    expected.remove(0);
+    expected.remove(5);
+    expected.remove(1 << 40);
+    assert_eq!(RoaringTreemap::deserialize_pilosa_from(&bytes[..]).unwrap(), expected);
+
+    let empty = RoaringTreemap::new();
+    let mut bytes = vec![];
+    empty.serialize_pilosa_into(&mut bytes).unwrap();
+    assert_eq!(RoaringTreemap::deserialize_pilosa_from(&bytes[..]).unwrap(), empty);
+}
+
+#[test]
+fn pilosa_errors() {
+    let treemap: RoaringTreemap = (0..10).chain((1 << 40)..(1 << 40) + 10_000).collect();
+    let mut bytes = vec![];
+    treemap.serialize_pilosa_into(&mut bytes).unwrap();
+    let len = bytes.len() as u64;
+
+    match RoaringTreemap::deserialize_pilosa_from(&[0x3a, 0x30, 0, 0][..]) {
+        Err(DeserializeError::UnknownCookie { cookie: 0x303a, offset: 0 }) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+    match RoaringTreemap::deserialize_pilosa_from(&bytes[..bytes.len() - 1]) {
+        Err(DeserializeError::Truncated { key: Some(0), .. }) => (),
+        result => panic!("unexpected result {:?}", result),
+    }
+
+    let mut unknown = bytes.clone();
+    push_pilosa_op(&mut unknown, 0, 10, &[]);
+    push_pilosa_op(&mut unknown, 7, 10, &[]);
+    match RoaringTreemap::deserialize_pilosa_from(&unknown[..]) {
+        Err(DeserializeError::UnknownOp { op: 7, offset }) => assert_eq!(offset, len + 13),
+        result => panic!("unexpected result {:?}", result),
+    }
+
+    let mut corrupted = bytes.clone();
+    push_pilosa_op(&mut corrupted, 0, 10, &[]);
+    corrupted[len as usize + 1] ^= 1;
+    match RoaringTreemap::deserialize_pilosa_from(&corrupted[..]) {
+        Err(DeserializeError::ChecksumMismatch { offset, .. }) => assert_eq!(offset, len + 9),
+        result => panic!("unexpected result {:?}", result),
+    }
+
+    let mut truncated = bytes;
+    push_pilosa_op(&mut truncated, 2, 2, &[1, 2]);
+    truncated.pop();
+    match RoaringTreemap::deserialize_pilosa_from(&truncated[..]) {
+        Err(DeserializeError::Truncated { key: None, offset }) => assert_eq!(offset, len),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
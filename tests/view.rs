@@ -0,0 +1,168 @@
+extern crate roaring;
+
+use roaring::{DeserializeError, RoaringBitmap, RoaringBitmapView};
+
+// Test data from https://github.com/RoaringBitmap/RoaringFormatSpec/tree/master/testdata
+static BITMAP_WITHOUT_RUNS: &[u8] = include_bytes!("bitmapwithoutruns.bin");
+static BITMAP_WITH_RUNS: &[u8] = include_bytes!("bitmapwithruns.bin");
+
+fn test_data_bitmap() -> RoaringBitmap {
+    (0..100)
+        .map(|i| i * 1000)
+        .chain((100_000..200_000).map(|i| i * 3))
+        .chain(700_000..800_000)
+        .collect::<RoaringBitmap>()
+}
+
+fn serialize(bitmap: &RoaringBitmap) -> Vec<u8> {
+    let mut buffer = vec![];
+    bitmap.serialize_into(&mut buffer).unwrap();
+    buffer
+}
+
+#[test]
+fn provided_data() {
+    let expected = test_data_bitmap();
+    for bytes in [BITMAP_WITHOUT_RUNS, BITMAP_WITH_RUNS] {
+        let view = RoaringBitmapView::from_bytes(bytes).unwrap();
+        assert_eq!(view.len(), expected.len());
+        assert_eq!(view.min(), expected.min());
+        assert_eq!(view.max(), expected.max());
+        assert!(view.contains(3000));
+        assert!(!view.contains(3001));
+        assert!(view.contains(750_000));
+        assert_eq!(view.rank(750_000), expected.rank(750_000));
+        assert_eq!(view.select(100_000), expected.select(100_000));
+        assert_eq!(RoaringBitmap::from(&view), expected);
+    }
+}
+
+#[test]
+fn without_offsets() {
+    // Run containers are written without offsets below 4 containers
+    let mut bitmap = RoaringBitmap::new();
+    bitmap.insert_range(10..20);
+    bitmap.insert(70_000);
+    bitmap.insert_range(140_000..150_000);
+    assert!(bitmap.run_optimize());
+    let bytes = serialize(&bitmap);
+
+    let view = RoaringBitmapView::from_bytes(&bytes).unwrap();
+    assert_eq!(RoaringBitmap::from(&view), bitmap);
+    assert!(view.contains(15));
+    assert!(view.contains(70_000));
+    assert!(!view.contains(150_000));
+}
+
+#[test]
+fn unaligned() {
+    let bitmap = test_data_bitmap();
+    let mut bytes = vec![0];
+    bitmap.serialize_into(&mut bytes).unwrap();
+
+    let view = RoaringBitmapView::from_bytes(&bytes[1..]).unwrap();
+    assert_eq!(RoaringBitmap::from(&view), bitmap);
+}
+
+#[test]
+fn ops() {
+    let lhs = (0..2000).chain(100_000..150_000).collect::<RoaringBitmap>();
+    let rhs = test_data_bitmap();
+    let bytes = serialize(&rhs);
+    let view = RoaringBitmapView::from_bytes(&bytes).unwrap();
+
+    assert_eq!(&lhs | &view, &lhs | &rhs);
+    assert_eq!(&lhs & &view, &lhs & &rhs);
+    assert_eq!(&lhs - &view, &lhs - &rhs);
+    assert_eq!(&lhs ^ &view, &lhs ^ &rhs);
+    assert_eq!(&view | &lhs, &rhs | &lhs);
+    assert_eq!(&view & &lhs, &rhs & &lhs);
+    assert_eq!(&view - &lhs, &rhs - &lhs);
+    assert_eq!(&view ^ &lhs, &rhs ^ &lhs);
+
+    let mut assigned = lhs.clone();
+    assigned |= &view;
+    assigned -= &view;
+    assert_eq!(assigned, &lhs - &rhs);
+}
+
+#[test]
+fn truncated() {
+    let bytes = serialize(&test_data_bitmap());
+    for len in [0, 3, 8, 20, bytes.len() - 1] {
+        assert!(RoaringBitmapView::from_bytes(&bytes[..len]).is_err());
+        assert!(RoaringBitmapView::from_bytes_unchecked(&bytes[..len]).is_err());
+    }
+}
+
+#[test]
+fn invalid() {
+    // An array container with cardinality 2 whose values are not sorted
+    let bytes = [0x3a, 0x30, 0, 0, 1, 0, 0, 0, 0, 0, 1, 0, 16, 0, 0, 0, 2, 0, 1, 0];
+    match RoaringBitmapView::from_bytes(&bytes) {
+        Err(DeserializeError::InvalidContainer { key: 0, offset: 16, .. }) => (),
+        result => panic!("unexpected result {:?}", result.map(|view| view.len())),
+    }
+    assert!(RoaringBitmapView::from_bytes_unchecked(&bytes).is_ok());
+}
+
+#[test]
+fn debug_without_values() {
+    // A run container declaring 16 values but holding no run
+    let bytes = [0x3b, 0x30, 0, 0, 1, 0, 0, 15, 0, 0, 0];
+    let view = RoaringBitmapView::from_bytes_unchecked(&bytes).unwrap();
+    assert_eq!(format!("{:?}", view), "RoaringBitmapView<16 values between None and None>");
+}
+
+#[test]
+fn unsorted_keys() {
+    // Two containers with keys 1 and 0
+    let bytes = [0x3b, 0x30, 1, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 5, 0, 5, 0];
+    match RoaringBitmapView::from_bytes(&bytes) {
+        Err(DeserializeError::UnsortedKeys { key: 0, offset: 9 }) => (),
+        result => panic!("unexpected result {:?}", result.map(|view| view.len())),
+    }
+    assert!(RoaringBitmapView::from_bytes_unchecked(&bytes).is_ok());
+
+    // The same keys in the frozen format
+    let frozen = [5, 0, 5, 0, 1, 0, 0, 0, 0, 0, 0, 0, 2, 2, 0xc6, 0x35, 1, 0];
+    match RoaringBitmapView::from_frozen_bytes(&frozen) {
+        Err(DeserializeError::UnsortedKeys { key: 0, offset: 6 }) => (),
+        result => panic!("unexpected result {:?}", result.map(|view| view.len())),
+    }
+    assert!(RoaringBitmapView::from_frozen_bytes_unchecked(&frozen).is_ok());
+}
+
+#[test]
+fn frozen() {
+    // The layout written by CRoaring's `roaring_bitmap_frozen_serialize` for {1, 2, 3}
+    let bytes = [1, 0, 2, 0, 3, 0, 0, 0, 2, 0, 2, 0xc6, 0xb5, 0, 0];
+    let bitmap = (1..4).collect::<RoaringBitmap>();
+    let mut frozen = vec![];
+    bitmap.serialize_frozen_into(&mut frozen).unwrap();
+    assert_eq!(frozen, bytes);
+
+    let mut bitmap = test_data_bitmap();
+    bitmap.insert_range(1_000_000..1_001_000);
+    bitmap.run_optimize();
+    let mut bytes = vec![0];
+    bitmap.serialize_frozen_into(&mut bytes).unwrap();
+    let view = RoaringBitmapView::from_frozen_bytes(&bytes[1..]).unwrap();
+    assert_eq!(view.len(), bitmap.len());
+    assert!(view.contains(1_000_500));
+    assert!(!view.contains(1_001_000));
+    assert_eq!(RoaringBitmap::from(&view), bitmap);
+
+    for len in [0, 3, 8, bytes.len() - 2] {
+        assert!(RoaringBitmapView::from_frozen_bytes(&bytes[1..][..len]).is_err());
+    }
+    // A bitmap container holding a single value must be an array container
+    let mut bitmap_container = vec![0; 8192];
+    bitmap_container[0] = 1;
+    bitmap_container.extend([0, 0, 0, 0, 1, 0xc6, 0xb5, 0, 0]);
+    assert!(RoaringBitmapView::from_frozen_bytes(&bitmap_container).is_err());
+    assert!(RoaringBitmapView::from_frozen_bytes_unchecked(&bitmap_container).is_ok());
+
+    // The portable format is not mistaken for the frozen one
+    assert!(RoaringBitmapView::from_frozen_bytes(&serialize(&bitmap)).is_err());
+}